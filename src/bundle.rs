@@ -0,0 +1,121 @@
+use crate::fs::{list_files, FileEntry};
+use crate::types::{Journal, PlannedChange};
+use anyhow::{bail, Result};
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::{Component, Path};
+
+/// Packs the workspace directories into an archive encrypted with `key`,
+/// written to `filepath`. Files are stored as they are, so journals that
+/// were encrypted are still encrypted with their own key inside the bundle.
+/// Returns the paths of the bundled files, i.e. {workspace}/{file}.
+pub fn create(filepath: &FileEntry, workspaces: &[FileEntry], key: &String) -> Result<Vec<String>> {
+    let mut buf = Cursor::new(Vec::new());
+    let mut zip = zip::ZipWriter::new(&mut buf);
+    let options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    let mut bundled = Vec::new();
+    for dir in workspaces {
+        let workspace = dir.filename();
+        zip.add_directory(&workspace, options)?;
+
+        // All files, including the index and metadata of the workspace
+        for entry in list_files(dir.path())? {
+            let name = format!("{}/{}", workspace, entry.filename());
+            zip.start_file(&name, options)?;
+            zip.write_all(&entry.read_bytes()?)?;
+            bundled.push(name);
+        }
+    }
+
+    zip.finish()?;
+    drop(zip);
+
+    // The authenticated encryption also protects the integrity of the bundle
+    Journal::save(filepath, Some(key), buf.get_ref())?;
+    Ok(bundled)
+}
+
+/// Unpacks the bundle at `filepath` into `workspaces_dir`.
+/// Existing files are only replaced if `force` is set, and otherwise skipped.
+/// Returns the paths of the imported and skipped files.
+pub fn apply(
+    filepath: &FileEntry,
+    workspaces_dir: &FileEntry,
+    key: &str,
+    force: bool,
+    dry_run: bool,
+) -> Result<Vec<PlannedChange>> {
+    if !filepath.exists() {
+        bail!("no bundle at {}", filepath.path().display());
+    }
+
+    let journal = Journal::open(filepath, Some(key.to_string()))?;
+    journal.require_encrypted()?;
+    let bytes = journal.bytes()?;
+
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+    let mut changes = Vec::new();
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if file.is_dir() {
+            continue;
+        }
+
+        let name = file.name().to_string();
+        if !is_safe_path(&name) {
+            bail!("bundle contains an invalid path: {}", name);
+        }
+
+        let target = workspaces_dir.path().join(&name);
+        if target.exists() && !force {
+            changes.push(PlannedChange::Skip { path: name });
+            continue;
+        }
+
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+        let replaced = match fs::metadata(&target) {
+            Ok(metadata) => Some(metadata.len()),
+            Err(_) => None,
+        };
+        if !dry_run {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&target, &content)?;
+        }
+        changes.push(PlannedChange::Write {
+            path: name,
+            bytes: content.len() as u64,
+            replaced,
+        });
+    }
+
+    Ok(changes)
+}
+
+/// Bundled files must be exactly {workspace}/{file}, so that
+/// a bundle can't write outside of the workspaces directory.
+fn is_safe_path(name: &str) -> bool {
+    let components: Vec<Component> = Path::new(name).components().collect();
+    components.len() == 2 && components.iter().all(|c| matches!(c, Component::Normal(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_safe_path;
+
+    #[test]
+    fn bundled_paths() {
+        assert!(is_safe_path("work/notes.md"));
+        assert!(is_safe_path("private/.index"));
+        assert!(!is_safe_path("notes.md"));
+        assert!(!is_safe_path("../notes.md"));
+        assert!(!is_safe_path("work/../../notes.md"));
+        assert!(!is_safe_path("/etc/passwd"));
+        assert!(!is_safe_path("work/sub/notes.md"));
+    }
+}
@@ -0,0 +1,47 @@
+use anyhow::Result;
+use signal_hook::consts::SIGINT;
+use signal_hook::flag;
+use signal_hook::SigId;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+/// Exit status after being interrupted by Ctrl-C, as shells use for SIGINT.
+pub const INTERRUPTED: i32 = 130;
+
+static REQUESTED: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+/// While alive, Ctrl-C requests long running operations, like exports and
+/// searches, to stop between journals, instead of exiting in the middle of
+/// writing one. They check `requested` to stop and clean up after themselves.
+/// A second Ctrl-C exits right away.
+pub struct Cancellation {
+    handlers: Vec<SigId>,
+}
+
+/// Handles Ctrl-C until the returned `Cancellation` is dropped.
+pub fn install() -> Result<Cancellation> {
+    let requested = REQUESTED.get_or_init(Default::default);
+    requested.store(false, Ordering::SeqCst);
+
+    // Exits if already requested, so it's registered before the flag is set
+    let handlers = vec![
+        flag::register_conditional_shutdown(SIGINT, INTERRUPTED, Arc::clone(requested))?,
+        flag::register(SIGINT, Arc::clone(requested))?,
+    ];
+    Ok(Cancellation { handlers })
+}
+
+/// Whether Ctrl-C was pressed since `install` was last called.
+pub fn requested() -> bool {
+    REQUESTED
+        .get()
+        .is_some_and(|requested| requested.load(Ordering::SeqCst))
+}
+
+impl Drop for Cancellation {
+    fn drop(&mut self) {
+        for id in self.handlers.drain(..) {
+            signal_hook::low_level::unregister(id);
+        }
+    }
+}
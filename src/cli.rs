@@ -1,11 +1,34 @@
-use crate::validate::valid_workspace_name;
+use crate::validate::{
+    valid_export_target, valid_journal_path, valid_mood, valid_since, valid_workspace_name,
+    valid_workspace_pattern,
+};
+use chrono::NaiveDate;
 use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Command,
+    /// Only output errors, e.g. for scripts and scheduled exports.
+    /// Search then only signals matches by its exit status.
+    #[arg(long, short, global = true)]
+    pub quiet: bool,
+    /// Include hidden journals, whose names start with a dot.
+    #[arg(long, global = true)]
+    pub hidden: bool,
+    /// Show how long walking directories, reading, decrypting,
+    /// searching and uploading took, e.g. on network filesystems.
+    #[arg(long, global = true)]
+    pub profile_perf: bool,
+    /// Allow encrypting journals with very weak keys, such as common passwords.
+    #[arg(long, global = true)]
+    pub allow_weak_key: bool,
+    /// Use this configuration file instead of the one in the config directory,
+    /// e.g. for testing or separate setups.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -21,6 +44,10 @@ pub enum Command {
     Create(CreateArgs),
     /// Prints a journal to stdout.
     Print(OpenArgs),
+    /// Opens a journal like `open --create`, or with `-` saves stdin as the
+    /// journal and writes it to stdout, without an editor.
+    #[command()]
+    Edit(EditArgs),
     /// Lists journals.
     #[command(visible_alias = "ls")]
     List(ListArgs),
@@ -30,42 +57,187 @@ pub enum Command {
     /// Renames a journal or workspace.
     #[command(visible_alias = "mv")]
     Rename(RenameArgs),
+    /// Reverts the last remove, rename or replace.
+    #[command()]
+    Undo(UndoArgs),
+    /// Manage workspaces.
+    #[command(visible_alias = "ws")]
+    Workspace(WorkspaceArgs),
     /// Search in your journals.
     /// Searches across all workspaces by default.
     #[command()]
     Search(SearchArgs),
+    /// Replaces the matches of a pattern in journals.
+    /// Replaces across all workspaces by default.
+    #[command()]
+    Replace(ReplaceArgs),
+    /// Shows the differences between two journals,
+    /// or between a journal and a backup of it.
+    #[command()]
+    Diff(DiffArgs),
+    /// Splits a markdown journal into one journal per heading.
+    #[command()]
+    Split(SplitArgs),
     /// Export journals.
     #[command()]
     Export(ExportArgs),
+    /// Prints SHA-256 digests of journals in the format of `sha256sum`,
+    /// with paths relative to the workspaces directory.
+    /// Check them with `sha256sum -c` in that directory or in a backup of it.
+    #[command()]
+    Checksum(ChecksumArgs),
+    /// Finds journals with the same or similar content across workspaces,
+    /// and optionally removes or merges them.
+    #[command()]
+    Dedupe(DedupeArgs),
+    /// Lists conflicting copies of journals made by sync tools, such as
+    /// `notes (conflicted copy).md`, and optionally resolves them.
+    #[command()]
+    Conflicts(ConflictsArgs),
+    /// Create or apply encrypted bundles of workspaces, for moving
+    /// journals between machines.
+    #[command()]
+    Bundle(BundleArgs),
+    /// Encrypts a journal to a random passphrase, or an age recipient,
+    /// in a file that can be handed to someone else. Use `share open` to read it.
+    #[command()]
+    Share(ShareArgs),
+    /// Generates a strong random key for encrypting journals, and optionally
+    /// stores it in a key file or the OS keyring.
+    #[command()]
+    Keygen(KeygenArgs),
+    /// Watches workspaces and re-encrypts journals saved as plaintext
+    /// by other programs.
+    #[command()]
+    Watch(WatchArgs),
+    /// Records your mood in the front matter of today's journal, {DATE}.md,
+    /// and adds a message to it.
+    #[command()]
+    Log(LogArgs),
+    /// Adds text to the end of a journal, formatted by `append-template`.
+    #[command()]
+    Append(AppendArgs),
+    /// Shows the mood over time, as recorded in the front matter of journals.
+    #[command()]
+    Mood(MoodArgs),
+    /// Lists open to-dos: unchecked Markdown tasks, lines with TODO:,
+    /// and org headings with TODO, NEXT or WAITING.
+    #[command()]
+    Todo(TodoArgs),
+    /// Checks when journals were last written and exported.
+    /// Exits with status 1 if any reminder is due, suitable for cron or timers.
+    #[command()]
+    Remind(RemindArgs),
+    /// Lists journals that haven't been opened in a while, to resurface old notes.
+    /// Journals never opened count from when they were last modified.
+    /// Use `review generate` to create a review of the last week.
+    #[command()]
+    Review(ReviewArgs),
+    /// Opens a random journal, to reread an old entry.
+    #[command()]
+    Random(RandomArgs),
+    /// Prints the journals from this day in previous years, or months,
+    /// as dated by their front matter or a date at the start of their name.
+    #[command(name = "onthisday")]
+    OnThisDay(OnThisDayArgs),
+    /// Prints the lines, words and characters of journals, like `wc`.
+    #[command()]
+    Wc(WcArgs),
+    /// Shows the number of journals and words, and the words written
+    /// today towards `word-goal`.
+    #[command()]
+    Stats(StatsArgs),
+    /// Browse journals in a full-screen terminal UI.
+    #[command()]
+    Tui(TuiArgs),
+    /// Serves a JSON API for other applications on localhost.
+    #[command()]
+    Serve(ServeArgs),
+    /// Manage the configuration.
+    #[command()]
+    Config(ConfigArgs),
 }
 
 #[derive(Args)]
 pub struct OpenArgs {
-    /// Name of the journal to open. Can be part of the name.
+    /// Name of the journal to open. Can be part of the name,
+    /// matched case-insensitively.
     /// Opens first match or if there's multiple matches it
     /// queries the user for a match.
-    #[arg()]
-    pub name: String,
+    #[arg(required_unless_present = "index")]
+    pub name: Option<String>,
+    /// Open the journal at this position in the last listing
+    /// of the workspace, as shown by `jn list`.
+    #[arg(long, short = 'i', conflicts_with = "name")]
+    pub index: Option<usize>,
     /// Optional workspace to use, else use the default workspace.
     #[arg(long, short = 'w', value_parser = valid_workspace_name)]
     pub workspace: Option<String>,
+    /// Search the journals of all workspaces, picking among the matches,
+    /// shown as {workspace}/{journal}, if there's more than one.
+    #[arg(long, short = 'A', conflicts_with_all = ["workspace", "index", "create"])]
+    pub any_workspace: bool,
     /// Use as key for decryption. NOTE: when supplying a key
     /// on a journal which is not prior encrypted it will be encrypted
     /// after specifying a key.
     #[arg(long, short = 'k')]
     pub key: Option<String>,
+    /// Require the name to match the full filename.
+    #[arg(long, conflicts_with = "fuzzy")]
+    pub exact: bool,
+    /// Match the name fuzzily, ranking the candidates by score.
+    /// Can be enabled by default with `fuzzy-match` in the config.
+    #[arg(long)]
+    pub fuzzy: bool,
+    /// Print encrypted journals with a tag in `sensitive-tags`
+    /// without confirming.
+    #[arg(long)]
+    pub reveal: bool,
+    /// Create the journal from its template if no journal matches the name,
+    /// instead of failing. Can be enabled by default with `create-missing`.
+    #[arg(long, conflicts_with = "index")]
+    pub create: bool,
 }
 
 #[derive(Args)]
-pub struct CreateArgs {
-    /// Name of the journal to create.
-    #[arg()]
+pub struct EditArgs {
+    /// Name of the journal to edit, matched as when opening, or exactly with `-`.
+    /// It's created if no journal matches.
     pub name: String,
+    /// `-` to read the content of the journal from stdin instead of opening
+    /// the editor, e.g. for editor plugins that keep the buffer themselves.
+    #[arg(value_parser = ["-"])]
+    pub input: Option<String>,
+    /// Optional workspace to use, else use the default workspace.
+    #[arg(long, short = 'w', value_parser = valid_workspace_name)]
+    pub workspace: Option<String>,
+    /// Use as key for decryption, and for encrypting the journal if it's created.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Args)]
+pub struct CreateArgs {
+    /// Name of the journal to create, which may be in a subdirectory
+    /// of the workspace, e.g. `projects/alpha/notes.md`.
+    #[arg(required_unless_present = "pattern", value_parser = valid_journal_path)]
+    pub name: Option<String>,
+    /// Create a journal for every date from --from to --to, named by this
+    /// pattern with {date} replaced by the date, e.g. '{date}.md'.
+    /// Existing journals are skipped.
+    #[arg(long, conflicts_with = "name", requires_all = ["from", "to"])]
+    pub pattern: Option<String>,
+    /// First date of the journals to create, e.g. 2024-07-01.
+    #[arg(long, requires = "pattern")]
+    pub from: Option<NaiveDate>,
+    /// Last date of the journals to create, e.g. 2024-07-31.
+    #[arg(long, requires = "pattern")]
+    pub to: Option<NaiveDate>,
     /// Optional workspace to use, else use the default workspace.
     #[arg(long, short = 'w', value_parser = valid_workspace_name)]
     pub workspace: Option<String>,
     /// Encrypt the journal using this key.
-    /// The key have length 8 <= key <= 32;
+    /// The key must be at least 8 characters long.
     #[arg(long, short = 'k')]
     pub key: Option<String>,
 }
@@ -81,6 +253,12 @@ pub struct RemoveArgs {
     /// Remove `name` as a workspace instead of a journal.
     #[arg(long, conflicts_with = "workspace")]
     pub remove_workspace: bool,
+    /// Only show what would be removed.
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Key used to read the encrypted filenames of the workspace.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
 }
 
 #[derive(Args)]
@@ -88,7 +266,9 @@ pub struct RenameArgs {
     /// The name of the journal to rename.
     #[arg()]
     pub old: String,
-    #[arg()]
+    /// The new name, which may move it to a subdirectory of the
+    /// workspace, e.g. `projects/alpha/notes.md`.
+    #[arg(value_parser = valid_journal_path)]
     pub new: String,
     /// Optional workspace to use, else use the default workspace.
     #[arg(long, short = 'w', value_parser = valid_workspace_name)]
@@ -96,6 +276,22 @@ pub struct RenameArgs {
     /// Rename `name` as a workspace instead of a journal.
     #[arg(long, conflicts_with = "workspace")]
     pub rename_workspace: bool,
+    /// Rewrite `[[wiki-links]]` to the journal in the journals of the workspace.
+    #[arg(long, conflicts_with = "rename_workspace")]
+    pub update_links: bool,
+    /// Only show what would be renamed, and the journals whose links would be updated.
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Key used to read the encrypted filenames of the workspace.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Args)]
+pub struct UndoArgs {
+    /// Key used to read the encrypted filenames of the workspace.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
 }
 
 #[derive(Args)]
@@ -103,9 +299,276 @@ pub struct ListArgs {
     /// List all journals across all workspaces.
     #[arg(long, short = 'a')]
     pub all: bool,
+    /// List the journals of all workspaces in every root, as configured by `[roots]`.
+    #[arg(long, conflicts_with_all = ["all", "workspace"])]
+    pub all_roots: bool,
+    /// Only list encrypted journals.
+    #[arg(long, conflicts_with = "plaintext_only")]
+    pub encrypted_only: bool,
+    /// Only list journals that aren't encrypted.
+    #[arg(long)]
+    pub plaintext_only: bool,
+    /// Output format. CSV and TSV output has one row per journal with its
+    /// workspace, name, size, modification time, encryption, words and tags.
+    #[arg(long, default_value = "text", value_parser = ["text", "csv", "tsv"])]
+    pub format: String,
+    /// Sort the journals by name, or by when they were last opened
+    /// with the least recently opened first.
+    #[arg(long, value_parser = ["name", "opened"])]
+    pub sort: Option<String>,
+    /// Only print the number of journals in each workspace, and in total.
+    #[arg(long, conflicts_with_all = ["format", "sort"])]
+    pub count: bool,
+    /// Print the number of journals and their total size for each
+    /// workspace, and in total.
+    #[arg(long, conflicts_with_all = ["format", "sort", "count"])]
+    pub summary: bool,
+    /// Print the number of journals and their total size by workspace,
+    /// extension or month, instead of listing them.
+    #[arg(long, value_parser = ["workspace", "extension", "month"], conflicts_with_all = ["format", "sort", "count", "summary"])]
+    pub group_by: Option<String>,
+    /// Workspaces to list, else use the default workspace.
+    /// Can be repeated and be a glob pattern, e.g. 'client-*'.
+    #[arg(long, short = 'w', value_parser = valid_workspace_pattern)]
+    pub workspace: Vec<String>,
+    /// Key used to read the encrypted filenames of workspaces.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Args)]
+pub struct WorkspaceArgs {
+    #[command(subcommand)]
+    pub command: WorkspaceCommand,
+}
+
+#[derive(Subcommand)]
+pub enum WorkspaceCommand {
+    /// Lists workspaces with their descriptions.
+    #[command(visible_alias = "ls")]
+    List,
+    /// Creates a workspace, regardless of `create-workspaces`.
+    Create(WorkspaceCreateArgs),
+    /// Sets the description, icon or color of a workspace.
+    Describe(DescribeArgs),
+    /// Reports how many journals are encrypted, and which keys decrypt them.
+    Status(WorkspaceStatusArgs),
+}
+
+#[derive(Args)]
+pub struct WorkspaceCreateArgs {
+    /// Name of the workspace.
+    #[arg(value_parser = valid_workspace_name)]
+    pub name: String,
+}
+
+#[derive(Args)]
+pub struct WorkspaceStatusArgs {
+    /// Workspaces to check, else check all workspaces.
+    /// Can be repeated and be a glob pattern, e.g. 'client-*'.
+    #[arg(long, short = 'w', value_parser = valid_workspace_pattern)]
+    pub workspace: Vec<String>,
+    /// Keys to try on the encrypted journals. Can be repeated.
+    #[arg(long, short = 'k')]
+    pub key: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct DescribeArgs {
+    /// Name of the workspace.
+    #[arg(value_parser = valid_workspace_name)]
+    pub name: String,
+    /// Description of the workspace.
+    pub description: Option<String>,
+    /// Icon shown before the workspace name, e.g. an emoji.
+    #[arg(long)]
+    pub icon: Option<String>,
+    /// Color of the workspace name, e.g. blue or dark_green.
+    #[arg(long)]
+    pub color: Option<String>,
+}
+
+#[derive(Args)]
+pub struct ChecksumArgs {
+    /// Workspaces to use, else use all workspaces.
+    /// Can be repeated and be a glob pattern, e.g. 'client-*'.
+    #[arg(long, short = 'w', value_parser = valid_workspace_pattern)]
+    pub workspace: Vec<String>,
+    /// Digest the decrypted content of encrypted journals instead of the files.
+    #[arg(long)]
+    pub plaintext: bool,
+    /// Use as key for decryption, and for reading encrypted filenames.
+    /// If this is omitted encrypted files will be skipped with --plaintext.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Args)]
+pub struct DedupeArgs {
+    /// Workspaces to use, else use all workspaces.
+    /// Can be repeated and be a glob pattern, e.g. 'client-*'.
+    #[arg(long, short = 'w', value_parser = valid_workspace_pattern)]
+    pub workspace: Vec<String>,
+    /// Share of lines that near duplicates have in common, from 0 to 1.
+    #[arg(long, default_value_t = 0.8)]
+    pub threshold: f64,
+    /// Go through the duplicates and choose which to keep, remove or merge.
+    #[arg(long, short = 'i')]
+    pub interactive: bool,
+    /// Use as key for decryption, and for reading encrypted filenames.
+    /// If this is omitted encrypted journals will be skipped.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Args)]
+pub struct ConflictsArgs {
+    /// Workspaces to use, else use all workspaces.
+    /// Can be repeated and be a glob pattern, e.g. 'client-*'.
+    #[arg(long, short = 'w', value_parser = valid_workspace_pattern)]
+    pub workspace: Vec<String>,
+    /// Go through the conflicts, showing how each copy differs, and choose
+    /// which to keep or to merge them.
+    #[arg(long, short = 'i')]
+    pub interactive: bool,
+    /// Use as key for decryption, and for reading encrypted filenames.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Args)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommand,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Loads the configuration again in the REPL, without waiting for it to
+    /// notice that the file changed. Other commands always load it.
+    Reload,
+    /// Stores a credential, such as `sas-token`, so it isn't kept in plaintext
+    /// in the config. Prints the value to use in the config instead.
+    Secret(SecretArgs),
+    /// Packs the configuration, with its templates, aliases and appearance,
+    /// and the prompts file into an archive, e.g. to set up another machine.
+    Export(ConfigExportArgs),
+    /// Unpacks an archive made by `jn config export` as the configuration.
+    Import(ConfigImportArgs),
+}
+
+#[derive(Args)]
+pub struct ConfigExportArgs {
+    /// Path of the archive to create.
+    pub file: String,
+    /// Also include credentials, such as `sas-token` and `api-token`.
+    #[arg(long)]
+    pub include_secrets: bool,
+}
+
+#[derive(Args)]
+pub struct ConfigImportArgs {
+    /// Path of the archive to import.
+    pub file: String,
+    /// Replace the existing configuration.
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args)]
+pub struct SecretArgs {
+    /// Store it in the OS keyring by service and account, e.g. `journal/azure`,
+    /// instead of encrypting it with the key.
+    #[arg(long, value_name = "SERVICE/ACCOUNT")]
+    pub keyring: Option<String>,
+    /// Key to encrypt the secret with, the one given when exporting.
+    #[arg(long, short = 'k', conflicts_with = "keyring")]
+    pub key: Option<String>,
+}
+
+#[derive(Args)]
+pub struct BundleArgs {
+    #[command(subcommand)]
+    pub command: BundleCommand,
+}
+
+#[derive(Subcommand)]
+pub enum BundleCommand {
+    /// Packs workspaces into a single encrypted file.
+    /// Encrypted journals stay encrypted with their own key in the bundle.
+    Create(BundleCreateArgs),
+    /// Imports the workspaces of a bundle.
+    Apply(BundleApplyArgs),
+}
+
+#[derive(Args)]
+pub struct BundleCreateArgs {
+    /// Path of the bundle to create.
+    pub file: String,
+    /// Workspaces to bundle, else bundle all workspaces.
+    /// Can be repeated and be a glob pattern, e.g. 'client-*'.
+    #[arg(long, short = 'w', value_parser = valid_workspace_pattern)]
+    pub workspace: Vec<String>,
+    /// Key used to encrypt the bundle.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Args)]
+pub struct BundleApplyArgs {
+    /// Path of the bundle to import.
+    pub file: String,
+    /// Key used to decrypt the bundle.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+    /// Replace existing files, else they are skipped.
+    #[arg(long)]
+    pub force: bool,
+    /// Only show the files that would be written and skipped.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct ShareArgs {
+    #[command(subcommand)]
+    pub command: Option<ShareCommand>,
+    /// Name of the journal to share.
+    #[arg(required = true)]
+    pub name: Option<String>,
     /// Optional workspace to use, else use the default workspace.
     #[arg(long, short = 'w', value_parser = valid_workspace_name)]
     pub workspace: Option<String>,
+    /// Path of the file to write, defaults to the name of the journal with
+    /// the `.jnshare` extension in the current directory.
+    #[arg(long, short = 'o')]
+    pub output: Option<String>,
+    /// Encrypt to this age recipient, e.g. `age1...`, instead of a passphrase.
+    /// Requires the age command.
+    #[arg(long, short = 'r')]
+    pub recipient: Option<String>,
+    /// Use as key for decryption.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Subcommand)]
+pub enum ShareCommand {
+    /// Prints a shared journal.
+    Open(ShareOpenArgs),
+}
+
+#[derive(Args)]
+pub struct ShareOpenArgs {
+    /// Path of the shared journal.
+    pub file: String,
+    /// The passphrase printed when it was shared, else it's asked for.
+    #[arg(long, short = 'p')]
+    pub passphrase: Option<String>,
+    /// Age identity file, for journals shared with an age recipient.
+    #[arg(long, short = 'i')]
+    pub identity: Option<String>,
 }
 
 #[derive(Args)]
@@ -115,26 +578,432 @@ pub struct SearchArgs {
     /// Ignore case when searching.
     #[arg(long, short = 'i')]
     pub case_insensitive: bool,
-    /// Optional workspace to use, else search across all workspaces.
+    /// Workspaces to search, else search across all workspaces,
+    /// or only the default workspace if `search-scope` says so.
+    /// Can be repeated and be a glob pattern, e.g. 'client-*'.
+    #[arg(long, short = 'w', value_parser = valid_workspace_pattern)]
+    pub workspace: Vec<String>,
+    /// Search all workspaces, whatever `search-scope` is set to.
+    #[arg(long, conflicts_with = "workspace")]
+    pub all: bool,
+    /// Search all workspaces in every root, as configured by `[roots]`.
+    #[arg(long, conflicts_with_all = ["all", "workspace"])]
+    pub all_roots: bool,
+    /// Only search this journal, in the given workspace or the default one.
+    #[arg(long)]
+    pub name: Option<String>,
+    /// Use as key for decryption.
+    /// If this is omitted encrypted files will be skipped.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+    /// Treat the pattern as a literal string instead of a regular expression.
+    #[arg(long, short = 'F')]
+    pub fixed_strings: bool,
+    /// Only match whole words, i.e. the pattern surrounded by word boundaries.
+    #[arg(long)]
+    pub word: bool,
+    /// Match patterns across line boundaries, letting `.` match newlines.
+    /// Matches are reported by their starting line.
+    #[arg(long)]
+    pub multiline: bool,
+    /// Show at most this many matching lines in total.
+    #[arg(long, value_name = "N")]
+    pub max_results: Option<usize>,
+    /// Show at most this many matching lines per journal.
+    #[arg(long, value_name = "N")]
+    pub max_per_file: Option<usize>,
+    /// Report the number of journals searched and the ones skipped.
+    #[arg(long)]
+    pub stats: bool,
+    /// Print the number of matching journals and matches by workspace,
+    /// extension or month, instead of the matches.
+    #[arg(long, value_parser = ["workspace", "extension", "month"], conflicts_with = "open")]
+    pub group_by: Option<String>,
+    /// Print the results directly instead of using a pager
+    /// when they don't fit in the terminal.
+    #[arg(long)]
+    pub no_pager: bool,
+    /// Select a matching journal and open it in the editor at its first match.
+    #[arg(long)]
+    pub open: bool,
+}
+
+#[derive(Args)]
+pub struct ReplaceArgs {
+    /// Regular expression to replace in the journals.
+    pub pattern: String,
+    /// The replacement, which may refer to capture groups with $1 or $name.
+    pub replacement: String,
+    /// Ignore case when matching.
+    #[arg(long, short = 'i')]
+    pub case_insensitive: bool,
+    /// Optional workspace to use, else replace across all workspaces.
     #[arg(long, short = 'w', value_parser = valid_workspace_name)]
     pub workspace: Option<String>,
+    /// Only replace in this journal, in the given or default workspace.
+    #[arg(long)]
+    pub name: Option<String>,
     /// Use as key for decryption.
     /// If this is omitted encrypted files will be skipped.
     #[arg(long, short = 'k')]
     pub key: Option<String>,
+    /// Replace without asking for confirmation.
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+    /// Only show the replacements and the journals that would be rewritten.
+    #[arg(long, conflicts_with = "yes")]
+    pub dry_run: bool,
 }
 
 #[derive(Args)]
 pub struct ExportArgs {
-    /// The target to use for exporting.
-    #[arg(long, short, value_parser = ["zip"])]
-    pub target: String,
+    /// The target to use for exporting: zip, pdf, azure, plaintext-mirror
+    /// or sftp://[user@]host[:port]/path. If omitted, the target and
+    /// workspaces are picked interactively.
+    #[arg(long, short, value_parser = valid_export_target)]
+    pub target: Option<String>,
+    /// Export to a single PDF with a table of contents,
+    /// instead of one PDF per journal.
+    #[arg(long)]
+    pub combined: bool,
     /// Output the results to a directory.
     /// Defaults to current working directory.
     #[arg(long, short)]
     pub dir: Option<String>,
+    /// Workspaces to export, else export all workspaces.
+    /// Can be repeated and be a glob pattern, e.g. 'client-*'.
+    #[arg(long, short = 'w', value_parser = valid_workspace_pattern)]
+    pub workspace: Vec<String>,
+    /// Only export journals modified since a date, e.g. 2024-06-01, or a duration
+    /// ago in hours, days, weeks, months or years, e.g. 12h, 30d, 2w, 1mo or 1y.
+    #[arg(long, value_parser = valid_since)]
+    pub since: Option<String>,
+    /// Use as key for decryption.
+    /// If this is omitted encrypted files will be skipped.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+    /// Also write a report of the export to this file, with the exported and
+    /// skipped journals, digests and duration. Written as TOML if the path
+    /// ends with .toml, else as JSON.
+    #[arg(long)]
+    pub report: Option<String>,
+    /// Never prompt, e.g. replace an existing export without asking.
+    #[arg(long)]
+    pub non_interactive: bool,
+    /// Only show what would be uploaded to a remote target.
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Upload encrypted journals to a remote target as they are stored,
+    /// instead of decrypting them.
+    #[arg(long)]
+    pub keep_encrypted: bool,
+    /// Instead of exporting, install a systemd user timer, launchd agent or
+    /// scheduled task, depending on the platform, that runs this export
+    /// non-interactively on --schedule.
+    #[arg(long, requires = "target")]
+    pub install_timer: bool,
+    /// How often the installed timer runs the export.
+    #[arg(long, default_value = "daily", value_parser = ["hourly", "daily", "weekly"], requires = "install_timer")]
+    pub schedule: String,
+}
+
+#[derive(Args)]
+pub struct ServeArgs {
+    /// Port to listen on.
+    #[arg(long, short, default_value_t = 8421)]
+    pub port: u16,
+    /// Token clients must send as `Authorization: Bearer <token>`.
+    /// Defaults to `api-token` in the config, else a random token is generated.
+    #[arg(long)]
+    pub token: Option<String>,
+}
+
+#[derive(Args)]
+pub struct TuiArgs {
+    /// Use as key for decryption.
+    /// If this is omitted encrypted journals can't be previewed.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Args)]
+pub struct KeygenArgs {
+    /// Encoding of the key.
+    #[arg(long, default_value = "base64", value_parser = ["base64", "hex"])]
+    pub format: String,
+    /// Write the key to this new file, only readable by you, instead of printing it.
+    #[arg(long, short = 'o', conflicts_with = "keyring")]
+    pub output: Option<String>,
+    /// Store the key in the OS keyring instead of printing it. Uses `security`
+    /// on macOS and `secret-tool` elsewhere.
+    #[arg(long)]
+    pub keyring: bool,
+    /// Account to store the key by in the keyring, under the service `journal`.
+    #[arg(long, default_value = "default", requires = "keyring")]
+    pub account: String,
+}
+
+#[derive(Args)]
+pub struct WatchArgs {
+    /// Optional workspace to watch, else watch all workspaces.
+    #[arg(long, short = 'w', value_parser = valid_workspace_name)]
+    pub workspace: Option<String>,
+    /// Key used to re-encrypt journals.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+    /// Seconds a journal must be left unchanged before it's handled,
+    /// so that it isn't read while being written.
+    #[arg(long, default_value_t = 2)]
+    pub interval: u64,
+    /// Command to run after a journal changed, e.g. to export or sync.
+    /// It's run with `sh -c`, or `cmd /C` on Windows.
+    #[arg(long)]
+    pub exec: Option<String>,
+}
+
+#[derive(Args)]
+pub struct DiffArgs {
+    /// Name of the journal to compare, matched as when opening.
+    pub name: String,
+    /// Name of the journal to compare with.
+    #[arg(required_unless_present = "backup")]
+    pub other: Option<String>,
+    /// Compare with a backup of the journal taken by `jn replace`,
+    /// where 1 is the latest backup.
+    #[arg(long, conflicts_with = "other")]
+    pub backup: Option<usize>,
+    /// Only show the number of changed lines.
+    #[arg(long)]
+    pub stat: bool,
+    /// Optional workspace to use, else use the default workspace.
+    #[arg(long, short = 'w', value_parser = valid_workspace_name)]
+    pub workspace: Option<String>,
+    /// Use as key for decryption.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Args)]
+pub struct SplitArgs {
+    /// Name of the journal to split, matched as when opening.
+    pub name: String,
+    /// Level of the headings to split at, e.g. 2 for `## `.
+    #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u8).range(1..=6))]
+    pub level: u8,
+    /// Only show the journals that would be created.
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Optional workspace to use, else use the default workspace.
+    #[arg(long, short = 'w', value_parser = valid_workspace_name)]
+    pub workspace: Option<String>,
+    /// Use as key for decryption, and for encrypting the new journals
+    /// if the journal is encrypted.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Args)]
+pub struct LogArgs {
+    /// Text to add to the journal, formatted by `append-template`.
+    #[arg(required_unless_present = "mood")]
+    pub message: Option<String>,
+    /// Mood from 1 (bad) to 5 (great), or an emoji such as 🙂.
+    #[arg(long, short, value_parser = valid_mood)]
+    pub mood: Option<String>,
+    /// Optional workspace to use, else use the default workspace.
+    #[arg(long, short = 'w', value_parser = valid_workspace_name)]
+    pub workspace: Option<String>,
+    /// Use as key for decryption, and for encrypting a new journal.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Args)]
+pub struct AppendArgs {
+    /// Name of the journal to add to.
+    pub name: String,
+    /// Text to add, else it's read from stdin.
+    pub text: Option<String>,
+    /// Optional workspace to use, else use the default workspace.
+    #[arg(long, short = 'w', value_parser = valid_workspace_name)]
+    pub workspace: Option<String>,
+    /// Use as key for decryption.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Args)]
+pub struct MoodArgs {
+    /// Workspaces to use, else use all workspaces.
+    /// Can be repeated and be a glob pattern, e.g. 'client-*'.
+    #[arg(long, short = 'w', value_parser = valid_workspace_pattern)]
+    pub workspace: Vec<String>,
+    /// Group the moods by week or month.
+    #[arg(long, default_value = "week", value_parser = ["week", "month"])]
+    pub by: String,
     /// Use as key for decryption.
     /// If this is omitted encrypted files will be skipped.
     #[arg(long, short = 'k')]
     pub key: Option<String>,
 }
+
+#[derive(Args)]
+pub struct TodoArgs {
+    /// Workspaces to use, else use all workspaces.
+    /// Can be repeated and be a glob pattern, e.g. 'client-*'.
+    #[arg(long, short = 'w', value_parser = valid_workspace_pattern)]
+    pub workspace: Vec<String>,
+    /// Use as key for decryption.
+    /// If this is omitted encrypted files will be skipped.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct ReviewArgs {
+    #[command(subcommand)]
+    pub command: Option<ReviewCommand>,
+    /// List journals not opened in this many days.
+    #[arg(long, default_value_t = 30)]
+    pub days: i64,
+    /// Workspaces to review, else review all workspaces.
+    /// Can be repeated and be a glob pattern, e.g. 'client-*'.
+    #[arg(long, short = 'w', value_parser = valid_workspace_pattern)]
+    pub workspace: Vec<String>,
+    /// Key used to read the encrypted filenames of workspaces.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Subcommand)]
+pub enum ReviewCommand {
+    /// Creates a review journal of the journals from the last days,
+    /// from `review-template`, and opens it in the editor.
+    Generate(ReviewGenerateArgs),
+}
+
+#[derive(Args)]
+pub struct ReviewGenerateArgs {
+    /// Include journals from this many days, counting today, as dated by their
+    /// front matter, a date at the start of the name or when they were modified.
+    #[arg(long, default_value_t = 7)]
+    pub days: i64,
+    /// Workspaces to include, else include all workspaces.
+    /// Can be repeated and be a glob pattern, e.g. 'client-*'.
+    #[arg(long, short = 'w', value_parser = valid_workspace_pattern)]
+    pub workspace: Vec<String>,
+    /// Workspace to create the review in, else the default workspace.
+    #[arg(long, value_parser = valid_workspace_name)]
+    pub into: Option<String>,
+    /// Name of the review, defaults to review-{DATE}.md.
+    #[arg(long)]
+    pub name: Option<String>,
+    /// Only include the headings and open to-dos of the journals.
+    #[arg(long)]
+    pub summary: bool,
+    /// Use as key for decryption, and for encrypting the review.
+    /// If this is omitted encrypted journals are left out.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Args)]
+pub struct RandomArgs {
+    /// Workspaces to pick from, else pick from all workspaces.
+    /// Can be repeated and be a glob pattern, e.g. 'client-*'.
+    #[arg(long, short = 'w', value_parser = valid_workspace_pattern)]
+    pub workspace: Vec<String>,
+    /// Only pick journals with this tag in their front matter.
+    #[arg(long)]
+    pub tag: Option<String>,
+    /// Only pick journals dated before a date, e.g. 2024-01-01, or a duration
+    /// ago, e.g. 1y, as dated by their front matter, name or modification time.
+    #[arg(long, value_parser = valid_since, value_name = "DATE|DURATION")]
+    pub older_than: Option<String>,
+    /// Print the journal instead of opening it in the editor.
+    #[arg(long, short = 'p')]
+    pub print: bool,
+    /// Print encrypted journals with a tag in `sensitive-tags`
+    /// without confirming.
+    #[arg(long, requires = "print")]
+    pub reveal: bool,
+    /// Use as key for decryption.
+    /// If this is omitted encrypted journals aren't picked.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Args)]
+pub struct OnThisDayArgs {
+    /// Workspaces to use, else use all workspaces.
+    /// Can be repeated and be a glob pattern, e.g. 'client-*'.
+    #[arg(long, short = 'w', value_parser = valid_workspace_pattern)]
+    pub workspace: Vec<String>,
+    /// Also include journals from this day in previous months.
+    #[arg(long)]
+    pub monthly: bool,
+    /// Use this day instead of today, e.g. 2024-06-05.
+    #[arg(long)]
+    pub date: Option<NaiveDate>,
+    /// Print encrypted journals with a tag in `sensitive-tags`
+    /// instead of leaving out their content.
+    #[arg(long)]
+    pub reveal: bool,
+    /// Print the journals directly instead of using a pager
+    /// when they don't fit in the terminal.
+    #[arg(long)]
+    pub no_pager: bool,
+    /// Use as key for decryption.
+    /// If this is omitted encrypted journals are listed without content.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Args)]
+pub struct WcArgs {
+    /// Names of the journals to count, matched as when opening,
+    /// else all journals of the workspace are counted.
+    pub name: Vec<String>,
+    /// Also print the totals of the journals.
+    #[arg(long)]
+    pub total: bool,
+    /// Optional workspace to use, else use the default workspace.
+    #[arg(long, short = 'w', value_parser = valid_workspace_name)]
+    pub workspace: Option<String>,
+    /// Use as key for decryption.
+    /// If this is omitted encrypted journals can't be counted.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Args)]
+pub struct StatsArgs {
+    /// Only count the journals written today, as dated by their
+    /// front matter, name or modification time.
+    #[arg(long)]
+    pub today: bool,
+    /// Workspaces to count, else those of `word-goal`, or all workspaces.
+    /// Can be repeated and be a glob pattern, e.g. 'client-*'.
+    #[arg(long, short = 'w', value_parser = valid_workspace_pattern)]
+    pub workspace: Vec<String>,
+    /// Use as key for decryption.
+    /// If this is omitted encrypted journals aren't counted.
+    #[arg(long, short = 'k')]
+    pub key: Option<String>,
+}
+
+#[derive(Args)]
+pub struct RemindArgs {
+    /// Remind if no journal was written in this many days.
+    /// Defaults to `entry-days` in the `remind` config, else 3.
+    #[arg(long)]
+    pub entry_days: Option<i64>,
+    /// Remind if no export was made in this many days.
+    /// Defaults to `export-days` in the `remind` config, else 7.
+    #[arg(long)]
+    pub export_days: Option<i64>,
+    /// Show reminders as desktop notifications.
+    #[arg(long)]
+    pub notify: bool,
+}
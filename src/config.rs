@@ -13,8 +13,247 @@ pub struct Config {
     #[serde(rename = "default-workspace")]
     pub default_workspace: Option<String>,
     pub template: Option<HashMap<String, String>>,
+    /// Template of the entries added by `jn log` and `jn append`, where
+    /// {{CONTENT}} is the added text, e.g. `"\n## {{TIME}}\n{{CONTENT}}\n"`.
+    /// Supports the same placeholders as `template`.
+    #[serde(rename = "append-template")]
+    pub append_template: Option<String>,
+    /// Template of the reviews created by `jn review generate`, where {{ENTRIES}}
+    /// are the journals and {{FROM}} and {{TO}} the dates they are from.
+    #[serde(rename = "review-template")]
+    pub review_template: Option<String>,
+    /// File with prompts for the `{{PROMPT_OF_DAY}}` placeholder, one per line.
+    #[serde(rename = "prompts-file")]
+    pub prompts_file: Option<String>,
+    /// Don't create new journals left empty in the editor.
+    #[serde(rename = "discard-empty", default)]
+    pub discard_empty: bool,
+    /// Create journals that `jn open` finds no match for, like --create.
+    #[serde(rename = "create-missing", default)]
+    pub create_missing: bool,
+    /// Whether workspaces that journals are created in are created if they
+    /// don't exist: `always` (the default), `prompt` or `never`. The default
+    /// workspace is always created.
+    #[serde(rename = "create-workspaces", default)]
+    pub create_workspaces: CreateWorkspaces,
+    /// Match journal names fuzzily by default when opening.
+    #[serde(rename = "fuzzy-match", default)]
+    pub fuzzy_match: bool,
+    /// Keep metadata of journals, such as word counts and tags,
+    /// in a store so that they aren't parsed again until changed.
+    #[serde(rename = "metadata-store", default)]
+    pub metadata_store: bool,
+    /// Workspaces searched unless given: `all` (the default) or `default-workspace`.
+    #[serde(rename = "search-scope", default)]
+    pub search_scope: SearchScope,
+    /// Journals larger than this, in bytes, are skipped when searching.
+    /// Defaults to 16 MiB.
+    #[serde(rename = "search-max-file-size")]
+    pub search_max_file_size: Option<u64>,
+    /// Keep the decrypted content of encrypted journals between searches,
+    /// in a file encrypted with the key, so that repeated searches are faster.
+    #[serde(rename = "search-cache", default)]
+    pub search_cache: bool,
+    /// Tags of encrypted journals that `jn print` only outputs
+    /// with --reveal or after confirming, e.g. `["sensitive"]`.
+    #[serde(rename = "sensitive-tags")]
+    pub sensitive_tags: Option<Vec<String>>,
+    /// Token required by the API served by `jn serve`, which may be
+    /// a secret from `jn config secret`, like `sas-token`.
+    #[serde(rename = "api-token")]
+    pub api_token: Option<String>,
+    /// Arguments that open an editor at a line, keyed by editor name,
+    /// with {line} and {file} replaced, e.g. `hx = "{file}:{line}"`.
+    /// Defaults to `+{line} {file}`.
+    #[serde(rename = "editor-line-args")]
+    pub editor_line_args: Option<HashMap<String, String>>,
+    /// How `jn open` opens journals, keyed by extension: `editor`, `system`
+    /// for the default application, or a command given the file, e.g.
+    /// `pdf = "system"` and `png = "feh"`. Defaults to the editor.
+    pub open: Option<HashMap<String, String>>,
+    /// Time zone of the dates in filenames and templates: `local`,
+    /// `utc` or an offset such as `+02:00`. Defaults to `local`.
+    pub timezone: Option<String>,
+    /// Command shortcuts, mapping names to the arguments they expand to,
+    /// e.g. `standup = "create standup-{{DATE}}.md -w work"`.
+    pub aliases: Option<HashMap<String, String>>,
+    /// Export targets, `azure` or `sftp://` URLs, that journals are exported
+    /// to as soon as they're created or edited, e.g. `auto-export = ["azure"]`.
+    #[serde(rename = "auto-export")]
+    pub auto_export: Option<Vec<String>>,
+    /// Colors and icons used when listing journals.
+    pub appearance: Option<Appearance>,
+    /// Settings for `jn remind`.
+    pub remind: Option<RemindConfig>,
+    /// A daily goal of words to write, reported by `jn stats` and after saving.
+    #[serde(rename = "word-goal")]
+    pub word_goal: Option<WordGoalConfig>,
+    /// Settings for export targets.
+    pub export: Option<ExportConfig>,
+    /// Sizes of journals and workspaces to warn about.
+    pub quotas: Option<QuotaConfig>,
+    /// Settings for individual workspaces, keyed by workspace name.
+    pub workspaces: Option<HashMap<String, WorkspaceConfig>>,
+    /// Other directories with workspaces, keyed by name, whose
+    /// workspaces are named `{root}:{workspace}`.
+    pub roots: Option<HashMap<String, RootConfig>>,
 }
 
+#[derive(Deserialize, Serialize)]
+pub struct RootConfig {
+    /// The directory, laid out like the root directory with its workspaces
+    /// in `workspaces`, e.g. on an encrypted drive.
+    pub path: String,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub struct WorkspaceConfig {
+    /// Store journals under random filenames, with an
+    /// encrypted index mapping them to their real names.
+    #[serde(rename = "encrypt-filenames", default)]
+    pub encrypt_filenames: bool,
+    /// Journals of the workspace are meant to be encrypted, so creating
+    /// one requires a key. Implied by `encrypt-filenames`.
+    #[serde(default)]
+    pub encrypt: bool,
+    /// Store the journals on a server instead, reached with ssh,
+    /// given as `sftp://[user@]host[:port]/path`.
+    pub remote: Option<String>,
+    /// SSH identity file for `remote`, else the default keys are used.
+    #[serde(rename = "ssh-key")]
+    pub ssh_key: Option<String>,
+}
+
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct Appearance {
+    /// Marker shown after encrypted journals, defaults to a lock.
+    #[serde(rename = "encrypted-icon")]
+    pub encrypted_icon: Option<String>,
+    /// Styles of journals, keyed by file extension.
+    pub extensions: Option<HashMap<String, Style>>,
+    /// Styles of workspace names, keyed by workspace name.
+    pub workspaces: Option<HashMap<String, Style>>,
+}
+
+impl Appearance {
+    pub fn encrypted_icon(&self) -> &str {
+        self.encrypted_icon.as_deref().unwrap_or("🔒")
+    }
+}
+
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct Style {
+    /// Name of the color, e.g. "blue" or "dark_green".
+    pub color: Option<String>,
+    /// Shown before the name, e.g. an emoji.
+    pub icon: Option<String>,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub struct RemindConfig {
+    /// Workspaces to check for new entries, defaults to all workspaces.
+    pub workspaces: Option<Vec<String>>,
+    #[serde(rename = "entry-days")]
+    pub entry_days: Option<i64>,
+    #[serde(rename = "export-days")]
+    pub export_days: Option<i64>,
+    /// Show reminders as desktop notifications.
+    #[serde(default)]
+    pub notify: bool,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub struct WordGoalConfig {
+    /// Words to write every day.
+    pub words: usize,
+    /// Workspaces the words are counted in, defaults to all workspaces.
+    pub workspaces: Option<Vec<String>>,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub struct ExportConfig {
+    pub azure: Option<AzureConfig>,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub struct AzureConfig {
+    /// Name of the storage account.
+    pub account: String,
+    pub container: String,
+    /// Prefix of the blob names, e.g. "journals/".
+    pub prefix: Option<String>,
+    /// Shared access signature with write permission.
+    /// Can also be given in AZURE_STORAGE_SAS_TOKEN, else the
+    /// account signed in to the Azure CLI is used. May be a secret
+    /// from `jn config secret`: `keyring:{service}/{account}` or `encrypted:…`.
+    #[serde(rename = "sas-token")]
+    pub sas_token: Option<String>,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SearchScope {
+    #[default]
+    All,
+    DefaultWorkspace,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CreateWorkspaces {
+    #[default]
+    Always,
+    /// Ask before creating a workspace, and don't create it if not in a terminal.
+    Prompt,
+    /// Only create workspaces with `jn workspace create`.
+    Never,
+}
+
+/// Sizes in MB (1024 × 1024 bytes) above which journals and workspaces
+/// are warned about when listing and before exporting.
+#[derive(Default, Deserialize, Serialize)]
+pub struct QuotaConfig {
+    #[serde(rename = "journal-mb")]
+    pub journal_mb: Option<f64>,
+    #[serde(rename = "workspace-mb")]
+    pub workspace_mb: Option<f64>,
+}
+
+impl QuotaConfig {
+    /// Warnings for the journals in `workspace`, given by name and size in
+    /// bytes, that exceed the quota of a journal, and for the workspace if
+    /// their total size exceeds the quota of a workspace.
+    pub fn warnings(&self, workspace: &str, journals: &[(String, u64)]) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        if let Some(quota) = self.journal_mb {
+            for (name, size) in journals {
+                let mb = *size as f64 / MB;
+                if mb > quota {
+                    warnings.push(format!(
+                        "journal {}/{} is {:.1} MB, above the quota of {} MB",
+                        workspace, name, mb, quota
+                    ));
+                }
+            }
+        }
+
+        if let Some(quota) = self.workspace_mb {
+            let mb = journals.iter().map(|(_, size)| size).sum::<u64>() as f64 / MB;
+            if mb > quota {
+                warnings.push(format!(
+                    "workspace {} is {:.1} MB, above the quota of {} MB",
+                    workspace, mb, quota
+                ));
+            }
+        }
+
+        warnings
+    }
+}
+
+const MB: f64 = 1024.0 * 1024.0;
+
 impl Config {
     pub fn load(path: &FileEntry) -> Result<Self> {
         let config = if path.exists() {
@@ -27,4 +266,38 @@ impl Config {
 
         Ok(config)
     }
+
+    pub fn workspace(&self, name: &str) -> Option<&WorkspaceConfig> {
+        match &self.workspaces {
+            Some(workspaces) => workspaces.get(name),
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QuotaConfig;
+
+    #[test]
+    fn warning_about_quotas() {
+        let quotas = QuotaConfig {
+            journal_mb: Some(1.0),
+            workspace_mb: Some(2.5),
+        };
+        let journals = [
+            ("a.md".to_string(), 512 * 1024),
+            ("b.md".to_string(), 2 * 1024 * 1024),
+        ];
+
+        let warnings = quotas.warnings("work", &journals);
+        assert_eq!(
+            warnings,
+            ["journal work/b.md is 2.0 MB, above the quota of 1 MB"]
+        );
+
+        let warnings = quotas.warnings("work", &[journals[1].clone(), journals[1].clone()]);
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[2].starts_with("workspace work is 4.0 MB"));
+    }
 }
@@ -0,0 +1,66 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Names of conflicting copies, compiled once since every listed file is matched.
+static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+
+/// The name of the journal that `filename` is a conflicting copy of, if it's
+/// named like the copies sync tools make when a file was changed on two machines:
+///   - Dropbox: `notes (conflicted copy).md`, `notes (Jane's conflicted copy 2024-06-05).md`
+///   - Syncthing: `notes.sync-conflict-20240605-101112-ABCDEFG.md`
+///   - jn, when a journal was modified while editing: `notes.conflict-20240605101112.md`
+pub fn original_name(filename: &str) -> Option<String> {
+    let (dir, base) = match filename.rsplit_once('/') {
+        Some((dir, base)) => (format!("{}/", dir), base),
+        None => (String::new(), filename),
+    };
+
+    let patterns = PATTERNS.get_or_init(|| {
+        [
+            r"^(?P<stem>.+) \([^()]*conflicted copy[^()]*\)(?P<ext>\.[^.]+)?$",
+            r"^(?P<stem>.+)\.sync-conflict-\d{8}-\d{6}(-[A-Z0-9]+)?(?P<ext>\.[^.]+)?$",
+            r"^(?P<stem>.+)\.conflict-\d{14}(?P<ext>\.[^.]+)?$",
+        ]
+        .iter()
+        .filter_map(|pattern| Regex::new(pattern).ok())
+        .collect()
+    });
+    patterns.iter().find_map(|re| {
+        let captures = re.captures(base)?;
+        let ext = captures.name("ext").map_or("", |ext| ext.as_str());
+        Some(format!("{}{}{}", dir, &captures["stem"], ext))
+    })
+}
+
+/// Whether `filename` is a conflicting copy of another journal.
+pub fn is_conflict(filename: &str) -> bool {
+    original_name(filename).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::original_name;
+
+    #[test]
+    fn finding_originals() {
+        let cases = [
+            ("notes (conflicted copy).md", "notes.md"),
+            ("notes (Jane's conflicted copy 2024-06-05).md", "notes.md"),
+            ("notes.sync-conflict-20240605-101112-ABCDEFG.md", "notes.md"),
+            ("notes.conflict-20240605101112.md", "notes.md"),
+            ("projects/plan (conflicted copy)", "projects/plan"),
+        ];
+        for (conflict, original) in cases {
+            assert_eq!(
+                original_name(conflict).as_deref(),
+                Some(original),
+                "{}",
+                conflict
+            );
+        }
+
+        assert_eq!(original_name("notes.md"), None);
+        assert_eq!(original_name("notes (copy).md"), None);
+        assert_eq!(original_name("conflict-resolution.md"), None);
+    }
+}
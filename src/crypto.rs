@@ -1,3 +1,4 @@
+use crate::perf;
 use anyhow::bail;
 use anyhow::Result;
 use ring::aead::Aad;
@@ -9,9 +10,12 @@ use ring::aead::SealingKey;
 use ring::aead::UnboundKey;
 use ring::aead::AES_256_GCM;
 use ring::aead::NONCE_LEN;
+use ring::digest::{digest, SHA256};
 use ring::error::Unspecified;
+use ring::hmac;
 use ring::rand::SecureRandom;
 use ring::rand::SystemRandom;
+use std::fmt;
 
 struct ArrayNonceSequence<'a>(&'a [u8]);
 
@@ -21,6 +25,50 @@ impl<'a> NonceSequence for ArrayNonceSequence<'a> {
     }
 }
 
+/// Why encrypted data couldn't be decrypted, so that the user
+/// can be told what to do about it.
+#[derive(Debug, PartialEq)]
+pub enum DecryptError {
+    /// No key was given.
+    MissingKey,
+    /// The key isn't the one the data was encrypted with, known from its key check.
+    WrongKey,
+    /// The key is right, but the content doesn't match its authentication tag.
+    Corrupted,
+    /// Either of the above, for data without a key check.
+    WrongKeyOrCorrupted,
+    /// The header before the content is incomplete.
+    CorruptedHeader(String),
+    /// The data isn't encrypted, where it must be.
+    NotEncrypted,
+}
+
+impl fmt::Display for DecryptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingKey => write!(f, "key required (hint: use --key or set JOURNAL_KEY)"),
+            Self::WrongKey => write!(f, "wrong key (hint: it's encrypted with another key)"),
+            Self::Corrupted => write!(
+                f,
+                "content is corrupted, e.g. truncated or modified (hint: restore it from a backup)"
+            ),
+            Self::WrongKeyOrCorrupted => write!(
+                f,
+                "wrong key or corrupted content (hint: check the key, since files encrypted \
+                 by older versions of jn can't tell which)"
+            ),
+            Self::CorruptedHeader(reason) => write!(
+                f,
+                "corrupted header, {} (hint: the file may be truncated, restore it from a backup)",
+                reason
+            ),
+            Self::NotEncrypted => write!(f, "not encrypted (hint: was it written by jn?)"),
+        }
+    }
+}
+
+impl std::error::Error for DecryptError {}
+
 pub struct EncryptionResult {
     /// The encrypted data.
     pub ciphertext: Vec<u8>,
@@ -39,12 +87,7 @@ pub fn encrypt(data: &[u8], key: &str) -> Result<EncryptionResult> {
     };
 
     // Generate nonce
-    let rand = SystemRandom::new();
-    let mut nonce_bytes = vec![0; NONCE_LEN];
-    let nonce = match rand.fill(&mut nonce_bytes) {
-        Ok(_) => nonce_bytes,
-        Err(err) => bail!("error generating key: {}", err),
-    };
+    let nonce = random_bytes(NONCE_LEN)?;
 
     let nonce_sequence = ArrayNonceSequence(&nonce[..]);
 
@@ -53,25 +96,25 @@ pub fn encrypt(data: &[u8], key: &str) -> Result<EncryptionResult> {
     let mut sealing_key = SealingKey::new(unbound_key, nonce_sequence);
 
     // Create a mutable copy of the data that will be encrypted in place
-    let mut ciphertext = Vec::new();
-    ciphertext.extend_from_slice(data);
+    let mut ciphertext = data.to_vec();
 
     // Encrypt the data with AEAD using the AES_256_GCM algorithm
     match sealing_key.seal_in_place_separate_tag(Aad::empty(), &mut ciphertext) {
-        Ok(tag) => {
-            let mut t = Vec::new();
-            t.extend_from_slice(tag.as_ref());
-            Ok(EncryptionResult {
-                ciphertext,
-                nonce,
-                tag: t,
-            })
-        }
+        Ok(tag) => Ok(EncryptionResult {
+            ciphertext,
+            nonce,
+            tag: tag.as_ref().to_vec(),
+        }),
         Err(err) => bail!("error encrypting: {}", err),
     }
 }
 
-pub fn decrypt(key: &str, nonce: &[u8], tag: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+/// Decrypts `data` in place, reusing its allocation for the plaintext.
+pub fn decrypt(key: &str, nonce: &[u8], tag: &[u8], data: Vec<u8>) -> Result<Vec<u8>> {
+    perf::time("decrypt", || decrypt_in_place(key, nonce, tag, data))
+}
+
+fn decrypt_in_place(key: &str, nonce: &[u8], tag: &[u8], mut data: Vec<u8>) -> Result<Vec<u8>> {
     let key = get_key(key)?;
 
     let nonce_sequence = ArrayNonceSequence(nonce);
@@ -81,28 +124,60 @@ pub fn decrypt(key: &str, nonce: &[u8], tag: &[u8], data: &[u8]) -> Result<Vec<u
     };
 
     let mut opening_key = OpeningKey::new(unbound_key, nonce_sequence);
-    let mut plaintext = [data, tag].concat();
+    data.extend_from_slice(tag);
 
-    match opening_key.open_in_place(Aad::empty(), &mut plaintext) {
-        Ok(plaintext) => {
-            let mut p = Vec::with_capacity(plaintext.len());
-            p.extend_from_slice(plaintext);
-            Ok(p)
-        }
-        Err(err) => bail!("{}", err),
+    // Authentication fails the same way for a wrong key and modified data
+    let len = match opening_key.open_in_place(Aad::empty(), &mut data) {
+        Ok(plaintext) => plaintext.len(),
+        Err(_) => bail!(DecryptError::WrongKeyOrCorrupted),
+    };
+
+    data.truncate(len);
+    Ok(data)
+}
+
+/// Length of the values returned by `key_check`.
+pub const KEY_CHECK_LEN: usize = 8;
+
+/// A short value, stored next to the nonce of an encrypted journal,
+/// that tells if a key is the one it was encrypted with without
+/// decrypting it. It's a truncated HMAC of the nonce, so it reveals
+/// nothing about the key or the content.
+pub fn key_check(key: &str, nonce: &[u8]) -> Result<Vec<u8>> {
+    let key = get_key(key)?;
+    let key = hmac::Key::new(hmac::HMAC_SHA256, &key);
+
+    let mut message = b"journal key check:".to_vec();
+    message.extend_from_slice(nonce);
+    let tag = hmac::sign(&key, &message);
+    Ok(tag.as_ref()[..KEY_CHECK_LEN].to_vec())
+}
+
+/// Generates `len` cryptographically secure random bytes.
+pub fn random_bytes(len: usize) -> Result<Vec<u8>> {
+    let rand = SystemRandom::new();
+    let mut bytes = vec![0; len];
+    match rand.fill(&mut bytes) {
+        Ok(_) => Ok(bytes),
+        Err(err) => bail!("error generating random bytes: {}", err),
     }
 }
 
 const KEY_LEN: usize = 32;
 
 fn get_key(key_str: &str) -> Result<Vec<u8>> {
-    match key_str.bytes().len() {
+    match key_str.len() {
         0 => bail!("empty key"),
         n if n < 8 => bail!("key must not be shorter than 8 characters"),
-        n if n > 32 => bail!("key must not be longer than 32 characters"),
         _ => (),
     }
 
+    // Longer keys are hashed to the key length, while shorter ones are
+    // padded as they always were, to decrypt existing journals.
+    if key_str.len() > KEY_LEN {
+        return Ok(digest(&SHA256, key_str.as_bytes()).as_ref().to_vec());
+    }
+
     let mut key = Vec::with_capacity(KEY_LEN);
     key.extend_from_slice(key_str.as_bytes());
 
@@ -125,11 +200,34 @@ mod tests {
         let res = encrypt(data.as_bytes(), key).unwrap();
 
         // Decrypt
-        let plaintext = decrypt(key, &res.nonce[..], &res.tag[..], &res.ciphertext[..]).unwrap();
+        let plaintext = decrypt(key, &res.nonce[..], &res.tag[..], res.ciphertext).unwrap();
         let plaintext = String::from_utf8(plaintext).unwrap();
         assert_eq!(plaintext, data);
     }
 
+    #[test]
+    fn test_decrypt_wrong_key() {
+        let res = encrypt(b"journals", "testing-encryption").unwrap();
+        let err = decrypt("testing-other-key", &res.nonce, &res.tag, res.ciphertext).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<DecryptError>(),
+            Some(&DecryptError::WrongKeyOrCorrupted)
+        );
+    }
+
+    #[test]
+    fn test_key_check() {
+        let nonce = [1; NONCE_LEN];
+        let check = key_check("testing-encryption", &nonce).unwrap();
+        assert_eq!(check.len(), KEY_CHECK_LEN);
+        assert_eq!(check, key_check("testing-encryption", &nonce).unwrap());
+        assert_ne!(check, key_check("testing-other-key", &nonce).unwrap());
+        assert_ne!(
+            check,
+            key_check("testing-encryption", &[2; NONCE_LEN]).unwrap()
+        );
+    }
+
     #[test]
     fn test_encrypt_short_key() {
         // Encrypt
@@ -140,9 +238,13 @@ mod tests {
 
     #[test]
     fn test_encrypt_long_key() {
-        // Encrypt
         let key = "testing-testing-testing-testing-testing-testing";
-        let res = encrypt(b"journals", key);
-        assert!(res.is_err());
+        let res = encrypt(b"journals", key).unwrap();
+
+        // Only the same long key decrypts, not one sharing its first 32 characters
+        let truncated = &key[..KEY_LEN];
+        assert!(decrypt(truncated, &res.nonce, &res.tag, res.ciphertext.clone()).is_err());
+        let plaintext = decrypt(key, &res.nonce, &res.tag, res.ciphertext).unwrap();
+        assert_eq!(plaintext, b"journals");
     }
 }
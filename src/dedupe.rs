@@ -0,0 +1,139 @@
+use crate::diff::{diff, Edit};
+use std::collections::{HashMap, HashSet};
+
+/// Journals with the same content, or similar content.
+pub struct Duplicates {
+    /// Groups of indices of journals with exactly the same content.
+    pub exact: Vec<Vec<usize>>,
+    /// Pairs of indices of journals whose lines are similar,
+    /// with how similar they are from 0 to 1.
+    pub near: Vec<(usize, usize, f64)>,
+}
+
+/// Finds duplicates among `journals`, given by their digest and content.
+/// Journals are near duplicates if the share of distinct non-blank lines
+/// they have in common is at least `threshold`.
+pub fn find(journals: &[(String, String)], threshold: f64) -> Duplicates {
+    let mut by_digest: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, (digest, _)) in journals.iter().enumerate() {
+        by_digest.entry(digest).or_default().push(i);
+    }
+    let mut exact: Vec<Vec<usize>> = by_digest
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect();
+    exact.sort();
+
+    let lines: Vec<HashSet<&str>> = journals
+        .iter()
+        .map(|(_, content)| {
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect()
+        })
+        .collect();
+
+    let mut near = Vec::new();
+    for i in 0..journals.len() {
+        for j in i + 1..journals.len() {
+            if journals[i].0 == journals[j].0 {
+                continue;
+            }
+            let similarity = similarity(&lines[i], &lines[j]);
+            if similarity >= threshold {
+                near.push((i, j, similarity));
+            }
+        }
+    }
+
+    Duplicates { exact, near }
+}
+
+/// The number of lines in both sets relative to the lines in either.
+fn similarity(a: &HashSet<&str>, b: &HashSet<&str>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Merges `other` into `content`, keeping the lines of both in order.
+pub fn merge(content: &str, other: &str) -> String {
+    let mut merged: String = diff(content, other)
+        .into_iter()
+        .map(|edit| match edit {
+            Edit::Equal(line) | Edit::Delete(line) | Edit::Insert(line) => line,
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    if content.ends_with('\n') || other.ends_with('\n') {
+        merged.push('\n');
+    }
+    merged
+}
+
+/// Merges the journals by the (into, from) indices in `merges`, where `journals`
+/// are given by their digest and content. Merges into the same journal build on
+/// each other. Returns the merged content by the index of the journal merged into.
+pub fn merge_all(
+    journals: &[(String, String)],
+    merges: &[(usize, usize)],
+) -> HashMap<usize, String> {
+    let mut merged: HashMap<usize, String> = HashMap::new();
+    for (into, from) in merges {
+        let other = merged
+            .remove(from)
+            .unwrap_or_else(|| journals[*from].1.clone());
+        let content = merged.get(into).unwrap_or(&journals[*into].1);
+        let content = merge(content, &other);
+        merged.insert(*into, content);
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find, merge, merge_all};
+
+    #[test]
+    fn finding_duplicates() {
+        let journals = [
+            ("1".to_string(), "a\nb\nc\nd\n".to_string()),
+            ("2".to_string(), "x\ny\n".to_string()),
+            ("1".to_string(), "a\nb\nc\nd\n".to_string()),
+            ("3".to_string(), "a\nb\nc\nd\ne\n".to_string()),
+        ];
+
+        let duplicates = find(&journals, 0.8);
+        assert_eq!(duplicates.exact, [vec![0, 2]]);
+        let pairs: Vec<(usize, usize)> = duplicates.near.iter().map(|(i, j, _)| (*i, *j)).collect();
+        assert_eq!(pairs, [(0, 3), (2, 3)]);
+    }
+
+    #[test]
+    fn merging_journals() {
+        assert_eq!(merge("a\nb\nd\n", "a\nc\nd\ne\n"), "a\nb\nc\nd\ne\n");
+        assert_eq!(merge("same", "same"), "same");
+    }
+
+    #[test]
+    fn merging_several_journals() {
+        let journals = [
+            ("1".to_string(), "a\nb\n".to_string()),
+            ("2".to_string(), "a\nc\n".to_string()),
+            ("3".to_string(), "a\nd\n".to_string()),
+        ];
+
+        let merged = merge_all(&journals, &[(0, 1), (0, 2)]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[&0], "a\nb\nc\nd\n");
+
+        // Chained merges end up in the last journal merged into
+        let merged = merge_all(&journals, &[(1, 2), (0, 1)]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[&0], "a\nb\nc\nd\n");
+    }
+}
@@ -0,0 +1,168 @@
+/// A line in a diff.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Edit {
+    Equal(String),
+    Delete(String),
+    Insert(String),
+}
+
+/// Changed lines with the unchanged lines around them.
+/// Line numbers start at 1, as in unified diffs.
+#[derive(Debug, PartialEq)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub edits: Vec<Edit>,
+}
+
+/// Diffs the lines of `old` and `new` using their longest common subsequence,
+/// with the common prefix and suffix left out of the comparison.
+pub fn diff(old: &str, new: &str) -> Vec<Edit> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    // lcs[i][j] is the length of the LCS of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut edits: Vec<Edit> = old[..prefix]
+        .iter()
+        .map(|line| Edit::Equal(line.to_string()))
+        .collect();
+
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            edits.push(Edit::Equal(a[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            // Deletions before insertions, as in other diff tools
+            edits.push(Edit::Delete(a[i].to_string()));
+            i += 1;
+        } else {
+            edits.push(Edit::Insert(b[j].to_string()));
+            j += 1;
+        }
+    }
+
+    edits.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|line| Edit::Equal(line.to_string())),
+    );
+    edits
+}
+
+/// Groups the changes in `edits` into hunks with `context` unchanged lines
+/// before and after them. Changes closer than that share a hunk.
+pub fn hunks(edits: &[Edit], context: usize) -> Vec<Hunk> {
+    let changed: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, edit)| !matches!(edit, Edit::Equal(_)))
+        .map(|(i, _)| i)
+        .collect();
+
+    // Ranges of edits to include, merged when they overlap
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for i in changed {
+        let start = i.saturating_sub(context);
+        let end = (i + context + 1).min(edits.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let (mut old_start, mut new_start) = (1, 1);
+            for edit in &edits[..start] {
+                match edit {
+                    Edit::Equal(_) => {
+                        old_start += 1;
+                        new_start += 1;
+                    }
+                    Edit::Delete(_) => old_start += 1,
+                    Edit::Insert(_) => new_start += 1,
+                }
+            }
+
+            let edits = edits[start..end].to_vec();
+            let old_len = edits
+                .iter()
+                .filter(|e| !matches!(e, Edit::Insert(_)))
+                .count();
+            let new_len = edits
+                .iter()
+                .filter(|e| !matches!(e, Edit::Delete(_)))
+                .count();
+
+            Hunk {
+                old_start,
+                old_len,
+                new_start,
+                new_len,
+                edits,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, hunks, Edit};
+
+    #[test]
+    fn diffing_lines() {
+        let edits = diff("a\nb\nc\nd\n", "a\nc\nx\nd\n");
+        assert_eq!(
+            edits,
+            [
+                Edit::Equal("a".into()),
+                Edit::Delete("b".into()),
+                Edit::Equal("c".into()),
+                Edit::Insert("x".into()),
+                Edit::Equal("d".into()),
+            ]
+        );
+
+        assert!(diff("same\n", "same\n")
+            .iter()
+            .all(|e| matches!(e, Edit::Equal(_))));
+    }
+
+    #[test]
+    fn grouping_hunks() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let new = "1\ntwo\n3\n4\n5\n6\n7\n8\n9\nten\n";
+        let hunks = hunks(&diff(old, new), 2);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!((hunks[0].old_start, hunks[0].old_len), (1, 4));
+        assert_eq!((hunks[1].new_start, hunks[1].new_len), (8, 3));
+
+        assert!(super::hunks(&diff(old, old), 3).is_empty());
+    }
+}
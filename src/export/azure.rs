@@ -0,0 +1,165 @@
+use super::manifest::Manifest;
+use crate::{
+    cancel,
+    config::AzureConfig,
+    format::{ExportStatus, ExportedJournal, Output},
+    perf, secrets,
+    types::Workspaces,
+};
+use anyhow::{bail, Result};
+use std::env;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const API_VERSION: &str = "2021-08-06";
+
+enum Auth {
+    /// Shared access signature, appended to the URL.
+    Sas(String),
+    /// Access token from the Azure CLI.
+    Bearer(String),
+}
+
+/// Uploads the journals as blobs named {prefix}{workspace}/{journal},
+/// as given by `uploads`. Nothing is uploaded if `dry_run` is set.
+pub fn export(
+    config: &AzureConfig,
+    ws: Workspaces,
+    key: Option<String>,
+    keep_encrypted: bool,
+    manifest: &mut Manifest,
+    dry_run: bool,
+) -> Result<Output> {
+    let auth = if dry_run {
+        None
+    } else {
+        Some(auth(config, key.as_deref())?)
+    };
+    let prefix = config.prefix.as_deref().unwrap_or("");
+
+    let (uploads, mut skipped) = super::uploads(ws, key, keep_encrypted, manifest)?;
+    let mut exported: Vec<ExportedJournal> = Vec::new();
+
+    for item in uploads {
+        // Uploaded journals are kept in the manifest when cancelled
+        if cancel::requested() {
+            break;
+        }
+        if let Some(auth) = &auth {
+            let blob = format!("{}{}", prefix, item.name);
+            if let Err(err) = perf::time("upload", || upload(config, auth, &blob, &item.content)) {
+                skipped.push(ExportedJournal::skipped(
+                    &item.name,
+                    item.encrypted,
+                    ExportStatus::FailedIo,
+                    &err.to_string(),
+                ));
+                continue;
+            }
+            manifest.set(&item.name, item.entry.clone());
+        }
+        exported.push(item.exported()?);
+    }
+
+    Ok(Output::ExportResult { exported, skipped })
+}
+
+/// Uses the SAS token in AZURE_STORAGE_SAS_TOKEN or the config, where it may
+/// be in the keyring or encrypted with `key`, else an access token for the
+/// account signed in to the Azure CLI.
+fn auth(config: &AzureConfig, key: Option<&str>) -> Result<Auth> {
+    let sas = match (env::var("AZURE_STORAGE_SAS_TOKEN"), &config.sas_token) {
+        (Ok(sas), _) => Some(sas),
+        (Err(_), Some(token)) => Some(secrets::resolve(token, key)?),
+        (Err(_), None) => None,
+    };
+    if let Some(sas) = sas {
+        return Ok(Auth::Sas(sas.trim_start_matches('?').to_string()));
+    }
+
+    let output = Command::new("az")
+        .args([
+            "account",
+            "get-access-token",
+            "--resource",
+            "https://storage.azure.com/",
+            "--query",
+            "accessToken",
+            "--output",
+            "tsv",
+        ])
+        .output();
+    match output {
+        Ok(output) if output.status.success() => Ok(Auth::Bearer(
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        )),
+        _ => bail!("no SAS token for Azure and failed to get an access token (hint: az login)"),
+    }
+}
+
+/// Puts `content` as a block blob using curl.
+fn upload(config: &AzureConfig, auth: &Auth, blob: &str, content: &[u8]) -> Result<()> {
+    let mut url = format!(
+        "https://{}.blob.core.windows.net/{}/{}",
+        config.account,
+        config.container,
+        encode_path(blob)
+    );
+
+    let mut cmd = Command::new("curl");
+    cmd.args(["--silent", "--show-error", "--fail", "--request", "PUT"])
+        .args(["--header", "x-ms-blob-type: BlockBlob"])
+        .args(["--header", &format!("x-ms-version: {}", API_VERSION)])
+        .args(["--data-binary", "@-"]);
+    match auth {
+        Auth::Sas(sas) => url = format!("{}?{}", url, sas),
+        Auth::Bearer(token) => {
+            cmd.args(["--header", &format!("Authorization: Bearer {}", token)]);
+        }
+    }
+
+    let mut child = match cmd
+        .arg(&url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(err) => bail!("failed to run curl: {}", err),
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(content)?;
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("failed to upload {}: curl exited with {}", blob, status);
+    }
+    Ok(())
+}
+
+/// Percent-encodes a blob name, keeping the slashes between its segments.
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::encode_path;
+
+    #[test]
+    fn encoding_blob_names() {
+        assert_eq!(encode_path("journals/work/a.md"), "journals/work/a.md");
+        assert_eq!(encode_path("work/my notes#1.md"), "work/my%20notes%231.md");
+        assert_eq!(encode_path("work/å.md"), "work/%C3%A5.md");
+    }
+}
@@ -0,0 +1,94 @@
+use crate::fs::{read_file, FileEntry};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+
+/// The journals last exported to a remote target, so that
+/// unchanged journals aren't uploaded again.
+#[derive(Default, Deserialize, Serialize)]
+pub struct Manifest {
+    #[serde(default)]
+    journals: HashMap<String, ManifestEntry>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ManifestEntry {
+    /// SHA-256 of the uploaded content.
+    pub digest: String,
+    /// Size and modification time in milliseconds of the journal file,
+    /// so that unchanged journals can be skipped without being read.
+    pub size: u64,
+    pub modified: u64,
+    /// If the journal was uploaded encrypted, as stored.
+    #[serde(default)]
+    pub stored: bool,
+}
+
+impl Manifest {
+    pub fn load(path: &FileEntry) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = read_file(path.path())?;
+        let manifest: Manifest = serde_json::from_str(&content)?;
+        Ok(manifest)
+    }
+
+    pub fn save(&self, path: &FileEntry) -> Result<()> {
+        if let Some(dir) = path.path().parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        // Replaced as a whole, so an interrupted save keeps the previous manifest
+        let content = serde_json::to_string_pretty(self)?;
+        let temp = path.path().with_extension("json.tmp");
+        fs::write(&temp, content)?;
+        fs::rename(&temp, path.path())?;
+        Ok(())
+    }
+
+    /// Checks if `name` was last exported from a file with the same size
+    /// and modification time, uploaded as stored or not the same way.
+    pub fn unchanged(&self, name: &str, size: u64, modified: u64, stored: bool) -> bool {
+        matches!(
+            self.journals.get(name),
+            Some(entry) if entry.size == size && entry.modified == modified && entry.stored == stored
+        )
+    }
+
+    /// Checks if `name` was last exported with the same `digest`.
+    pub fn same_digest(&self, name: &str, digest: &str) -> bool {
+        matches!(self.journals.get(name), Some(entry) if entry.digest == digest)
+    }
+
+    pub fn set(&mut self, name: &str, entry: ManifestEntry) {
+        self.journals.insert(name.to_string(), entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Manifest, ManifestEntry};
+
+    #[test]
+    fn comparing_entries() {
+        let mut manifest = Manifest::default();
+        let entry = ManifestEntry {
+            digest: "abc".to_string(),
+            size: 10,
+            modified: 1000,
+            stored: false,
+        };
+        manifest.set("work/a.md", entry);
+
+        assert!(manifest.unchanged("work/a.md", 10, 1000, false));
+        assert!(!manifest.unchanged("work/a.md", 10, 2000, false));
+        assert!(!manifest.unchanged("work/a.md", 10, 1000, true));
+        assert!(!manifest.unchanged("work/b.md", 10, 1000, false));
+        assert!(manifest.same_digest("work/a.md", "abc"));
+        assert!(!manifest.same_digest("work/a.md", "def"));
+    }
+}
@@ -0,0 +1,103 @@
+use crate::{
+    cancel,
+    format::{ExportStatus, ExportedJournal, Output},
+    fs::{list_files_recursive, FileEntry},
+    types::Workspaces,
+};
+use anyhow::{bail, Result};
+use std::collections::HashSet;
+use std::fs;
+
+/// Writes decrypted copies of the journals to {dir}/{workspace}/{journal},
+/// named as they are listed, with line endings normalized to `\n`.
+/// Files whose content is unchanged aren't written, to keep their
+/// modification times, and files of journals that no longer exist are
+/// removed from the mirrored workspaces. Copies of skipped journals are kept.
+/// Files are replaced as a whole, so a cancelled export leaves none half written.
+pub fn export(dir: Option<String>, ws: Workspaces, key: Option<String>) -> Result<Output> {
+    let dir = match dir {
+        Some(dir) => FileEntry::from(dir.as_str()),
+        None => bail!("--dir is required for the plaintext-mirror target"),
+    };
+
+    let mut exported: Vec<ExportedJournal> = Vec::new();
+    let mut skipped: Vec<ExportedJournal> = Vec::new();
+
+    let mut ws: Vec<_> = ws.into_iter().collect();
+    ws.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (ws_name, ws) in ws {
+        let ws_dir = dir.push(&ws_name);
+        ws_dir.mkdir()?;
+        let mut kept = HashSet::new();
+
+        for file_entry in ws.files {
+            // Stale files are only known once every journal is kept
+            if cancel::requested() {
+                return Ok(Output::ExportResult { exported, skipped });
+            }
+
+            let filename = file_entry.filename();
+            let name = format!("{}/{}", ws_name, filename);
+            kept.insert(filename.clone());
+
+            let (encrypted, bytes) = super::read(&name, &file_entry, key.clone(), false);
+            let content = match bytes.map(String::from_utf8) {
+                Ok(Ok(content)) => normalize(&content),
+                Ok(Err(_)) => {
+                    skipped.push(ExportedJournal::skipped(
+                        &name,
+                        encrypted,
+                        ExportStatus::SkippedNotText,
+                        "not text",
+                    ));
+                    continue;
+                }
+                Err(journal) => {
+                    skipped.push(journal);
+                    continue;
+                }
+            };
+
+            let path = ws_dir.path().join(&filename);
+            if fs::read(&path).ok().as_deref() != Some(content.as_bytes()) {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let mut temp = path.clone().into_os_string();
+                temp.push(".jn-export");
+                fs::write(&temp, &content)?;
+                fs::rename(&temp, &path)?;
+            }
+            exported.push(ExportedJournal::exported(
+                &name,
+                encrypted,
+                content.as_bytes(),
+            )?);
+        }
+
+        for stale in list_files_recursive(ws_dir.path())?.files {
+            if !kept.contains(&stale.filename()) {
+                fs::remove_file(stale.path())?;
+            }
+        }
+    }
+
+    Ok(Output::ExportResult { exported, skipped })
+}
+
+/// Converts CRLF and CR line endings to LF.
+fn normalize(content: &str) -> String {
+    content.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize;
+
+    #[test]
+    fn normalizing_line_endings() {
+        assert_eq!(normalize("a\r\nb\rc\n"), "a\nb\nc\n");
+        assert_eq!(normalize("unchanged\n"), "unchanged\n");
+    }
+}
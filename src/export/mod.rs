@@ -1 +1,152 @@
+pub mod azure;
+pub mod manifest;
+pub mod mirror;
+pub mod pdf;
+pub mod report;
+pub mod sftp;
+pub mod timer;
 pub mod zip;
+
+use crate::cancel;
+use crate::format::{ExportStatus, ExportedJournal};
+use crate::fs::{digest, FileEntry};
+use crate::types::{Journal, Workspaces};
+use anyhow::Result;
+use manifest::{Manifest, ManifestEntry};
+use std::time::UNIX_EPOCH;
+
+/// A journal to upload to a remote target.
+pub struct Upload {
+    /// Path of the journal in the export, i.e. {workspace}/{journal}.
+    pub name: String,
+    pub encrypted: bool,
+    pub content: Vec<u8>,
+    /// To record in the manifest once uploaded.
+    pub entry: ManifestEntry,
+}
+
+impl Upload {
+    pub fn exported(&self) -> Result<ExportedJournal> {
+        ExportedJournal::exported(&self.name, self.encrypted, &self.content)
+    }
+}
+
+/// Reads the content of the journal at `file_entry` to export, as `name`:
+/// decrypted, or as stored if `stored` is set. Returns if the journal is
+/// encrypted along with the content, or else why it's skipped.
+pub fn read(
+    name: &str,
+    file_entry: &FileEntry,
+    key: Option<String>,
+    stored: bool,
+) -> (bool, std::result::Result<Vec<u8>, ExportedJournal>) {
+    let failed = |err: anyhow::Error, encrypted| {
+        ExportedJournal::skipped(name, encrypted, ExportStatus::FailedIo, &err.to_string())
+    };
+
+    let journal = match Journal::open(file_entry, key) {
+        Ok(journal) => journal,
+        Err(err) => return (false, Err(failed(err, false))),
+    };
+    let encrypted = journal.encrypted();
+    if stored && encrypted {
+        return (
+            encrypted,
+            file_entry.read_bytes().map_err(|err| failed(err, true)),
+        );
+    }
+
+    let content = match journal.try_bytes() {
+        Ok(Some(content)) => Ok(content),
+        Ok(None) => {
+            let reason = if journal.has_key() {
+                "wrong key"
+            } else {
+                "no key"
+            };
+            Err(ExportedJournal::skipped(
+                name,
+                true,
+                ExportStatus::SkippedEncrypted,
+                reason,
+            ))
+        }
+        Err(err) => Err(failed(err, encrypted)),
+    };
+    (encrypted, content)
+}
+
+/// Collects the journals to upload to a remote target, sorted by name.
+/// Journals are decrypted, unless `keep_encrypted` is set in which case
+/// encrypted journals are uploaded as they are stored. Journals that are
+/// unchanged since the last export, according to `manifest`, are skipped:
+/// without being read if the size and modification time of the file are
+/// the same, else if the digest of the content is. In the latter case the
+/// manifest is updated with the new size and modification time.
+pub fn uploads(
+    ws: Workspaces,
+    key: Option<String>,
+    keep_encrypted: bool,
+    manifest: &mut Manifest,
+) -> Result<(Vec<Upload>, Vec<ExportedJournal>)> {
+    let mut uploads = Vec::new();
+    let mut skipped = Vec::new();
+
+    let mut ws: Vec<_> = ws.into_iter().collect();
+    ws.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (ws_name, ws) in ws {
+        for file_entry in ws.files {
+            if cancel::requested() {
+                return Ok((uploads, skipped));
+            }
+
+            let name = format!("{}/{}", ws_name, file_entry.filename());
+            let encrypted = Journal::is_encrypted(&file_entry)?;
+            let stored = encrypted && keep_encrypted;
+
+            let size = file_entry.size()?;
+            let modified = file_entry
+                .modified()?
+                .duration_since(UNIX_EPOCH)?
+                .as_millis() as u64;
+            if manifest.unchanged(&name, size, modified, stored) {
+                skipped.push(unchanged(&name, encrypted));
+                continue;
+            }
+
+            let content = match read(&name, &file_entry, key.clone(), stored).1 {
+                Ok(content) => content,
+                Err(journal) => {
+                    skipped.push(journal);
+                    continue;
+                }
+            };
+
+            let entry = ManifestEntry {
+                digest: digest(&content)?,
+                size,
+                modified,
+                stored,
+            };
+            if manifest.same_digest(&name, &entry.digest) {
+                manifest.set(&name, entry);
+                skipped.push(unchanged(&name, encrypted));
+                continue;
+            }
+
+            uploads.push(Upload {
+                name,
+                encrypted,
+                content,
+                entry,
+            });
+        }
+    }
+
+    Ok((uploads, skipped))
+}
+
+fn unchanged(name: &str, encrypted: bool) -> ExportedJournal {
+    ExportedJournal::skipped(name, encrypted, ExportStatus::SkippedUnchanged, "unchanged")
+}
@@ -0,0 +1,474 @@
+use crate::{
+    cancel,
+    format::{ExportStatus, ExportedJournal, Output},
+    frontmatter,
+    fs::FileEntry,
+    types::Workspaces,
+};
+use anyhow::Result;
+use std::fs;
+
+/// A4 in points.
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 56.0;
+
+const BODY_SIZE: f32 = 11.0;
+const CODE_SIZE: f32 = 9.5;
+
+/// Approximate width of a character relative to the font size,
+/// since the widths of the standard fonts aren't embedded.
+const CHAR_WIDTH: f32 = 0.52;
+const CODE_CHAR_WIDTH: f32 = 0.6;
+
+/// Exports the journals to PDF, either one file per journal or all of them
+/// in a single file named by `date` with a table of contents if `combined` is set.
+/// Journals that can't be decrypted or aren't text are skipped.
+pub fn export(
+    dir: Option<String>,
+    ws: Workspaces,
+    key: Option<String>,
+    date: &str,
+    combined: bool,
+) -> Result<Output> {
+    let dir = match dir {
+        Some(dir) => FileEntry::from(dir.as_str()),
+        None => FileEntry::from("."),
+    };
+
+    let mut exported: Vec<ExportedJournal> = Vec::new();
+    let mut skipped: Vec<ExportedJournal> = Vec::new();
+    // Name and content of the journals to render
+    let mut documents: Vec<(ExportedJournal, String)> = Vec::new();
+
+    let mut ws: Vec<_> = ws.into_iter().collect();
+    ws.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (ws_name, ws) in ws {
+        for file_entry in ws.files {
+            // A combined PDF isn't written at all if cancelled
+            if cancel::requested() {
+                return Ok(Output::ExportResult { exported, skipped });
+            }
+
+            let name = format!("{}/{}", ws_name, file_entry.filename());
+            let (encrypted, bytes) = super::read(&name, &file_entry, key.clone(), false);
+            match bytes.map(String::from_utf8) {
+                Ok(Ok(content)) => {
+                    let item = ExportedJournal::exported(&name, encrypted, content.as_bytes())?;
+                    documents.push((item, content))
+                }
+                Ok(Err(_)) => skipped.push(ExportedJournal::skipped(
+                    &name,
+                    encrypted,
+                    ExportStatus::SkippedNotText,
+                    "not text",
+                )),
+                Err(journal) => skipped.push(journal),
+            }
+        }
+    }
+
+    if documents.is_empty() {
+        return Ok(Output::ExportResult { exported, skipped });
+    }
+
+    if combined {
+        let filepath = dir.push(&format!("journals.{}.pdf", date));
+        let docs: Vec<(&str, &str)> = documents
+            .iter()
+            .map(|(item, content)| (item.name.as_str(), content.as_str()))
+            .collect();
+        fs::write(filepath.path(), render_combined(&docs))?;
+        exported.extend(documents.into_iter().map(|(item, _)| item));
+    } else {
+        for (item, content) in documents {
+            if cancel::requested() {
+                break;
+            }
+            let filename = format!("{}.pdf", item.name.replace('/', "-"));
+            let mut layout = Layout::new();
+            layout.document(&item.name, &content);
+            fs::write(dir.push(&filename).path(), layout.finish())?;
+            exported.push(item);
+        }
+    }
+
+    Ok(Output::ExportResult { exported, skipped })
+}
+
+/// Renders all documents to a single PDF, starting with a table of contents.
+fn render_combined(docs: &[(&str, &str)]) -> Vec<u8> {
+    let mut layout = Layout::new();
+
+    // Lay out the table of contents first to know where the documents start
+    let toc_pages = {
+        let mut toc = Layout::new();
+        toc.toc(&vec![(String::new(), 0); docs.len()]);
+        toc.pages.len()
+    };
+
+    let mut entries = Vec::with_capacity(docs.len());
+    let mut body = Layout::new();
+    for (i, (title, content)) in docs.iter().enumerate() {
+        if i > 0 {
+            body.new_page();
+        }
+        // The document starts on the current, last, page
+        entries.push((title.to_string(), toc_pages + body.pages.len() - 1));
+        body.document(title, content);
+    }
+
+    layout.toc(&entries);
+    layout.pages.extend(body.pages);
+    layout.finish()
+}
+
+#[derive(Clone, Copy)]
+enum Font {
+    Regular,
+    Bold,
+    Code,
+}
+
+impl Font {
+    fn resource(&self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+            Font::Code => "F3",
+        }
+    }
+}
+
+/// Lays out lines of text on pages, as PDF content stream operators.
+struct Layout {
+    pages: Vec<String>,
+    /// Baseline of the next line on the current page.
+    y: f32,
+}
+
+impl Layout {
+    fn new() -> Self {
+        Self {
+            pages: vec![String::new()],
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(String::new());
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    fn space(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    /// Writes `text` at `indent`, wrapping it at the right margin.
+    fn text(&mut self, text: &str, font: Font, size: f32, indent: f32) {
+        let char_width = match font {
+            Font::Code => CODE_CHAR_WIDTH,
+            _ => CHAR_WIDTH,
+        };
+        let max_chars = ((PAGE_WIDTH - 2.0 * MARGIN - indent) / (size * char_width)) as usize;
+
+        for line in wrap(text, max_chars.max(1)) {
+            self.line(&line, font, size, indent);
+        }
+    }
+
+    fn line(&mut self, text: &str, font: Font, size: f32, x: f32) {
+        let height = size * 1.4;
+        if self.y - height < MARGIN {
+            self.new_page();
+        }
+        self.y -= height;
+
+        let page = self.pages.last_mut().unwrap();
+        page.push_str(&format!(
+            "BT /{} {} Tf {:.1} {:.1} Td ({}) Tj ET\n",
+            font.resource(),
+            size,
+            MARGIN + x,
+            self.y,
+            escape(text)
+        ));
+    }
+
+    fn toc(&mut self, entries: &[(String, usize)]) {
+        self.text("Contents", Font::Bold, 20.0, 0.0);
+        self.space(8.0);
+        for (title, page) in entries {
+            self.text(
+                &format!("{}  ...  {}", title, page + 1),
+                Font::Regular,
+                BODY_SIZE,
+                0.0,
+            );
+        }
+    }
+
+    /// Renders the Markdown `content` of a journal, with `title` as the heading.
+    fn document(&mut self, title: &str, content: &str) {
+        self.text(title, Font::Bold, 22.0, 0.0);
+        self.space(8.0);
+
+        let mut code = false;
+        for line in frontmatter::body(content).lines() {
+            if line.trim_start().starts_with("```") {
+                code = !code;
+                continue;
+            }
+
+            if code {
+                self.text(line, Font::Code, CODE_SIZE, 12.0);
+                continue;
+            }
+
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                self.space(BODY_SIZE * 0.6);
+                continue;
+            }
+
+            let level = trimmed.chars().take_while(|c| *c == '#').count();
+            if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+                let size = match level {
+                    1 => 18.0,
+                    2 => 15.0,
+                    3 => 13.0,
+                    _ => BODY_SIZE,
+                };
+                self.space(size * 0.4);
+                self.text(&inline(trimmed[level..].trim()), Font::Bold, size, 0.0);
+                continue;
+            }
+
+            if let Some(item) = ["- ", "* ", "+ "]
+                .iter()
+                .find_map(|bullet| trimmed.strip_prefix(bullet))
+            {
+                let indent = (line.len() - line.trim_start().len()) as f32 * 4.0;
+                let item = format!("• {}", inline(item));
+                self.text(&item, Font::Regular, BODY_SIZE, 12.0 + indent);
+                continue;
+            }
+
+            self.text(&inline(trimmed), Font::Regular, BODY_SIZE, 0.0);
+        }
+    }
+
+    /// Writes the pages as a PDF document, with page numbers.
+    fn finish(self) -> Vec<u8> {
+        let count = self.pages.len();
+        let mut objects: Vec<Vec<u8>> = Vec::new();
+
+        // 1: catalog, 2: pages, 3-5: fonts, then content and page per page
+        let kids: Vec<String> = (0..count).map(|i| format!("{} 0 R", 7 + 2 * i)).collect();
+        objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+        objects.push(
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                kids.join(" "),
+                count
+            )
+            .into_bytes(),
+        );
+        for font in ["Helvetica", "Helvetica-Bold", "Courier"] {
+            objects.push(
+                format!(
+                    "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+                    font
+                )
+                .into_bytes(),
+            );
+        }
+
+        for (i, page) in self.pages.into_iter().enumerate() {
+            let mut content = page.into_bytes();
+            content.extend(
+                format!(
+                    "BT /F1 9 Tf {:.1} {:.1} Td ({}) Tj ET\n",
+                    PAGE_WIDTH / 2.0 - 8.0,
+                    MARGIN / 2.0,
+                    i + 1
+                )
+                .into_bytes(),
+            );
+
+            let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+            stream.extend(content);
+            stream.extend(b"endstream");
+            objects.push(stream);
+
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Contents {} 0 R \
+                     /Resources << /Font << /F1 3 0 R /F2 4 0 R /F3 5 0 R >> >> >>",
+                    PAGE_WIDTH,
+                    PAGE_HEIGHT,
+                    6 + 2 * i
+                )
+                .into_bytes(),
+            );
+        }
+
+        write_pdf(objects)
+    }
+}
+
+fn write_pdf(objects: Vec<Vec<u8>>) -> Vec<u8> {
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", i + 1).into_bytes());
+        pdf.extend(object);
+        pdf.extend(b"\nendobj\n");
+    }
+
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+    for offset in offsets {
+        pdf.extend(format!("{:010} 00000 n \n", offset).into_bytes());
+    }
+    pdf.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        )
+        .into_bytes(),
+    );
+
+    pdf
+}
+
+/// Removes the Markdown markers for emphasis, code and links in a line.
+fn inline(text: &str) -> String {
+    let text = text.replace("**", "").replace("__", "").replace('`', "");
+    // [label](url) is shown as label (url)
+    text.replace("](", " (").replace('[', "")
+}
+
+/// Wraps `text` into lines of at most `width` characters, breaking at spaces
+/// when possible.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+
+    for word in text.split(' ') {
+        let mut word = word.to_string();
+        while word.chars().count() > width {
+            if !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+            let split: String = word.chars().take(width).collect();
+            word = word.chars().skip(width).collect();
+            lines.push(split);
+        }
+
+        let len = current.chars().count();
+        if len > 0 && len + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(&word);
+    }
+
+    lines.push(current);
+    lines
+}
+
+/// Escapes text for a PDF string in WinAnsiEncoding.
+/// Characters that can't be encoded are replaced with `?`.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for ch in text.chars() {
+        let byte = match ch {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(ch);
+                continue;
+            }
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            ch if (ch as u32) < 0x80 && !ch.is_control() => {
+                escaped.push(ch);
+                continue;
+            }
+            ch if (0xa0..=0xff).contains(&(ch as u32)) => ch as u32 as u8,
+            _ => b'?',
+        };
+        escaped.push_str(&format!("\\{:03o}", byte));
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::bail;
+
+    /// Checks that the offsets in the cross-reference table point to the objects.
+    fn check_xref(pdf: &[u8]) -> Result<()> {
+        let text = String::from_utf8_lossy(pdf);
+        let xref: usize = match text.rsplit("startxref\n").next() {
+            Some(rest) => rest.lines().next().unwrap_or_default().parse()?,
+            None => bail!("missing startxref"),
+        };
+
+        for (i, line) in text[xref..].lines().skip(3).enumerate() {
+            if line.starts_with("trailer") {
+                break;
+            }
+            let offset: usize = line[..10].parse()?;
+            if !text[offset..].starts_with(&format!("{} 0 obj", i + 1)) {
+                bail!("wrong offset for object {}", i + 1);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn rendering_documents() -> Result<()> {
+        let content = "---\nmood: 4\n---\n# Notes\n\nSome *text* (with parens) and ünïcode 日記.\n\n- item\n```\nfn main() {}\n```\n";
+        let long = "word ".repeat(2000);
+
+        let mut layout = Layout::new();
+        layout.document("work/notes.md", content);
+        check_xref(&layout.finish())?;
+
+        let pdf = render_combined(&[("work/notes.md", content), ("work/long.md", &long)]);
+        assert!(pdf.starts_with(b"%PDF-1.4"));
+        check_xref(&pdf)?;
+        Ok(())
+    }
+
+    #[test]
+    fn wrapping_lines() {
+        assert_eq!(wrap("a b c", 3), ["a b", "c"]);
+        assert_eq!(wrap("abcdefg", 3), ["abc", "def", "g"]);
+        assert_eq!(wrap("", 3), [""]);
+    }
+
+    #[test]
+    fn escaping_text() {
+        assert_eq!(escape("a(b)\\"), "a\\(b\\)\\\\");
+        assert_eq!(escape("ü•日"), "\\374\\225\\077");
+    }
+}
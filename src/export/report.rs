@@ -0,0 +1,68 @@
+use crate::format::ExportedJournal;
+use anyhow::Result;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+/// Report of an export, written to a file for monitoring scheduled exports.
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Report<'a> {
+    pub target: &'a str,
+    /// Where the export was written, a directory or URL.
+    pub destination: String,
+    /// Start of the export, in RFC 3339.
+    pub started: String,
+    pub duration_ms: u64,
+    pub exported: &'a [ExportedJournal],
+    pub skipped: &'a [ExportedJournal],
+}
+
+impl Report<'_> {
+    /// Writes the report as TOML if `path` ends with `.toml`, else as JSON.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let content = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::to_string(self)?,
+            _ => serde_json::to_string_pretty(self)?,
+        };
+        fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Report;
+    use crate::format::{ExportStatus, ExportedJournal};
+
+    #[test]
+    fn serializing_reports() {
+        let exported = [ExportedJournal::exported("work/a.md", false, b"a").unwrap()];
+        let skipped = [ExportedJournal::skipped(
+            "work/b.md",
+            true,
+            ExportStatus::SkippedEncrypted,
+            "no key",
+        )];
+        let report = Report {
+            target: "zip",
+            destination: ".".to_string(),
+            started: "2024-07-01T10:00:00+02:00".to_string(),
+            duration_ms: 12,
+            exported: &exported,
+            skipped: &skipped,
+        };
+
+        let toml = toml::to_string(&report).unwrap();
+        assert!(toml.contains("duration-ms = 12"));
+        assert!(toml
+            .contains("[[skipped]]\nname = \"work/b.md\"\nencrypted = true\nstatus = \"skipped-encrypted\"\nreason = \"no key\""));
+
+        let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+        assert_eq!(
+            json["exported"][0]["digest"].as_str().map(|d| d.len()),
+            Some(64)
+        );
+        assert!(json["exported"][0].get("reason").is_none());
+    }
+}
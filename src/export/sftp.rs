@@ -0,0 +1,190 @@
+use super::manifest::Manifest;
+use crate::{
+    cancel,
+    format::{ExportStatus, ExportedJournal, Output},
+    perf,
+    types::Workspaces,
+};
+use anyhow::{bail, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// A server to export to, given as sftp://[user@]host[:port]/path.
+/// The path is absolute, unless it starts with /~/ in which case
+/// it's relative to the home directory of the user.
+#[derive(Debug, PartialEq)]
+pub struct Target {
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+    pub path: String,
+}
+
+impl Target {
+    pub fn parse(url: &str) -> Result<Self> {
+        let rest = match url.trim().strip_prefix("sftp://") {
+            Some(rest) => rest,
+            None => bail!("expected an sftp:// URL: {}", url),
+        };
+
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let (user, host) = match authority.rsplit_once('@') {
+            Some((user, host)) if !user.is_empty() => (Some(user.to_string()), host),
+            Some((_, host)) => (None, host),
+            None => (None, authority),
+        };
+        let (host, port) = match host.rsplit_once(':') {
+            Some((host, port)) => match port.parse::<u16>() {
+                Ok(port) => (host, Some(port)),
+                Err(_) => bail!("invalid port in URL: {}", port),
+            },
+            None => (host, None),
+        };
+        if host.is_empty() {
+            bail!("missing host in URL: {}", url);
+        }
+
+        let path = match path.strip_prefix("/~") {
+            Some(relative) => match relative.trim_matches('/') {
+                "" => ".".to_string(),
+                relative => relative.to_string(),
+            },
+            None => match path.trim_end_matches('/') {
+                "" => "/".to_string(),
+                absolute => absolute.to_string(),
+            },
+        };
+
+        Ok(Self {
+            user,
+            host: host.to_string(),
+            port,
+            path,
+        })
+    }
+
+    pub fn destination(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{}@{}", user, self.host),
+            None => self.host.clone(),
+        }
+    }
+}
+
+/// Uploads the journals to {path}/{workspace}/{journal} on the server,
+/// as given by `uploads`. Nothing is uploaded if `dry_run` is set.
+pub fn export(
+    target: &Target,
+    ws: Workspaces,
+    key: Option<String>,
+    keep_encrypted: bool,
+    manifest: &mut Manifest,
+    dry_run: bool,
+) -> Result<Output> {
+    let (uploads, mut skipped) = super::uploads(ws, key, keep_encrypted, manifest)?;
+    let mut exported: Vec<ExportedJournal> = Vec::new();
+
+    for item in uploads {
+        // Uploaded journals are kept in the manifest when cancelled
+        if cancel::requested() {
+            break;
+        }
+        if !dry_run {
+            let path = format!("{}/{}", target.path.trim_end_matches('/'), item.name);
+            if let Err(err) = perf::time("upload", || upload(target, &path, &item.content)) {
+                skipped.push(ExportedJournal::skipped(
+                    &item.name,
+                    item.encrypted,
+                    ExportStatus::FailedIo,
+                    &err.to_string(),
+                ));
+                continue;
+            }
+            manifest.set(&item.name, item.entry.clone());
+        }
+        exported.push(item.exported()?);
+    }
+
+    Ok(Output::ExportResult { exported, skipped })
+}
+
+/// Writes `content` to `path` on the server using ssh, piping the content
+/// so that decrypted journals are never written to the local disk.
+/// Requires key based authentication, since ssh may not prompt.
+fn upload(target: &Target, path: &str, content: &[u8]) -> Result<()> {
+    let dir = match path.rsplit_once('/') {
+        Some(("", _)) => "/",
+        Some((dir, _)) => dir,
+        None => ".",
+    };
+    // Written next to the journal first, so it's never left half written
+    let temp = format!("{}.jn-upload", path);
+    let script = format!(
+        "mkdir -p {} && cat > {} && mv {} {}",
+        quote(dir),
+        quote(&temp),
+        quote(&temp),
+        quote(path)
+    );
+
+    let mut cmd = Command::new("ssh");
+    cmd.args(["-o", "BatchMode=yes"]);
+    if let Some(port) = target.port {
+        cmd.args(["-p", &port.to_string()]);
+    }
+
+    let mut child = match cmd
+        .arg(target.destination())
+        .arg(script)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(err) => bail!("failed to run ssh: {}", err),
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(content)?;
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("failed to upload {}: ssh exited with {}", path, status);
+    }
+    Ok(())
+}
+
+/// Quotes `s` for a POSIX shell.
+pub fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Target;
+
+    #[test]
+    fn parsing_targets() {
+        let target = Target::parse("sftp://me@example.com:2222/srv/journals/").unwrap();
+        assert_eq!(
+            target,
+            Target {
+                user: Some("me".to_string()),
+                host: "example.com".to_string(),
+                port: Some(2222),
+                path: "/srv/journals".to_string(),
+            }
+        );
+
+        let target = Target::parse("sftp://example.com/~/backups").unwrap();
+        assert_eq!((target.user, target.port), (None, None));
+        assert_eq!(target.path, "backups");
+
+        assert!(Target::parse("sftp://me@/path").is_err());
+        assert!(Target::parse("sftp://host:port/path").is_err());
+        assert!(Target::parse("ftp://host/path").is_err());
+    }
+}
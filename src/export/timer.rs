@@ -0,0 +1,151 @@
+use anyhow::{bail, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Name of the installed systemd units and scheduled task.
+const NAME: &str = "jn-export";
+/// Label of the installed launchd agent.
+const LAUNCHD_LABEL: &str = "com.github.lunjon.journal.export";
+
+/// Installs a systemd user timer, launchd agent or scheduled task,
+/// depending on the platform, that runs `exe` with `args` on `schedule`:
+/// hourly, daily or weekly. Returns the files written, if any.
+pub fn install(exe: &Path, args: &[String], schedule: &str) -> Result<Vec<PathBuf>> {
+    let basedir = match directories::BaseDirs::new() {
+        Some(basedir) => basedir,
+        None => bail!("failed to resolver user base directories"),
+    };
+
+    if cfg!(target_os = "macos") {
+        let dir = basedir.home_dir().join("Library/LaunchAgents");
+        fs::create_dir_all(&dir)?;
+        let plist = dir.join(format!("{}.plist", LAUNCHD_LABEL));
+        fs::write(&plist, launchd_plist(exe, args, schedule))?;
+
+        run(Command::new("launchctl").arg("load").arg("-w").arg(&plist))?;
+        Ok(vec![plist])
+    } else if cfg!(windows) {
+        let command = command_line(exe, args);
+        run(Command::new("schtasks").args([
+            "/Create",
+            "/F",
+            "/TN",
+            NAME,
+            "/SC",
+            &schedule.to_uppercase(),
+            "/TR",
+            &command,
+        ]))?;
+        Ok(vec![])
+    } else {
+        let dir = basedir.config_dir().join("systemd/user");
+        fs::create_dir_all(&dir)?;
+        let (service, timer) = systemd_units(exe, args, schedule);
+        let service_path = dir.join(format!("{}.service", NAME));
+        let timer_path = dir.join(format!("{}.timer", NAME));
+        fs::write(&service_path, service)?;
+        fs::write(&timer_path, timer)?;
+
+        run(Command::new("systemctl").args(["--user", "daemon-reload"]))?;
+        run(Command::new("systemctl").args([
+            "--user",
+            "enable",
+            "--now",
+            &format!("{}.timer", NAME),
+        ]))?;
+        Ok(vec![service_path, timer_path])
+    }
+}
+
+fn run(cmd: &mut Command) -> Result<()> {
+    let program = cmd.get_program().to_string_lossy().to_string();
+    match cmd.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => bail!("{} failed with {}", program, status),
+        Err(err) => bail!("failed to run {}: {}", program, err),
+    }
+}
+
+/// Joins `exe` and `args` to a command line, quoting arguments with whitespace.
+fn command_line(exe: &Path, args: &[String]) -> String {
+    std::iter::once(exe.display().to_string())
+        .chain(args.iter().cloned())
+        .map(|arg| {
+            if arg.contains(char::is_whitespace) {
+                format!("\"{}\"", arg)
+            } else {
+                arg
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// The systemd service and timer units.
+fn systemd_units(exe: &Path, args: &[String], schedule: &str) -> (String, String) {
+    let service = format!(
+        "[Unit]\nDescription=Export journals\n\n[Service]\nType=oneshot\nExecStart={}\n",
+        command_line(exe, args)
+    );
+    let timer = format!(
+        "[Unit]\nDescription=Export journals {}\n\n[Timer]\nOnCalendar={}\nPersistent=true\n\n[Install]\nWantedBy=timers.target\n",
+        schedule, schedule
+    );
+    (service, timer)
+}
+
+/// The launchd agent, run at an interval given by `schedule`.
+fn launchd_plist(exe: &Path, args: &[String], schedule: &str) -> String {
+    let interval = match schedule {
+        "hourly" => 60 * 60,
+        "weekly" => 7 * 24 * 60 * 60,
+        _ => 24 * 60 * 60,
+    };
+    let arguments: String = std::iter::once(exe.display().to_string())
+        .chain(args.iter().cloned())
+        .map(|arg| format!("        <string>{}</string>\n", escape_xml(&arg)))
+        .collect();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+{}    </array>
+    <key>StartInterval</key>
+    <integer>{}</integer>
+</dict>
+</plist>
+"#,
+        LAUNCHD_LABEL, arguments, interval
+    )
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{launchd_plist, systemd_units};
+    use std::path::Path;
+
+    #[test]
+    fn generating_units() {
+        let args = ["export", "--target", "zip", "-d", "/my backups"].map(String::from);
+        let (service, timer) = systemd_units(Path::new("/usr/bin/jn"), &args, "weekly");
+        assert!(service.contains("ExecStart=/usr/bin/jn export --target zip -d \"/my backups\"\n"));
+        assert!(timer.contains("OnCalendar=weekly\n"));
+
+        let plist = launchd_plist(Path::new("/usr/bin/jn"), &args, "hourly");
+        assert!(plist.contains("<string>/my backups</string>"));
+        assert!(plist.contains("<integer>3600</integer>"));
+    }
+}
@@ -1,23 +1,32 @@
 use crate::{
-    format::Output,
+    cancel,
+    format::{ExportedJournal, Output},
     fs::FileEntry,
-    types::{Journal, Workspaces},
-    util::get_date,
+    types::Workspaces,
 };
 use anyhow::Result;
 use crossterm::style::Stylize;
-use std::{fs::OpenOptions, io::Write};
-
-pub fn export(dir: Option<String>, ws: Workspaces, key: Option<String>) -> Result<Output> {
+use std::fs::{self, OpenOptions};
+use std::io::{BufWriter, Write};
+
+/// Exports the journals to a zip-archive in `dir`, named by `date`. If it already exists,
+/// the user is asked whether to replace it, unless `interactive` is false.
+pub fn export(
+    dir: Option<String>,
+    ws: Workspaces,
+    key: Option<String>,
+    date: &str,
+    interactive: bool,
+) -> Result<Output> {
     let dir = match dir {
         Some(dir) => FileEntry::from(dir.as_str()),
         None => FileEntry::from("."),
     };
 
-    let filename = format!("journals.{}.zip", get_date());
+    let filename = format!("journals.{}.zip", date);
     let filepath = dir.push(&filename);
 
-    if filepath.exists() {
+    if filepath.exists() && interactive {
         let msg = format!(
             "Journals already exported at {}. Do you want to replace it?",
             filepath.to_string().green()
@@ -27,53 +36,47 @@ pub fn export(dir: Option<String>, ws: Workspaces, key: Option<String>) -> Resul
         }
     }
 
-    let zipfile_name = format!("{}", filepath);
-    let mut file = OpenOptions::new()
+    let zipfile_name = filepath.path();
+    let file = OpenOptions::new()
         .create(true)
         .write(true)
         .truncate(true)
         .open(zipfile_name)?;
 
-    let mut zip = zip::ZipWriter::new(&mut file);
+    // Headers of entries are written in small parts, so these are buffered
+    let mut zip = zip::ZipWriter::new(BufWriter::new(file));
     let options =
         zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
 
-    let mut exported: Vec<String> = Vec::new();
-    let mut skipped: Vec<String> = Vec::new();
+    let mut exported: Vec<ExportedJournal> = Vec::new();
+    let mut skipped: Vec<ExportedJournal> = Vec::new();
 
     for (ws_name, ws) in ws {
         zip.add_directory(&ws_name, options)?;
 
         for file_entry in ws.files {
-            let filename = format!("{}/{}", ws_name, file_entry.filename());
-            zip.start_file(&filename, options)?;
-
-            let journal = match Journal::open(&file_entry, key.clone()) {
-                Ok(journal) => journal,
-                Err(_) => {
-                    skipped.push(filename);
-                    continue;
-                }
-            };
+            // A partial zip file would look like a complete export
+            if cancel::requested() {
+                drop(zip);
+                fs::remove_file(zipfile_name)?;
+                return Ok(Output::empty_export());
+            }
 
-            match journal.bytes() {
-                Ok(bytes) => {
-                    exported.push(filename);
+            let filename = format!("{}/{}", ws_name, file_entry.filename());
+            match super::read(&filename, &file_entry, key.clone(), false) {
+                (encrypted, Ok(bytes)) => {
+                    zip.start_file(&filename, options)?;
                     zip.write_all(&bytes)?;
+                    exported.push(ExportedJournal::exported(&filename, encrypted, &bytes)?);
                 }
-                Err(_) => {
-                    skipped.push(filename);
-                }
+                (_, Err(journal)) => skipped.push(journal),
             }
         }
     }
 
-    zip.finish()?;
+    zip.finish()?.flush()?;
 
-    Ok(Output::ExportResult {
-        exported,
-        skipped: vec![],
-    })
+    Ok(Output::ExportResult { exported, skipped })
 }
 
 #[cfg(test)]
@@ -117,7 +120,10 @@ mod tests {
         workspaces.insert("testdata".to_string(), workspace);
 
         // Act
-        export(Some(fx.dirstr()), workspaces, None)?;
+        export(Some(fx.dirstr()), workspaces, None, "2024-06-05", false)?;
+
+        // Assert
+        assert!(fx.dir.join("journals.2024-06-05.zip").exists());
 
         Ok(())
     }
@@ -1,17 +1,187 @@
-use crate::fs::FileEntry;
-use crossterm::style::Stylize;
+use crate::config::{Appearance, Style};
+use crate::diff::{Edit, Hunk};
+use crate::fs::digest;
+use crate::types::{
+    Conflict, Group, JournalRow, ListedJournal, MoodPeriod, PastJournal, PlannedChange,
+    Replacement, ReviewJournal, SearchResult, SearchStats, WordCount, WordStats, WorkspaceMeta,
+    WorkspaceStatus,
+};
+use anyhow::Result;
+use chrono::Datelike;
+use crossterm::style::{Color, Stylize};
+use serde::Serialize;
 
 /// Output represents things that can be presented to the
 /// user in various formats.
 pub enum Output {
-    /// List of shallow representations of journals.
-    WorkspaceJournals(String, Vec<FileEntry>),
+    /// List of shallow representations of journals,
+    /// numbered from 1 in the order listed.
+    WorkspaceJournals(String, WorkspaceMeta, Vec<ListedJournal>),
+    /// Workspaces with their metadata and number of journals,
+    /// if the journals could be listed.
+    Workspaces(Vec<(String, WorkspaceMeta, Option<usize>)>),
+    /// Journals with lines matching a search, possibly limited,
+    /// along with the total number of matches and journals.
+    SearchResults {
+        results: Vec<SearchResult>,
+        total_matches: usize,
+        total_journals: usize,
+    },
+    /// Journals as rows of values separated by `delimiter`, with a header.
+    JournalTable {
+        delimiter: char,
+        rows: Vec<JournalRow>,
+    },
+    /// Open to-dos in journals.
+    Todos(Vec<SearchResult>),
+    /// Statistics of a search.
+    SearchStats(SearchStats),
+    /// Journals not opened in a while, least recently opened first.
+    Review(Vec<ReviewJournal>),
+    /// The average mood per period, oldest first.
+    MoodReport(Vec<MoodPeriod>),
+    /// The number of journals in workspaces, and their total size in bytes
+    /// if `sizes` is set, followed by the totals of all workspaces.
+    JournalCounts {
+        workspaces: Vec<(String, usize, u64)>,
+        sizes: bool,
+    },
+    /// SHA-256 digests and paths, as printed by `sha256sum`.
+    Checksums(Vec<(String, String)>),
+    /// Journals with the same content, in groups, and pairs of journals
+    /// with similar content along with how similar they are from 0 to 1.
+    Duplicates {
+        exact: Vec<Vec<String>>,
+        near: Vec<(String, String, f64)>,
+    },
+    /// Journals from the same day as `today` in earlier years or months, oldest first.
+    OnThisDay {
+        today: chrono::NaiveDate,
+        journals: Vec<PastJournal>,
+    },
+    /// Lines, words and characters of journals, followed by their totals if `total` is set.
+    WordCounts { counts: Vec<WordCount>, total: bool },
+    /// Numbers of journals and words, only those of today if `today` is set.
+    WordStats { stats: WordStats, today: bool },
+    /// Journals counted by workspace, extension or month, as named by `by`,
+    /// followed by the totals.
+    Groups { by: String, groups: Vec<Group> },
+    /// Conflicting copies of journals and the journals they are copies of.
+    Conflicts(Vec<Conflict>),
+    /// A unified diff of two journals.
+    Diff {
+        old: String,
+        new: String,
+        hunks: Vec<Hunk>,
+    },
+    /// The number of lines changed between two journals.
+    DiffStat {
+        old: String,
+        new: String,
+        insertions: usize,
+        deletions: usize,
+    },
+    /// Journals created, or that would be created, by splitting a journal,
+    /// with their number of lines.
+    Split {
+        journal: String,
+        created: Vec<(String, usize)>,
+        dry_run: bool,
+    },
+    /// The changes a command would make with `--dry-run`, followed by the
+    /// bytes they would remove and write.
+    DryRun(Vec<PlannedChange>),
+    /// A preview of the lines changed by a replacement.
+    ReplacePreview(Vec<Replacement>),
     /// The result of performing an export.
     ExportResult {
-        exported: Vec<String>,
+        exported: Vec<ExportedJournal>,
+        skipped: Vec<ExportedJournal>,
+    },
+    /// A journal, as {workspace}/{journal}, or a workspace that was created.
+    Created(String),
+    /// A journal or workspace that was removed.
+    Removed(String),
+    /// A journal or workspace that was renamed.
+    Renamed { from: String, to: String },
+    /// A shared journal, and the passphrase it's encrypted with
+    /// unless it's encrypted to an age recipient.
+    Shared {
+        file: String,
+        passphrase: Option<String>,
+    },
+    /// A generated key, or where it was stored instead of printing it.
+    GeneratedKey {
+        key: Option<String>,
+        stored: Option<String>,
+    },
+    /// Encryption status of workspaces.
+    WorkspaceStatus(Vec<WorkspaceStatus>),
+    /// A journal or workspace that was restored from a backup.
+    Restored(String),
+    /// No journal with the name in the workspace.
+    NothingFound { workspace: String, name: String },
+    /// Files that were written, e.g. by applying a bundle, and existing
+    /// files that were skipped, as {workspace}/{file}.
+    Files {
+        title: &'static str,
+        files: Vec<String>,
         skipped: Vec<String>,
     },
 }
+
+/// How a journal was handled by an export.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportStatus {
+    Exported,
+    /// Encrypted, and no key or the wrong key was given.
+    SkippedEncrypted,
+    /// Unchanged since the last export to a remote target.
+    SkippedUnchanged,
+    /// Not text, for targets that render the content.
+    SkippedNotText,
+    /// Failed to read, write or upload.
+    FailedIo,
+}
+
+/// A journal included in, or skipped by, an export.
+#[derive(Serialize)]
+pub struct ExportedJournal {
+    /// Path of the journal in the export, i.e. {workspace}/{journal}.
+    pub name: String,
+    pub encrypted: bool,
+    pub status: ExportStatus,
+    /// SHA-256 of the exported content.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    /// Why the journal was skipped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl ExportedJournal {
+    pub fn exported(name: &str, encrypted: bool, content: &[u8]) -> Result<Self> {
+        Ok(Self {
+            name: name.to_string(),
+            encrypted,
+            status: ExportStatus::Exported,
+            digest: Some(digest(content)?),
+            reason: None,
+        })
+    }
+
+    pub fn skipped(name: &str, encrypted: bool, status: ExportStatus, reason: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            encrypted,
+            status,
+            digest: None,
+            reason: Some(reason.to_string()),
+        }
+    }
+}
+
 impl Output {
     pub(crate) fn empty_export() -> Output {
         Self::ExportResult {
@@ -21,17 +191,569 @@ impl Output {
     }
 }
 
-pub struct TextFormatter {}
+pub struct TextFormatter {
+    appearance: Appearance,
+}
 
 impl TextFormatter {
+    pub fn new(appearance: Appearance) -> Self {
+        Self { appearance }
+    }
+
     pub fn format(&self, output: Output) -> String {
         match output {
-            Output::WorkspaceJournals(wrk_sp, entries) => {
+            Output::WorkspaceJournals(wrk_sp, meta, entries) => {
                 let lines: Vec<String> = entries
                     .iter()
-                    .map(|entry| format!("    {}", entry.filename()))
+                    .enumerate()
+                    .map(|(i, journal)| {
+                        let index = format!("{:>4}", i + 1);
+                        format!("{} {}", index.dark_grey(), self.journal_name(journal))
+                    })
+                    .collect();
+                let mut header = format!("{}/", self.workspace_name(&wrk_sp, &meta));
+                if let Some(description) = &meta.description {
+                    header = format!("{} {}", header, description.as_str().dark_grey());
+                }
+                format!("{}\n{}", header, lines.join("\n"))
+            }
+            Output::Workspaces(workspaces) => {
+                let lines: Vec<String> = workspaces
+                    .iter()
+                    .map(|(name, meta, count)| {
+                        let count = match count {
+                            Some(count) => format!("{} journals", count),
+                            None => "encrypted filenames".to_string(),
+                        };
+                        let mut line = format!("{} ({})", self.workspace_name(name, meta), count);
+                        if let Some(created) = &meta.created {
+                            line = format!("{} created {}", line, created);
+                        }
+                        if let Some(description) = &meta.description {
+                            line = format!("{}\n    {}", line, description.as_str().dark_grey());
+                        }
+                        line
+                    })
+                    .collect();
+                lines.join("\n")
+            }
+            Output::WorkspaceStatus(statuses) => {
+                let mut lines: Vec<String> = Vec::new();
+                for status in statuses {
+                    let counts = match status.counts {
+                        Some((encrypted, plaintext)) => {
+                            format!("{} encrypted, {} plaintext", encrypted, plaintext)
+                        }
+                        None => "encrypted filenames, no key opens the index".to_string(),
+                    };
+                    lines.push(format!("{} {}", status.name.as_str().bold(), counts));
+
+                    let has_encrypted = matches!(status.counts, Some((n, _)) if n > 0);
+                    for (i, count) in status.keys.iter().enumerate() {
+                        if has_encrypted {
+                            let line = format!("    key {} decrypts {}", i + 1, count);
+                            lines.push(line.dark_grey().to_string());
+                        }
+                    }
+                    if !status.keys.is_empty() && status.locked > 0 {
+                        let line = format!("    {} not decrypted by any key", status.locked);
+                        lines.push(line.yellow().to_string());
+                    }
+                    for name in &status.plaintext_flagged {
+                        let line = format!("    plaintext in encrypted workspace: {}", name);
+                        lines.push(line.red().to_string());
+                    }
+                }
+                lines.join("\n")
+            }
+            Output::SearchResults {
+                results,
+                total_matches,
+                total_journals,
+            } => {
+                let mut lines: Vec<String> = Vec::new();
+                let shown: usize = results.iter().map(|r| r.matches.len()).sum();
+
+                for result in results {
+                    lines.push(format!(
+                        "{}/{}",
+                        result.workspace.bold().magenta(),
+                        result.journal.bold().magenta()
+                    ));
+
+                    for m in result.matches {
+                        let linenum = format!("{}", m.line);
+                        lines.push(format!("{}: {}", linenum.green(), m.text));
+                    }
+                }
+
+                let mut totals = format!(
+                    "{} in {}",
+                    plural(total_matches, "match", "matches"),
+                    plural(total_journals, "journal", "journals")
+                );
+                if shown < total_matches {
+                    totals.push_str(&format!(" (showing {})", shown));
+                }
+                lines.push(totals.dark_grey().to_string());
+
+                lines.join("\n")
+            }
+            Output::JournalTable { delimiter, rows } => {
+                let header = [
+                    "workspace",
+                    "name",
+                    "size",
+                    "modified",
+                    "encrypted",
+                    "words",
+                    "tags",
+                ];
+                let mut lines = vec![header.join(&delimiter.to_string())];
+
+                for row in rows {
+                    let values = [
+                        row.workspace,
+                        row.name,
+                        row.size.to_string(),
+                        row.modified,
+                        row.encrypted.to_string(),
+                        row.words.map(|w| w.to_string()).unwrap_or_default(),
+                        row.tags.join(";"),
+                    ];
+                    let values: Vec<String> = values
+                        .iter()
+                        .map(|v| delimited_value(v, delimiter))
+                        .collect();
+                    lines.push(values.join(&delimiter.to_string()));
+                }
+
+                lines.join("\n")
+            }
+            Output::Todos(results) => {
+                let mut lines: Vec<String> = Vec::new();
+
+                for result in results {
+                    lines.push(format!(
+                        "{}/{}",
+                        result.workspace.bold().magenta(),
+                        result.journal.bold().magenta()
+                    ));
+
+                    for m in result.matches {
+                        let linenum = format!("{}", m.line);
+                        lines.push(format!("{}: {}", linenum.green(), m.text));
+                    }
+                }
+
+                lines.join("\n")
+            }
+            Output::JournalCounts { workspaces, sizes } => {
+                let total = (
+                    "total".to_string(),
+                    workspaces.iter().map(|(_, count, _)| count).sum(),
+                    workspaces.iter().map(|(_, _, size)| size).sum(),
+                );
+                // Not styled, so that it's easy to use in scripts
+                let lines: Vec<String> = workspaces
+                    .iter()
+                    .chain(std::iter::once(&total))
+                    .map(|(name, count, size)| {
+                        if sizes {
+                            format!(
+                                "{} {}, {}",
+                                name,
+                                plural(*count, "journal", "journals"),
+                                human_size(*size)
+                            )
+                        } else {
+                            format!("{} {}", name, count)
+                        }
+                    })
+                    .collect();
+                lines.join("\n")
+            }
+            Output::OnThisDay { today, journals } => {
+                let mut lines: Vec<String> = Vec::new();
+                for journal in journals {
+                    let months = (today.year() - journal.date.year()) * 12 + today.month() as i32
+                        - journal.date.month() as i32;
+                    let ago = if months % 12 == 0 {
+                        plural(months as usize / 12, "year", "years")
+                    } else {
+                        plural(months as usize, "month", "months")
+                    };
+                    lines.push(format!(
+                        "{} {}",
+                        format!("{}/{}", journal.workspace, journal.name)
+                            .bold()
+                            .magenta(),
+                        format!("{}, {} ago", journal.date, ago).dark_grey()
+                    ));
+                    match &journal.content {
+                        Some(content) => lines.push(content.trim_end().to_string()),
+                        None if journal.sensitive => lines.push(
+                            "(sensitive, use --reveal to print it)"
+                                .dark_grey()
+                                .to_string(),
+                        ),
+                        None => lines.push("(encrypted)".dark_grey().to_string()),
+                    }
+                    lines.push(String::new());
+                }
+                lines.pop();
+                lines.join("\n")
+            }
+            Output::WordCounts { mut counts, total } => {
+                if total {
+                    counts.push(WordCount {
+                        name: "total".to_string(),
+                        lines: counts.iter().map(|c| c.lines).sum(),
+                        words: counts.iter().map(|c| c.words).sum(),
+                        chars: counts.iter().map(|c| c.chars).sum(),
+                    });
+                }
+                let width = |count: fn(&WordCount) -> usize| {
+                    counts
+                        .iter()
+                        .map(|c| count(c).to_string().len())
+                        .max()
+                        .unwrap_or_default()
+                };
+                let (lines, words, chars) =
+                    (width(|c| c.lines), width(|c| c.words), width(|c| c.chars));
+
+                // Not styled, so that it's easy to use in scripts, like `wc`
+                let rows: Vec<String> = counts
+                    .iter()
+                    .map(|c| {
+                        format!(
+                            "{:>lines$} {:>words$} {:>chars$} {}",
+                            c.lines, c.words, c.chars, c.name
+                        )
+                    })
+                    .collect();
+                rows.join("\n")
+            }
+            Output::WordStats { stats, today } => {
+                let words = match stats.goal {
+                    Some(goal) if stats.today_words >= goal => {
+                        format!("{}/{} words", stats.today_words, goal)
+                            .green()
+                            .to_string()
+                    }
+                    Some(goal) => format!("{}/{} words", stats.today_words, goal),
+                    None => plural(stats.today_words, "word", "words"),
+                };
+                let written = format!(
+                    "{} today, in {}",
+                    words,
+                    plural(stats.today_journals, "journal", "journals")
+                );
+                if today {
+                    return written;
+                }
+                format!(
+                    "{}, {}\n{}",
+                    plural(stats.journals, "journal", "journals"),
+                    plural(stats.words, "word", "words"),
+                    written
+                )
+            }
+            Output::Groups { by, groups } => {
+                let total = Group {
+                    name: "total".to_string(),
+                    journals: groups.iter().map(|g| g.journals).sum(),
+                    size: groups.iter().map(|g| g.size).sum(),
+                    matches: groups.iter().map(|g| g.matches).sum(),
+                };
+                let width = groups
+                    .iter()
+                    .chain(std::iter::once(&total))
+                    .map(|g| g.name.chars().count())
+                    .max()
+                    .unwrap_or_default()
+                    .max(by.len());
+
+                let mut header = format!("{:<width$}  {:>8}", by, "journals");
+                if total.size.is_some() {
+                    header.push_str(&format!("  {:>9}", "size"));
+                }
+                if total.matches.is_some() {
+                    header.push_str(&format!("  {:>7}", "matches"));
+                }
+
+                let mut lines = vec![header.bold().to_string()];
+                for group in groups.iter().chain(std::iter::once(&total)) {
+                    let mut line = format!("{:<width$}  {:>8}", group.name, group.journals);
+                    if let Some(size) = group.size {
+                        line.push_str(&format!("  {:>9}", human_size(size)));
+                    }
+                    if let Some(matches) = group.matches {
+                        line.push_str(&format!("  {:>7}", matches));
+                    }
+                    lines.push(line);
+                }
+                lines.join("\n")
+            }
+            Output::Checksums(digests) => {
+                // Not styled, so that it can be checked by `sha256sum -c`
+                let lines: Vec<String> = digests
+                    .iter()
+                    .map(|(digest, path)| format!("{}  {}", digest, path))
+                    .collect();
+                lines.join("\n")
+            }
+            Output::Duplicates { exact, near } => {
+                let mut lines: Vec<String> = Vec::new();
+
+                if !exact.is_empty() {
+                    lines.push("Exact duplicates:".to_string());
+                    for group in exact {
+                        let names: Vec<String> = group
+                            .iter()
+                            .map(|n| n.as_str().blue().to_string())
+                            .collect();
+                        lines.push(format!("  {}", names.join(", ")));
+                    }
+                }
+                if !near.is_empty() {
+                    lines.push("Near duplicates:".to_string());
+                    for (a, b, similarity) in near {
+                        let percent = format!("{:.0}%", similarity * 100.0);
+                        lines.push(format!(
+                            "  {} ~ {} {}",
+                            a.as_str().blue(),
+                            b.as_str().blue(),
+                            percent.dark_grey()
+                        ));
+                    }
+                }
+
+                lines.join("\n")
+            }
+            Output::Conflicts(conflicts) => {
+                let mut lines = vec!["Conflicts:".to_string()];
+                for conflict in conflicts {
+                    let changes = match conflict.changes {
+                        Some((insertions, deletions)) => format!(
+                            "{} {}",
+                            format!("+{}", insertions).green(),
+                            format!("-{}", deletions).red()
+                        ),
+                        None => "encrypted".dark_grey().to_string(),
+                    };
+                    lines.push(format!(
+                        "  {} => {} {}",
+                        conflict.copy.as_str().blue(),
+                        conflict.original.as_str().blue(),
+                        changes
+                    ));
+                }
+                lines.join("\n")
+            }
+            Output::Diff { old, new, hunks } => {
+                let mut lines = vec![
+                    format!("--- {}", old).bold().to_string(),
+                    format!("+++ {}", new).bold().to_string(),
+                ];
+
+                for hunk in hunks {
+                    let range = format!(
+                        "@@ -{},{} +{},{} @@",
+                        hunk.old_start, hunk.old_len, hunk.new_start, hunk.new_len
+                    );
+                    lines.push(range.cyan().to_string());
+                    lines.extend(hunk.edits.into_iter().map(|edit| match edit {
+                        Edit::Equal(line) => format!(" {}", line),
+                        Edit::Delete(line) => format!("-{}", line).red().to_string(),
+                        Edit::Insert(line) => format!("+{}", line).green().to_string(),
+                    }));
+                }
+
+                lines.join("\n")
+            }
+            Output::DiffStat {
+                old,
+                new,
+                insertions,
+                deletions,
+            } => {
+                let bar = format!(
+                    "{}{}",
+                    "+".repeat(insertions).green(),
+                    "-".repeat(deletions).red()
+                );
+                format!(
+                    "{} => {} | {} {}\n{}, {}",
+                    old,
+                    new,
+                    insertions + deletions,
+                    bar,
+                    plural(insertions, "insertion(+)", "insertions(+)"),
+                    plural(deletions, "deletion(-)", "deletions(-)")
+                )
+            }
+            Output::Split {
+                journal,
+                created,
+                dry_run,
+            } => {
+                let title = if dry_run {
+                    format!("Splitting {} would create:", journal.bold())
+                } else {
+                    format!("Split {} into:", journal.bold())
+                };
+                let mut lines = vec![title];
+                lines.extend(created.iter().map(|(name, count)| {
+                    let count = plural(*count, "line", "lines");
+                    format!("  {} {}", name.as_str().green(), count.dark_grey())
+                }));
+                lines.join("\n")
+            }
+            Output::Review(journals) => {
+                let lines: Vec<String> = journals
+                    .iter()
+                    .map(|journal| {
+                        let path = format!("{}/{}", journal.workspace, journal.name);
+                        let age = if journal.opened {
+                            format!(
+                                "opened {} ago",
+                                plural(journal.days as usize, "day", "days")
+                            )
+                        } else {
+                            format!(
+                                "never opened, modified {} ago",
+                                plural(journal.days as usize, "day", "days")
+                            )
+                        };
+                        format!("{} {}", path.bold(), age.dark_grey())
+                    })
                     .collect();
-                format!("{}/\n{}", wrk_sp.bold(), lines.join("\n"))
+                lines.join("\n")
+            }
+            Output::SearchStats(stats) => {
+                let mut lines = vec![format!(
+                    "searched {}",
+                    plural(stats.searched, "journal", "journals")
+                )];
+                for (reason, names) in [
+                    ("binary", stats.binary),
+                    ("too large", stats.oversized),
+                    ("not decrypted", stats.undecryptable),
+                ] {
+                    if !names.is_empty() {
+                        lines.push(format!(
+                            "skipped {} ({}): {}",
+                            names.len(),
+                            reason,
+                            names.join(", ")
+                        ));
+                    }
+                }
+
+                lines.join("\n").dark_grey().to_string()
+            }
+            Output::MoodReport(periods) => {
+                let sparkline: String = periods.iter().map(|p| spark(p.average)).collect();
+                let mut lines = vec![sparkline.cyan().to_string()];
+
+                for p in periods {
+                    let bar = format!("{:<5}", "█".repeat(p.average.round() as usize));
+                    lines.push(format!(
+                        "{:<8}  {} {:.1} {}",
+                        p.period,
+                        bar.cyan(),
+                        p.average,
+                        format!("({})", plural(p.entries, "entry", "entries")).dark_grey()
+                    ));
+                }
+
+                lines.join("\n")
+            }
+            Output::ReplacePreview(replacements) => {
+                let mut lines: Vec<String> = Vec::new();
+
+                for replacement in replacements {
+                    lines.push(format!(
+                        "{}/{}",
+                        replacement.workspace.bold().magenta(),
+                        replacement.journal.bold().magenta()
+                    ));
+
+                    for change in replacement.changes {
+                        let linenum = format!("{}", change.line).green();
+                        lines.push(format!(
+                            "{}: {}",
+                            linenum,
+                            format!("- {}", change.old).red()
+                        ));
+                        lines.push(format!(
+                            "{}: {}",
+                            linenum,
+                            format!("+ {}", change.new).green()
+                        ));
+                    }
+                }
+
+                lines.join("\n")
+            }
+            Output::DryRun(changes) => {
+                let (mut removed, mut written) = (0, 0);
+                // Padded before styling, since styles don't pad
+                let action = |name: &str| format!("{:<8}", name);
+                let mut lines: Vec<String> = changes
+                    .iter()
+                    .map(|change| match change {
+                        PlannedChange::Remove { path, files, bytes } => {
+                            removed += bytes;
+                            let size = if path.ends_with('/') {
+                                format!(
+                                    "({}, {})",
+                                    plural(*files, "file", "files"),
+                                    human_size(*bytes)
+                                )
+                            } else {
+                                format!("({})", human_size(*bytes))
+                            };
+                            format!("{}{} {}", action("remove").red(), path, size.dark_grey())
+                        }
+                        PlannedChange::Rename { from, to } => {
+                            format!(
+                                "{}{} => {}",
+                                action("rename").yellow(),
+                                from,
+                                to.as_str().bold()
+                            )
+                        }
+                        PlannedChange::Write {
+                            path,
+                            bytes,
+                            replaced,
+                        } => {
+                            written += bytes;
+                            let (name, size) = match replaced {
+                                Some(old) => (
+                                    "rewrite",
+                                    format!("({}, was {})", human_size(*bytes), human_size(*old)),
+                                ),
+                                None => ("write", format!("({})", human_size(*bytes))),
+                            };
+                            format!("{}{} {}", action(name).green(), path, size.dark_grey())
+                        }
+                        PlannedChange::Skip { path } => {
+                            format!("{}{}", action("skip").dark_grey(), path)
+                        }
+                    })
+                    .collect();
+
+                let summary = format!(
+                    "dry run, nothing was changed: {} removed, {} written",
+                    human_size(removed),
+                    human_size(written)
+                );
+                lines.push(summary.dark_grey().to_string());
+                lines.join("\n")
             }
             Output::ExportResult {
                 exported: synced,
@@ -39,11 +761,21 @@ impl TextFormatter {
             } => {
                 let synced: Vec<String> = synced
                     .iter()
-                    .map(|entry| format!("  {}", entry.to_string().green()))
+                    .map(|entry| {
+                        let name = entry.name.as_str().green().to_string();
+                        format!("  {}", self.mark_encrypted(name, entry.encrypted))
+                    })
                     .collect();
                 let skipped: Vec<String> = skipped
                     .iter()
-                    .map(|entry| format!("  {} ", entry.to_string().blue()))
+                    .map(|entry| {
+                        let name = entry.name.as_str().blue().to_string();
+                        let name = self.mark_encrypted(name, entry.encrypted);
+                        match &entry.reason {
+                            Some(reason) => format!("  {} {}", name, reason.as_str().dark_grey()),
+                            None => format!("  {}", name),
+                        }
+                    })
                     .collect();
 
                 let mut lines: Vec<String> = Vec::new();
@@ -59,6 +791,154 @@ impl TextFormatter {
 
                 lines.join("\n")
             }
+            Output::Created(path) => format!("Created {}", path.green()),
+            Output::Removed(path) => format!("Removed {}", path.green()),
+            Output::Renamed { from, to } => format!("Renamed {} to {}", from, to.green()),
+            Output::Shared { file, passphrase } => match passphrase {
+                Some(passphrase) => format!(
+                    "Shared in {}\nPassphrase: {}",
+                    file.as_str().green(),
+                    passphrase.as_str().bold()
+                ),
+                None => format!("Shared in {}", file.as_str().green()),
+            },
+            Output::GeneratedKey { key, stored } => match (key, stored) {
+                (Some(key), _) => key.to_string(),
+                (None, Some(stored)) => format!("Key stored in {}", stored.as_str().green()),
+                (None, None) => String::new(),
+            },
+            Output::Restored(path) => format!("Restored {}", path.green()),
+            Output::NothingFound { workspace, name } => {
+                format!("no journal named '{}' in workspace '{}'", name, workspace)
+            }
+            Output::Files {
+                title,
+                files,
+                skipped,
+            } => {
+                let mut lines: Vec<String> = Vec::new();
+
+                if !files.is_empty() {
+                    lines.push(format!("{}:", title));
+                    lines.extend(files.iter().map(|f| format!("  {}", f.as_str().green())));
+                }
+                if !skipped.is_empty() {
+                    lines.push("Skipped existing files:".to_string());
+                    lines.extend(skipped.iter().map(|f| format!("  {}", f.as_str().blue())));
+                }
+
+                lines.join("\n")
+            }
+        }
+    }
+}
+
+impl TextFormatter {
+    /// The journal name, styled by the appearance of its extension
+    /// and marked if encrypted.
+    fn journal_name(&self, journal: &ListedJournal) -> String {
+        let style = journal.entry.extension().and_then(|ext| {
+            self.appearance
+                .extensions
+                .as_ref()
+                .and_then(|styles| styles.get(&ext))
+        });
+
+        let name = styled(&journal.entry.filename(), style, false);
+        self.mark_encrypted(name, journal.encrypted)
+    }
+
+    fn mark_encrypted(&self, name: String, encrypted: bool) -> String {
+        let icon = self.appearance.encrypted_icon();
+        if encrypted && !icon.is_empty() {
+            format!("{} {}", name, icon)
+        } else {
+            name
         }
     }
+
+    /// The workspace name, styled by the icon and color of its metadata,
+    /// else by the appearance in the config.
+    fn workspace_name(&self, name: &str, meta: &WorkspaceMeta) -> String {
+        let configured = self
+            .appearance
+            .workspaces
+            .as_ref()
+            .and_then(|styles| styles.get(name));
+
+        let style = Style {
+            color: meta
+                .color
+                .clone()
+                .or(configured.and_then(|s| s.color.clone())),
+            icon: meta
+                .icon
+                .clone()
+                .or(configured.and_then(|s| s.icon.clone())),
+        };
+        styled(name, Some(&style), true)
+    }
+}
+
+/// Escapes a value for CSV, quoting if needed, or TSV, where tabs and newlines are replaced.
+fn delimited_value(value: &str, delimiter: char) -> String {
+    if delimiter == '\t' {
+        return value.replace(['\t', '\n', '\r'], " ");
+    }
+
+    if value.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// A character showing a mood from 1 to 5 in a sparkline.
+fn spark(mood: f64) -> char {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let i = ((mood - 1.0) / 4.0 * 7.0).round().clamp(0.0, 7.0) as usize;
+    BARS[i]
+}
+
+/// A size in bytes in the largest unit that keeps it at least 1, e.g. 1.5 MB.
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+fn plural(count: usize, singular: &str, plural: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, singular)
+    } else {
+        format!("{} {}", count, plural)
+    }
+}
+
+fn styled(name: &str, style: Option<&Style>, bold: bool) -> String {
+    let color = style
+        .and_then(|s| s.color.as_deref())
+        .and_then(|c| Color::try_from(c).ok());
+
+    let mut text = name.stylize();
+    if let Some(color) = color {
+        text = text.with(color);
+    }
+    if bold {
+        text = text.bold();
+    }
+
+    match style.and_then(|s| s.icon.as_ref()) {
+        Some(icon) => format!("{} {}", icon, text),
+        None => text.to_string(),
+    }
 }
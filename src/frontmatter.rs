@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+const DELIMITER: &str = "---";
+
+/// Returns the lines of the front matter in `content`, i.e. the
+/// `key: value` lines between two `---` lines at the start of it,
+/// or `None` if it doesn't start with front matter.
+fn front_matter_lines(content: &str) -> Option<Vec<&str>> {
+    let mut lines = content.lines();
+    if lines.next()?.trim_end() != DELIMITER {
+        return None;
+    }
+
+    let mut fields = Vec::new();
+    for line in lines {
+        if line.trim_end() == DELIMITER {
+            return Some(fields);
+        }
+        fields.push(line);
+    }
+
+    // Not closed, so it's not front matter
+    None
+}
+
+/// Parses the fields of the front matter in `content`.
+pub fn parse(content: &str) -> HashMap<String, String> {
+    front_matter_lines(content)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// The content after the front matter, or all of it if there is none.
+pub fn body(content: &str) -> &str {
+    match front_matter_lines(content) {
+        Some(fields) => {
+            let len: usize = content
+                .split_inclusive('\n')
+                .take(fields.len() + 2)
+                .map(|line| line.len())
+                .sum();
+            &content[len..]
+        }
+        None => content,
+    }
+}
+
+/// Sets the field `key` in the front matter of `content`,
+/// adding the front matter if there is none.
+pub fn set(content: &str, key: &str, value: &str) -> String {
+    let field = format!("{}: {}", key, value);
+
+    let fields = match front_matter_lines(content) {
+        Some(fields) => fields,
+        None => return format!("{}\n{}\n{}\n{}", DELIMITER, field, DELIMITER, content),
+    };
+
+    let mut lines: Vec<String> = Vec::with_capacity(fields.len() + 1);
+    let mut replaced = false;
+    for line in &fields {
+        match line.split_once(':') {
+            Some((k, _)) if k.trim() == key => {
+                lines.push(field.clone());
+                replaced = true;
+            }
+            _ => lines.push(line.to_string()),
+        }
+    }
+    if !replaced {
+        lines.push(field);
+    }
+
+    format!(
+        "{}\n{}\n{}\n{}",
+        DELIMITER,
+        lines.join("\n"),
+        DELIMITER,
+        body(content)
+    )
+}
+
+/// The tags in the `tags` field of front matter,
+/// given as `tags: a, b` or `tags: [a, b]`.
+pub fn tags(fields: &HashMap<String, String>) -> Vec<String> {
+    let value = match fields.get("tags") {
+        Some(value) => value.trim_start_matches('[').trim_end_matches(']'),
+        None => return vec![],
+    };
+
+    value
+        .split(',')
+        .map(|tag| tag.trim().trim_matches(['"', '\'']).to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+/// Emojis accepted as moods, by score.
+const MOOD_EMOJIS: [(&str, u8); 10] = [
+    ("😢", 1),
+    ("😞", 1),
+    ("🙁", 2),
+    ("😕", 2),
+    ("😐", 3),
+    ("😶", 3),
+    ("🙂", 4),
+    ("😊", 4),
+    ("😄", 5),
+    ("😁", 5),
+];
+
+/// The score from 1 to 5 of a mood, given as a number or an emoji.
+pub fn mood_score(mood: &str) -> Option<u8> {
+    let mood = mood.trim();
+    match mood.parse::<u8>() {
+        Ok(score) if (1..=5).contains(&score) => Some(score),
+        Ok(_) => None,
+        Err(_) => MOOD_EMOJIS
+            .iter()
+            .find(|(emoji, _)| *emoji == mood)
+            .map(|(_, score)| *score),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{body, mood_score, parse, set, tags};
+
+    #[test]
+    fn parsing_front_matter() {
+        let fields = parse("---\ndate: 2024-06-05\nmood: 🙂\n---\n# Notes\nmood: 1\n");
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields["date"], "2024-06-05");
+        assert_eq!(fields["mood"], "🙂");
+
+        assert!(parse("# Notes\nmood: 1\n").is_empty());
+        assert!(parse("---\nmood: 1\n").is_empty());
+    }
+
+    #[test]
+    fn content_after_front_matter() {
+        assert_eq!(body("---\nmood: 1\n---\n# Notes\n"), "# Notes\n");
+        assert_eq!(body("---\nmood: 1\n---"), "");
+        assert_eq!(body("# Notes\n"), "# Notes\n");
+    }
+
+    #[test]
+    fn setting_fields() {
+        assert_eq!(
+            set("# Notes\n", "mood", "4"),
+            "---\nmood: 4\n---\n# Notes\n"
+        );
+        assert_eq!(
+            set("---\nmood: 2\n---\n# Notes\n", "mood", "4"),
+            "---\nmood: 4\n---\n# Notes\n"
+        );
+        assert_eq!(
+            set("---\ndate: 2024-06-05\n---\n\nText", "mood", "4"),
+            "---\ndate: 2024-06-05\nmood: 4\n---\n\nText"
+        );
+    }
+
+    #[test]
+    fn parsing_tags() {
+        assert_eq!(
+            tags(&parse("---\ntags: work, ideas\n---\n")),
+            ["work", "ideas"]
+        );
+        assert_eq!(tags(&parse("---\ntags: [\"a\", b]\n---\n")), ["a", "b"]);
+        assert!(tags(&parse("---\ntags: []\n---\n")).is_empty());
+        assert!(tags(&parse("# No front matter")).is_empty());
+    }
+
+    #[test]
+    fn mood_scores() {
+        assert_eq!(mood_score("4"), Some(4));
+        assert_eq!(mood_score(" 1 "), Some(1));
+        assert_eq!(mood_score("😄"), Some(5));
+        assert_eq!(mood_score("0"), None);
+        assert_eq!(mood_score("6"), None);
+        assert_eq!(mood_score("great"), None);
+    }
+}
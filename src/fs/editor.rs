@@ -1,49 +1,102 @@
 use anyhow::{bail, Result};
 use std::env;
-use std::fs::OpenOptions;
+use std::fs::{self, OpenOptions};
 use std::io::{Read, Write};
-use std::path::Path;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
 
 pub fn try_get_env(name: &str) -> Option<String> {
-    match std::env::var(name) {
-        Ok(v) => Some(v),
-        Err(_) => None,
-    }
+    std::env::var(name).ok()
 }
 
+/// Arguments that open most editors at a line, such as vim, nano and emacs.
+const DEFAULT_LINE_ARGS: &str = "+{line} {file}";
+
+/// Configured to open files in the editor, instead of with a command.
+pub const EDITOR: &str = "editor";
+
+/// Configured to open files with the default application of the system.
+pub const SYSTEM_OPENER: &str = "system";
+
 pub struct Editor {
     editor: String,
+    /// Arguments given in $EDITOR after the program, e.g. --wait.
+    editor_args: Vec<String>,
+    /// Line to open the file at, with the arguments to do so.
+    line: Option<(usize, String)>,
 }
 
 impl Editor {
     pub fn new() -> Self {
-        let editor = if let Some(e) = try_get_env("EDITOR") {
+        let command = if let Some(e) = try_get_env("EDITOR") {
             e
         } else if let Some(e) = try_get_env("VISUAL") {
             e
+        } else if cfg!(windows) {
+            String::from("notepad")
         } else {
             String::from("nano")
         };
 
-        Self { editor }
+        let mut words = split_command(&command).into_iter();
+        let editor = words.next().unwrap_or(command);
+        Self {
+            editor,
+            editor_args: words.collect(),
+            line: None,
+        }
+    }
+
+    /// The name of the editor program, e.g. vim for /usr/bin/vim.
+    pub fn name(&self) -> String {
+        Path::new(&self.editor)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.editor.clone())
+    }
+
+    /// Opens files at `line`, using `args` with {line} and {file} replaced
+    /// as the arguments to the editor, or `+{line} {file}` if not given.
+    pub fn at_line(mut self, line: usize, args: Option<&str>) -> Self {
+        let args = args.unwrap_or(match self.name().as_str() {
+            "code" | "codium" => "--goto {file}:{line}",
+            _ => DEFAULT_LINE_ARGS,
+        });
+        self.line = Some((line, args.to_string()));
+        self
+    }
+
+    fn args(&self, path: &Path) -> Vec<String> {
+        let file = path.display().to_string();
+        match &self.line {
+            Some((line, args)) => args
+                .split_whitespace()
+                .map(|arg| {
+                    arg.replace("{line}", &line.to_string())
+                        .replace("{file}", &file)
+                })
+                .collect(),
+            None => vec![file],
+        }
     }
 
     /// Edit the file at `path`.
     pub fn edit(&self, path: &Path) -> Result<()> {
         let mut cmd = Command::new(&self.editor);
-        cmd.arg(path);
+        cmd.args(&self.editor_args).args(self.args(path));
 
         match cmd.status() {
-            Ok(_) => Ok(()),
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => bail!("editor {} exited with {}", self.editor, status),
             Err(err) => bail!("error editing file {:?}: {}", &path, err),
         }
     }
 
-    /// Edit ...
+    /// Edits `content` in a temporary file named after `filename`, in a
+    /// directory only readable by the user, and returns the edited content.
+    /// Line endings changed to CRLF by the editor are changed back.
     pub fn edit_temp(&self, filename: &str, content: &[u8]) -> Result<Vec<u8>> {
-        let mut path = env::temp_dir();
-        path.push(filename);
+        let path = temp_path(filename)?;
 
         {
             // Write in block so file gets closed
@@ -54,15 +107,206 @@ impl Editor {
             file.write_all(content)?;
         }
 
-        self.edit(&path)?;
-
-        let mut buf = Vec::new();
-        let mut file = OpenOptions::new().read(true).open(&path)?;
-        file.read_to_end(&mut buf)?;
+        let res = self.edit(&path).and_then(|_| {
+            let mut buf = Vec::new();
+            let mut file = OpenOptions::new().read(true).open(&path)?;
+            file.read_to_end(&mut buf)?;
+            Ok(buf)
+        });
 
-        // Remove temporary file.
+        // Remove temporary file, also when editing failed.
         std::fs::remove_file(&path)?;
 
-        Ok(buf)
+        res.map(|edited| restore_line_endings(content, edited))
+    }
+}
+
+/// Opens the file at `path` with `command`, which is given the path as its last
+/// argument, or with the default application if it's `system`. Returns when the
+/// command exits, which the system opener does as soon as it started the application.
+pub fn open_with(command: &str, path: &Path) -> Result<()> {
+    let words = if command == SYSTEM_OPENER {
+        system_opener()
+    } else {
+        split_command(command)
+    };
+    let (program, args) = match words.split_first() {
+        Some(words) => words,
+        None => bail!("no command to open {:?} with", path),
+    };
+
+    match Command::new(program).args(args).arg(path).status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => bail!("{} exited with {}", program, status),
+        Err(err) => bail!("error opening file {:?} with {}: {}", path, program, err),
+    }
+}
+
+/// The command that opens files with their default application.
+fn system_opener() -> Vec<String> {
+    let words: &[&str] = if cfg!(target_os = "macos") {
+        &["open"]
+    } else if cfg!(windows) {
+        // The empty title keeps start from taking a quoted path as the window title
+        &["cmd", "/C", "start", ""]
+    } else {
+        &["xdg-open"]
+    };
+    words.iter().map(|word| word.to_string()).collect()
+}
+
+/// Splits an editor command into words at whitespace, keeping quoted words
+/// together, e.g. `"C:\Program Files\Notepad++\notepad++.exe" -multiInst`.
+/// Backslashes are kept as is since they separate paths on Windows.
+fn split_command(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote: Option<char> = None;
+    let mut in_word = false;
+
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// A path for a temporary file named after `filename`, in a directory only readable by the user.
+pub fn temp_path(filename: &str) -> Result<PathBuf> {
+    let mut path = temp_dir()?;
+    // Prefixed so that simultaneous edits of journals with the same name don't collide,
+    // and flattened for journals in subdirectories
+    path.push(format!("{}-{}", process::id(), filename.replace('/', "-")));
+    Ok(path)
+}
+
+/// The directory for temporary files, which may contain decrypted journals.
+/// On Unix it's $XDG_RUNTIME_DIR if set, else a directory in the system temp
+/// directory created only accessible by the user. On Windows the temp
+/// directory is already per user.
+fn temp_dir() -> Result<PathBuf> {
+    if cfg!(windows) {
+        return Ok(env::temp_dir());
+    }
+    if let Some(dir) = try_get_env("XDG_RUNTIME_DIR").filter(|d| Path::new(d).is_dir()) {
+        return Ok(PathBuf::from(dir));
+    }
+
+    let user = try_get_env("USER").unwrap_or_else(|| "user".to_string());
+    let dir = env::temp_dir().join(format!("jn-{}", user));
+    create_private_dir(&dir)?;
+    Ok(dir)
+}
+
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    if !dir.exists() {
+        fs::DirBuilder::new().mode(0o700).create(dir)?;
+    }
+    // A directory created by someone else with these permissions can't be written to
+    let metadata = fs::symlink_metadata(dir)?;
+    if !metadata.is_dir() || metadata.permissions().mode() & 0o077 != 0 {
+        bail!(
+            "temporary directory {} must only be accessible by you",
+            dir.display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    Ok(())
+}
+
+/// Changes CRLF line endings in `edited` back to LF, unless `original`
+/// had CRLF line endings, since editors such as notepad may change them.
+fn restore_line_endings(original: &[u8], edited: Vec<u8>) -> Vec<u8> {
+    let crlf = |bytes: &[u8]| bytes.windows(2).any(|w| w == b"\r\n");
+    if crlf(original) || !crlf(&edited) {
+        return edited;
+    }
+
+    let mut restored = Vec::with_capacity(edited.len());
+    for (i, byte) in edited.iter().enumerate() {
+        if *byte == b'\r' && edited.get(i + 1) == Some(&b'\n') {
+            continue;
+        }
+        restored.push(*byte);
+    }
+    restored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{restore_line_endings, split_command, Editor};
+    use std::path::Path;
+
+    #[test]
+    fn opening_at_lines() {
+        let editor = |name: &str| Editor {
+            editor: name.to_string(),
+            editor_args: vec![],
+            line: None,
+        };
+        let path = Path::new("/tmp/notes.md");
+
+        assert_eq!(editor("vim").args(path), ["/tmp/notes.md"]);
+        assert_eq!(
+            editor("/usr/bin/vim").at_line(12, None).args(path),
+            ["+12", "/tmp/notes.md"]
+        );
+        assert_eq!(
+            editor("code").at_line(3, None).args(path),
+            ["--goto", "/tmp/notes.md:3"]
+        );
+        assert_eq!(
+            editor("hx").at_line(3, Some("{file}:{line}")).args(path),
+            ["/tmp/notes.md:3"]
+        );
+    }
+
+    #[test]
+    fn splitting_commands() {
+        assert_eq!(split_command("code --wait"), ["code", "--wait"]);
+        assert_eq!(
+            split_command(r#""C:\Program Files\Notepad++\notepad++.exe" -multiInst"#),
+            [r"C:\Program Files\Notepad++\notepad++.exe", "-multiInst"]
+        );
+        assert_eq!(split_command("'my editor'"), ["my editor"]);
+    }
+
+    #[test]
+    fn restoring_line_endings() {
+        assert_eq!(
+            restore_line_endings(b"a\nb\n", b"a\r\nb\r\nc\r\n".to_vec()),
+            b"a\nb\nc\n"
+        );
+        assert_eq!(
+            restore_line_endings(b"a\r\n", b"a\r\nb\r\n".to_vec()),
+            b"a\r\nb\r\n"
+        );
     }
 }
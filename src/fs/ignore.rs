@@ -0,0 +1,132 @@
+use crate::util::glob_match;
+use anyhow::Result;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// Name of the files listing journals to ignore, in the root directory
+/// for all workspaces and in a workspace for only that workspace.
+pub const IGNORE_FILENAME: &str = ".journalignore";
+
+struct Rule {
+    pattern: String,
+    negated: bool,
+    /// Ends with `/`, so only matches directories.
+    directory: bool,
+    /// Starts with or contains `/`, so only matches paths from the top.
+    anchored: bool,
+}
+
+/// Gitignore style patterns of filenames to leave out when listing journals.
+/// Lines are globs where `*` matches any characters and `?` one character,
+/// lines starting with `#` are comments and a leading `!` includes files
+/// ignored by an earlier pattern. Journals in subdirectories are matched by
+/// their path relative to the workspace, or by their filename if the pattern
+/// has no `/`, and patterns ending with `/` match the directories they're in.
+#[derive(Default)]
+pub struct Ignore {
+    rules: Vec<Rule>,
+}
+
+impl Ignore {
+    /// Loads the patterns of the ignore files in `dirs`, in order,
+    /// so that later files can override earlier ones.
+    pub fn load(dirs: &[&Path]) -> Result<Self> {
+        let mut ignore = Self::default();
+        for dir in dirs {
+            match fs::read_to_string(dir.join(IGNORE_FILENAME)) {
+                Ok(content) => ignore.extend(&content),
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(ignore)
+    }
+
+    fn extend(&mut self, content: &str) {
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (negated, pattern) = match line.strip_prefix('!') {
+                Some(pattern) => (true, pattern),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let directory = pattern.ends_with('/');
+            let pattern = pattern.trim_end_matches('/');
+            let anchored = pattern.contains('/');
+            let pattern = pattern.trim_start_matches('/').replace("**", "*");
+            self.rules.push(Rule {
+                pattern,
+                negated,
+                directory,
+                anchored,
+            });
+        }
+    }
+
+    /// Whether the file `name` is ignored, by the last pattern matching it.
+    pub fn is_ignored(&self, name: &str) -> bool {
+        if name == IGNORE_FILENAME {
+            return true;
+        }
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(name))
+            .map(|rule| !rule.negated)
+            .unwrap_or(false)
+    }
+}
+
+impl Rule {
+    fn matches(&self, name: &str) -> bool {
+        let (dirs, filename) = match name.rsplit_once('/') {
+            Some((dirs, filename)) => (dirs, filename),
+            None => ("", name),
+        };
+
+        if self.directory {
+            // Any of the directories the file is in
+            let mut path = String::new();
+            return dirs.split('/').filter(|dir| !dir.is_empty()).any(|dir| {
+                if !path.is_empty() {
+                    path.push('/');
+                }
+                path.push_str(dir);
+                glob_match(&self.pattern, if self.anchored { &path } else { dir })
+            });
+        }
+
+        glob_match(&self.pattern, name) || (!self.anchored && glob_match(&self.pattern, filename))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Ignore;
+
+    #[test]
+    fn ignoring_files() {
+        let mut ignore = Ignore::default();
+        ignore.extend("# editor files\n*.swp\n.DS_Store\n/scratch-*\n\n*.tmp\n!keep.tmp\nbuild/\nprojects/old/\n");
+
+        assert!(ignore.is_ignored(".a.md.swp"));
+        assert!(ignore.is_ignored(".DS_Store"));
+        assert!(ignore.is_ignored("scratch-1.md"));
+        assert!(ignore.is_ignored("x.tmp"));
+        assert!(ignore.is_ignored(".journalignore"));
+
+        assert!(!ignore.is_ignored("keep.tmp"));
+        assert!(!ignore.is_ignored("notes.md"));
+        assert!(!ignore.is_ignored("build"));
+        assert!(ignore.is_ignored("build/notes.md"));
+        assert!(ignore.is_ignored("projects/old/notes.md"));
+        assert!(ignore.is_ignored("projects/x.swp"));
+        assert!(!ignore.is_ignored("projects/notes.md"));
+        assert!(!ignore.is_ignored("projects/scratch-1.md"));
+        assert!(!ignore.is_ignored("# editor files"));
+    }
+}
@@ -1,15 +1,21 @@
 pub mod editor;
+pub mod ignore;
 pub use editor::Editor;
+pub use ignore::{Ignore, IGNORE_FILENAME};
 
 use anyhow::{bail, Result};
 use data_encoding::HEXLOWER;
 use ring::digest::{Context, SHA256};
-use std::fs::OpenOptions;
-use std::io::{Read, Write};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
 use std::{fmt, fs};
 
-#[allow(unused)]
+/// Size of the chunks files are read in by `FileEntry::digest`.
+const DIGEST_BUFFER_SIZE: usize = 64 * 1024;
+
 pub fn digest(data: &[u8]) -> Result<String> {
     let mut context = Context::new(&SHA256);
     context.update(data);
@@ -19,29 +25,79 @@ pub fn digest(data: &[u8]) -> Result<String> {
     Ok(s)
 }
 
+/// The files in a directory, and the symlinks in it whose target doesn't exist.
+pub struct Listing {
+    pub files: Vec<FileEntry>,
+    pub broken_links: Vec<FileEntry>,
+}
+
 pub fn list_files(dir: &Path) -> Result<Vec<FileEntry>> {
-    let entries = internal_list_dir(dir)?;
-    let entries = entries.into_iter().filter(|e| !e.is_dir).collect();
-    Ok(entries)
+    Ok(list_files_and_links(dir)?.files)
+}
+
+/// Lists the files in `dir` like `list_files`, along with its broken symlinks.
+pub fn list_files_and_links(dir: &Path) -> Result<Listing> {
+    let (entries, broken_links) = internal_list_dir(dir)?;
+    let files = entries.into_iter().filter(|e| !e.is_dir).collect();
+    Ok(Listing {
+        files,
+        broken_links,
+    })
+}
+
+/// Lists the files in `dir` like `list_files_and_links`, and those of its
+/// subdirectories that aren't hidden, recursively. Files in subdirectories
+/// are named by their path relative to `dir`, e.g. `projects/alpha/notes.md`.
+pub fn list_files_recursive(dir: &Path) -> Result<Listing> {
+    let mut listing = list_files_and_links(dir)?;
+    for subdir in list_dirs(dir)? {
+        if subdir.is_hidden() {
+            continue;
+        }
+
+        let prefix = subdir.filename();
+        let nested = list_files_recursive(subdir.path())?;
+        let relative = |entry: FileEntry| {
+            let name = format!("{}/{}", prefix, entry.filename());
+            entry.with_alias(&name)
+        };
+        listing.files.extend(nested.files.into_iter().map(relative));
+        listing
+            .broken_links
+            .extend(nested.broken_links.into_iter().map(relative));
+    }
+    Ok(listing)
+}
+
+/// The latest modification time of `dir` and its subdirectories that aren't hidden.
+pub fn dirs_modified(dir: &Path) -> Result<SystemTime> {
+    let mut modified = fs::metadata(dir)?.modified()?;
+    for subdir in list_dirs(dir)? {
+        if !subdir.is_hidden() {
+            modified = modified.max(dirs_modified(subdir.path())?);
+        }
+    }
+    Ok(modified)
 }
 
 pub fn list_dirs(dir: &Path) -> Result<Vec<FileEntry>> {
-    let entries = internal_list_dir(dir)?;
+    let (entries, _) = internal_list_dir(dir)?;
     let entries = entries.into_iter().filter(|e| e.is_dir).collect();
     Ok(entries)
 }
 
+/// Reads the file at `path` into a string allocated for its size.
 pub fn read_file(path: &Path) -> Result<String> {
-    let mut buf = String::new();
-    let mut file = OpenOptions::new().read(true).open(path)?;
-    file.read_to_string(&mut buf)?;
-    Ok(buf)
+    Ok(fs::read_to_string(path)?)
 }
 
 #[derive(Clone)]
 pub struct FileEntry {
     filepath: PathBuf,
     is_dir: bool,
+    /// Name shown instead of the real filename, e.g. when
+    /// the filename is encrypted.
+    alias: Option<String>,
 }
 
 impl fmt::Debug for FileEntry {
@@ -55,16 +111,32 @@ impl FileEntry {
         Self {
             filepath: PathBuf::from(path),
             is_dir: path.is_dir(),
+            alias: None,
         }
     }
 
+    /// Whether the name of the entry, or of a directory in a relative name, starts with a dot.
+    pub fn is_hidden(&self) -> bool {
+        self.filename().split('/').any(|name| name.starts_with('.'))
+    }
+
+    /// Displays the entry as `alias` instead of its filename.
+    pub fn with_alias(mut self, alias: &str) -> Self {
+        self.alias = Some(alias.to_string());
+        self
+    }
+
     pub fn extension(&self) -> Option<String> {
-        self.filepath
+        Path::new(&self.filename())
             .extension()
             .map(|ext| ext.to_string_lossy().into())
     }
 
     pub fn filename(&self) -> String {
+        if let Some(alias) = &self.alias {
+            return alias.clone();
+        }
+
         self.filepath
             .file_name()
             .unwrap()
@@ -80,9 +152,25 @@ impl FileEntry {
         self.filepath.exists()
     }
 
+    pub fn modified(&self) -> Result<SystemTime> {
+        let modified = fs::metadata(&self.filepath)?.modified()?;
+        Ok(modified)
+    }
+
+    pub fn size(&self) -> Result<u64> {
+        let size = fs::metadata(&self.filepath)?.len();
+        Ok(size)
+    }
+
+    /// The entry at `path` relative to this one. If it's in a subdirectory,
+    /// it's displayed by the relative path, like entries listed recursively.
     pub fn push(&self, path: &str) -> Self {
         let root = self.filepath.join(path);
-        Self::new(&root)
+        let entry = Self::new(&root);
+        if path.contains('/') {
+            return entry.with_alias(path);
+        }
+        entry
     }
 
     pub fn mkdir(&self) -> Result<()> {
@@ -94,16 +182,48 @@ impl FileEntry {
         Ok(())
     }
 
-    pub fn read_bytes(&self) -> Result<Vec<u8>> {
+    /// Opens the file for reading.
+    pub fn open(&self) -> Result<File> {
         if self.is_dir {
             bail!("cannot read directory");
         }
 
-        let mut buf: Vec<u8> = Vec::new();
-        let mut file = OpenOptions::new().read(true).open(&self.filepath)?;
-        file.read_to_end(&mut buf)?;
+        let file = OpenOptions::new().read(true).open(&self.filepath)?;
+        Ok(file)
+    }
+
+    /// Reads at most the first `n` bytes of the file,
+    /// for when only the header or the start of the content is needed.
+    pub fn read_prefix(&self, n: usize) -> Result<Vec<u8>> {
+        let mut buf: Vec<u8> = Vec::with_capacity(n);
+        self.open()?.take(n as u64).read_to_end(&mut buf)?;
         Ok(buf)
     }
+
+    /// Reads the file into a buffer allocated for its size.
+    pub fn read_bytes(&self) -> Result<Vec<u8>> {
+        if self.is_dir {
+            bail!("cannot read directory");
+        }
+
+        Ok(fs::read(&self.filepath)?)
+    }
+
+    /// SHA-256 of the file, as by `digest`, read in chunks instead of at once.
+    pub fn digest(&self) -> Result<String> {
+        let mut reader = BufReader::with_capacity(DIGEST_BUFFER_SIZE, self.open()?);
+        let mut context = Context::new(&SHA256);
+        loop {
+            let chunk = reader.fill_buf()?;
+            if chunk.is_empty() {
+                break;
+            }
+            context.update(chunk);
+            let len = chunk.len();
+            reader.consume(len);
+        }
+        Ok(HEXLOWER.encode(context.finish().as_ref()))
+    }
 }
 
 impl fmt::Display for FileEntry {
@@ -119,21 +239,41 @@ impl From<&str> for FileEntry {
     }
 }
 
-fn internal_list_dir(dir: &Path) -> Result<Vec<FileEntry>> {
-    let entries = fs::read_dir(dir)?;
-    let entries = entries
-        .into_iter()
-        .filter_map(|entry| match entry {
-            Ok(entry) => Some(entry),
-            Err(_) => None,
-        })
-        .map(|e| {
-            let filepath = e.path();
-            let is_dir = filepath.is_dir();
-            FileEntry { filepath, is_dir }
-        })
-        .collect();
-    Ok(entries)
+/// Lists the entries of `dir`. Symlinks to files are followed, but not those
+/// to directories, which could create cycles. Symlinks whose target doesn't
+/// exist are returned separately.
+fn internal_list_dir(dir: &Path) -> Result<(Vec<FileEntry>, Vec<FileEntry>)> {
+    let mut entries = Vec::new();
+    let mut broken_links = Vec::new();
+
+    for entry in fs::read_dir(dir)?.filter_map(|entry| entry.ok()) {
+        let filepath = entry.path();
+        let file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(_) => continue,
+        };
+
+        let is_dir = if file_type.is_symlink() {
+            match fs::metadata(&filepath) {
+                Ok(metadata) if metadata.is_dir() => continue,
+                Ok(_) => false,
+                Err(_) => {
+                    broken_links.push(FileEntry::new(&filepath));
+                    continue;
+                }
+            }
+        } else {
+            file_type.is_dir()
+        };
+
+        entries.push(FileEntry {
+            filepath,
+            is_dir,
+            alias: None,
+        });
+    }
+
+    Ok((entries, broken_links))
 }
 
 impl AsRef<Path> for FileEntry {
@@ -142,20 +282,34 @@ impl AsRef<Path> for FileEntry {
     }
 }
 
+/// A file in the directory for temporary files, see `editor::temp_path`,
+/// removed when dropped.
 pub struct TempFile {
     file_entry: FileEntry,
 }
 
 impl TempFile {
-    #[allow(unused)]
-    pub fn create(path: &Path, bytes: &[u8]) -> Result<Self> {
-        let mut file = OpenOptions::new().create_new(true).write(true).open(path)?;
+    /// Creates a temporary file with `bytes`, named after `filename` but unique,
+    /// so that it never replaces another file.
+    pub fn create(filename: &str, bytes: &[u8]) -> Result<Self> {
+        static CREATED: AtomicUsize = AtomicUsize::new(0);
+        let unique = format!("{}-{}", CREATED.fetch_add(1, Ordering::Relaxed), filename);
+        let path = editor::temp_path(&unique)?;
+
+        let mut file = OpenOptions::new()
+            .create_new(true)
+            .write(true)
+            .open(&path)?;
         file.write_all(bytes)?;
 
         Ok(Self {
-            file_entry: FileEntry::new(path),
+            file_entry: FileEntry::new(&path),
         })
     }
+
+    pub fn path(&self) -> &Path {
+        self.file_entry.path()
+    }
 }
 
 impl Drop for TempFile {
@@ -165,3 +319,20 @@ impl Drop for TempFile {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::TempFile;
+
+    #[test]
+    fn creating_temp_files() {
+        let a = TempFile::create("manifest.json", b"a").unwrap();
+        let b = TempFile::create("manifest.json", b"b").unwrap();
+        assert_ne!(a.path(), b.path());
+        assert_eq!(std::fs::read(a.path()).unwrap(), b"a");
+
+        let path = a.path().to_path_buf();
+        drop(a);
+        assert!(!path.exists());
+    }
+}
@@ -1,46 +1,223 @@
+use crate::bundle;
+use crate::cancel;
 use crate::cli::{
-    Cli, Command, CreateArgs, ExportArgs, ListArgs, OpenArgs, RemoveArgs, RenameArgs, SearchArgs,
+    AppendArgs, BundleArgs, BundleCommand, ChecksumArgs, Cli, Command, ConfigArgs, ConfigCommand,
+    ConflictsArgs, CreateArgs, DedupeArgs, DiffArgs, EditArgs, ExportArgs, KeygenArgs, ListArgs,
+    LogArgs, MoodArgs, OnThisDayArgs, OpenArgs, RandomArgs, RemindArgs, RemoveArgs, RenameArgs,
+    ReplaceArgs, ReviewArgs, ReviewCommand, ReviewGenerateArgs, SearchArgs, SecretArgs, ServeArgs,
+    ShareArgs, ShareCommand, SplitArgs, StatsArgs, TodoArgs, TuiArgs, UndoArgs, WatchArgs, WcArgs,
+    WorkspaceArgs, WorkspaceCommand, WorkspaceStatusArgs,
 };
-use crate::config::Config;
-use crate::export::zip;
-use crate::format::{Output, TextFormatter};
-use crate::fs::{list_dirs, list_files, FileEntry};
+use crate::config::{Appearance, Config, CreateWorkspaces, SearchScope};
+use crate::conflicts;
+use crate::crypto;
+use crate::dedupe;
+use crate::diff;
+use crate::export::manifest::Manifest;
+use crate::export::report::Report;
+use crate::export::{azure, mirror, pdf, sftp, timer, zip};
+use crate::format::{ExportStatus, Output, TextFormatter};
+use crate::frontmatter;
+use crate::fs::{
+    digest, dirs_modified, editor, list_dirs, list_files_recursive, read_file, Editor, FileEntry,
+    Ignore, TempFile, IGNORE_FILENAME,
+};
+use crate::index::{NameIndex, INDEX_FILENAME};
+use crate::keygen;
+use crate::links;
+use crate::meta::MetaStore;
+use crate::perf;
+use crate::remote::Remote;
+use crate::review;
+use crate::search_cache::SearchCache;
+use crate::secrets;
+use crate::server::{self, Request, Response};
+use crate::setup;
+use crate::share::{self, Secret};
+use crate::split;
+use crate::state::{Change, State};
+use crate::strength::{self, Strength};
 use crate::template;
-use crate::types::{Journal, Workspace, Workspaces};
-use crate::validate::valid_workspace_name;
+use crate::todo;
+use crate::tui::Tui;
+use crate::types::{
+    Conflict, Group, Journal, JournalRow, LineChange, LineMatch, ListedJournal, MoodPeriod,
+    PastJournal, PlannedChange, Replacement, ReviewJournal, SearchResult, SearchStats, WordCount,
+    WordStats, Workspace, WorkspaceMeta, WorkspaceStatus, Workspaces, WORKSPACE_META_FILENAME,
+};
+use crate::util::{self, Zone};
+use crate::validate::{valid_export_target, valid_journal_path, valid_workspace_name};
 use anyhow::{bail, Result};
-use crossterm::style::Stylize;
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate};
+use clap::CommandFactory;
+use crossterm::style::{Color, Stylize};
+use data_encoding::HEXLOWER;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::Deserialize;
 
-use regex::RegexBuilder;
-use std::collections::HashMap;
-use std::io::{stdout, Write};
+use regex::{Regex, RegexBuilder};
+use std::cell::{Cell, OnceCell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{stdin, stdout, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime};
 use std::{env, fs};
 
 type CmdResult = Result<()>;
 
+/// Error returned by commands that exit with a specific status,
+/// e.g. to signal a result to scripts.
+#[derive(Debug)]
+pub struct ExitStatus(pub i32);
+
+impl std::fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "exit status {}", self.0)
+    }
+}
+
+impl std::error::Error for ExitStatus {}
+
+/// How well a filename matches a name, best first.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Rank {
+    /// The filename, or the filename without extension, equals the name.
+    Exact,
+    Prefix,
+    Substring,
+}
+
+impl Rank {
+    /// Ranks `filename` against `name`, ignoring case.
+    fn of(filename: &str, name: &str) -> Option<Self> {
+        let filename = filename.to_lowercase();
+        let name = name.to_lowercase();
+        let stem = match filename.rsplit_once('.') {
+            Some((stem, _)) => stem,
+            None => &filename,
+        };
+
+        if filename == name || stem == name {
+            Some(Rank::Exact)
+        } else if filename.starts_with(&name) {
+            Some(Rank::Prefix)
+        } else if filename.contains(&name) {
+            Some(Rank::Substring)
+        } else {
+            None
+        }
+    }
+}
+
+/// A journal presented in the journal picker, along with when it was modified.
+struct Candidate {
+    entry: FileEntry,
+    /// Workspace shown before the name, when picking among several workspaces.
+    workspace: Option<String>,
+    /// Marker shown if the journal is encrypted.
+    encrypted_icon: Option<String>,
+}
+
+impl std::fmt::Display for Candidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(workspace) = &self.workspace {
+            write!(f, "{}/", workspace)?;
+        }
+        write!(f, "{}", self.entry)?;
+        if let Some(icon) = &self.encrypted_icon {
+            write!(f, " {}", icon)?;
+        }
+
+        if let Ok(modified) = self.entry.modified() {
+            let modified: chrono::DateTime<chrono::Local> = modified.into();
+            write!(f, " ({})", modified.format("%Y-%m-%d %H:%M"))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// How a name given by the user is matched against journal filenames.
+#[derive(Clone, Copy)]
+enum MatchMode {
+    /// The name must equal the filename.
+    Exact,
+    /// The name must be part of the filename, ignoring case.
+    Substring,
+    /// The name is matched fuzzily, best matches first.
+    Fuzzy,
+}
+
 pub struct Handler {
+    /// The configuration file, given by --config or else
+    /// {config_dir}/journal/config.toml
+    config_path: FileEntry,
+    /// The configuration and the directories it determines, loaded when
+    /// the first command is handled.
+    setup: OnceCell<Setup>,
+    /// Journals of the workspaces listed earlier in the session, keyed by
+    /// workspace directory and key, so that REPL and TUI sessions don't
+    /// walk the filesystem for every command.
+    cache: RefCell<HashMap<(PathBuf, Option<String>), CachedJournals>>,
+    /// Only output errors, set by --quiet for each command.
+    quiet: Cell<bool>,
+    /// Include journals whose names start with a dot, set by --hidden.
+    hidden: Cell<bool>,
+    /// Allow encrypting journals with very weak keys, set by --allow-weak-key.
+    allow_weak_key: Cell<bool>,
+}
+
+struct Setup {
     config: Config,
+    /// The root directory, where .journalignore applies to all workspaces.
+    root_dir: FileEntry,
     /// The root directory of all workspaces.
     /// On the filesystem it: {root_dir}/workspaces
     workspaces_dir: FileEntry,
     /// The default workspace directory.
     /// On the filesystem it: {root_dir}/{workspaces_dir}/{default_workspace_dir}
     default_workspace_dir: FileEntry,
+    /// State kept between invocations.
+    /// On the filesystem it: {root_dir}/state.json
+    state_file: FileEntry,
+    /// Copies of journals taken before they are modified in bulk.
+    /// On the filesystem it: {root_dir}/backups
+    backups_dir: FileEntry,
+    /// The workspaces directories of other roots, by the name of the root.
+    /// On the filesystem it: {path}/workspaces
+    roots: HashMap<String, FileEntry>,
+    /// Digests of the journals exported to remote targets, by target.
+    /// On the filesystem it: {root_dir}/manifests
+    manifests_dir: FileEntry,
+    /// Metadata of journals, if enabled by `metadata-store`.
+    /// On the filesystem it: {root_dir}/.meta.db
+    meta_file: FileEntry,
+    /// Decrypted content of encrypted journals, if enabled by `search-cache`.
+    /// On the filesystem it: {root_dir}/.search-cache
+    search_cache_file: FileEntry,
     formatter: TextFormatter,
+    /// Time zone of the dates in filenames and templates.
+    zone: Zone,
 }
 
-impl Handler {
-    pub fn new() -> Result<Self> {
-        let basedir = match directories::BaseDirs::new() {
-            Some(basedir) => basedir,
-            None => bail!("failed to resolver user base directories"),
-        };
-
-        let config_dir = FileEntry::new(basedir.config_dir()).push("journal");
-        config_dir.mkdir()?;
+struct CachedJournals {
+    /// When the workspace, or its index, was last modified.
+    modified: SystemTime,
+    journals: Vec<FileEntry>,
+}
 
-        let config_path = config_dir.push("config.toml");
-        let config = Config::load(&config_path)?;
+impl Setup {
+    /// Loads the configuration at `config_path`, using the default
+    /// configuration if there's no such file.
+    fn load(config_path: &FileEntry, basedir: &directories::BaseDirs) -> Result<Self> {
+        let config = match Config::load(config_path) {
+            Ok(config) => config,
+            Err(err) => bail!("failed to load {}: {}", config_path.path().display(), err),
+        };
 
         let root_dir = match &config.root {
             Some(d) => FileEntry::from(d.as_str()),
@@ -48,313 +225,5197 @@ impl Handler {
         };
 
         let workspaces_dir = root_dir.push("workspaces");
+        let state_file = root_dir.push("state.json");
+        let backups_dir = root_dir.push("backups");
+        let meta_file = root_dir.push(".meta.db");
+        let manifests_dir = root_dir.push("manifests");
+        let search_cache_file = root_dir.push(".search-cache");
+        let roots: HashMap<String, FileEntry> = config
+            .roots
+            .iter()
+            .flatten()
+            .map(|(name, root)| {
+                let dir = FileEntry::from(root.path.as_str()).push("workspaces");
+                (name.clone(), dir)
+            })
+            .collect();
 
         let default_workspace = match &config.default_workspace {
-            Some(w) => {
-                valid_workspace_name(w)?;
-                workspaces_dir.push(w)
-            }
+            Some(w) => match valid_workspace_name(w)?.split_once(':') {
+                Some((root, w)) => match roots.get(root) {
+                    Some(dir) => dir.push(w),
+                    None => bail!("no root named '{}' for default-workspace", root),
+                },
+                None => workspaces_dir.push(w),
+            },
             None => workspaces_dir.push("default"),
         };
 
+        let appearance = config.appearance.clone().unwrap_or_default();
+        let zone = match &config.timezone {
+            Some(tz) => Zone::parse(tz)?,
+            None => Zone::Local,
+        };
+
         Ok(Self {
             config,
+            root_dir,
             workspaces_dir,
             default_workspace_dir: default_workspace,
-            formatter: TextFormatter {},
+            state_file,
+            backups_dir,
+            roots,
+            meta_file,
+            manifests_dir,
+            search_cache_file,
+            formatter: TextFormatter::new(appearance),
+            zone,
         })
     }
+}
+
+impl Handler {
+    /// Creates a handler with the configuration at `config_path`, which must
+    /// exist, or else with the one in the user's config directory, if any.
+    /// The configuration is loaded when the first command is handled.
+    pub fn new(config_path: Option<&Path>) -> Result<Self> {
+        let config_path = match config_path {
+            Some(path) if !path.is_file() => bail!("no config file at {}", path.display()),
+            Some(path) => FileEntry::new(path),
+            None => FileEntry::new(base_dirs()?.config_dir())
+                .push("journal")
+                .push("config.toml"),
+        };
+        Ok(Self::with_config_path(config_path))
+    }
+
+    fn with_config_path(config_path: FileEntry) -> Self {
+        Self {
+            config_path,
+            setup: OnceCell::new(),
+            cache: RefCell::new(HashMap::new()),
+            quiet: Cell::new(false),
+            hidden: Cell::new(false),
+            allow_weak_key: Cell::new(false),
+        }
+    }
+
+    /// Creates a handler with the configuration loaded again from the same file.
+    pub fn reload(&self) -> Result<Self> {
+        let handler = Self::with_config_path(self.config_path.clone());
+        // Loaded now, so that an invalid configuration is reported right away
+        handler.load_setup()?;
+        Ok(handler)
+    }
+
+    /// Loads the configuration and its directories, unless already loaded.
+    fn load_setup(&self) -> Result<&Setup> {
+        if let Some(setup) = self.setup.get() {
+            return Ok(setup);
+        }
+        let setup = Setup::load(&self.config_path, &base_dirs()?)?;
+        Ok(self.setup.get_or_init(|| setup))
+    }
+
+    /// The configuration and its directories, loaded when handling a command.
+    fn setup(&self) -> &Setup {
+        self.setup
+            .get()
+            .expect("the configuration is loaded before handling commands")
+    }
+
+    /// When the configuration file was last modified, to reload it when changed.
+    /// `None` if there's no configuration file.
+    pub fn config_modified(&self) -> Option<SystemTime> {
+        self.config_path.modified().ok()
+    }
+
+    /// Expands a user-defined alias in the first argument after the program name.
+    /// Built-in commands can't be overridden, and aliases aren't expanded recursively.
+    pub fn expand_aliases(&self, args: Vec<String>) -> Vec<String> {
+        // An invalid configuration is reported when the command is handled
+        let setup = match self.load_setup() {
+            Ok(setup) => setup,
+            Err(_) => return args,
+        };
+        let (aliases, name) = match (&setup.config.aliases, args.get(1)) {
+            (Some(aliases), Some(name)) => (aliases, name),
+            _ => return args,
+        };
+
+        if Cli::command().find_subcommand(name).is_some() {
+            return args;
+        }
+
+        match aliases.get(name) {
+            Some(expansion) => {
+                let mut expanded = vec![args[0].clone()];
+                expanded.extend(
+                    util::split_args(expansion)
+                        .iter()
+                        .map(|arg| template::create(Some(arg), "", &[], setup.zone.now())),
+                );
+                expanded.extend(args.into_iter().skip(2));
+                expanded
+            }
+            None => args,
+        }
+    }
 
     pub fn handle(&self, cli: Cli) -> Result<()> {
-        match cli.command {
+        self.load_setup()?;
+        self.quiet.set(cli.quiet);
+        self.hidden.set(cli.hidden);
+        self.allow_weak_key.set(cli.allow_weak_key);
+        if let Command::Create(_) | Command::Remove(_) | Command::Rename(_) | Command::Undo(_) =
+            cli.command
+        {
+            self.cache.borrow_mut().clear();
+        }
+
+        if !cli.profile_perf {
+            return self.handle_command(cli.command);
+        }
+
+        perf::start();
+        let started = Instant::now();
+        let result = self.handle_command(cli.command);
+        perf::record("total", started.elapsed());
+        self.output_timings(perf::finish());
+        result
+    }
+
+    fn handle_command(&self, command: Command) -> Result<()> {
+        match command {
             Command::Open(args) => self.handle_open(args, false)?,
             Command::Print(args) => self.handle_open(args, true)?,
+            Command::Edit(args) => self.handle_edit(args)?,
             Command::Create(args) => self.handle_create(args)?,
             Command::List(args) => self.handle_list(args)?,
             Command::Remove(args) => self.handle_remove(args)?,
             Command::Rename(args) => self.handle_rename(args)?,
+            Command::Undo(args) => self.handle_undo(args)?,
             Command::Search(args) => self.handle_search(args)?,
+            Command::Replace(args) => self.handle_replace(args)?,
+            Command::Diff(args) => self.handle_diff(args)?,
+            Command::Split(args) => self.handle_split(args)?,
+            Command::Workspace(args) => self.handle_workspace(args)?,
             Command::Export(args) => self.handle_export(args)?,
+            Command::Bundle(args) => self.handle_bundle(args)?,
+            Command::Config(args) => self.handle_config(args)?,
+            Command::Share(args) => self.handle_share(args)?,
+            Command::Keygen(args) => self.handle_keygen(args)?,
+            Command::Checksum(args) => self.handle_checksum(args)?,
+            Command::Dedupe(args) => self.handle_dedupe(args)?,
+            Command::Conflicts(args) => self.handle_conflicts(args)?,
+            Command::Serve(args) => self.handle_serve(args)?,
+            Command::Tui(args) => self.handle_tui(args)?,
+            Command::Watch(args) => self.handle_watch(args)?,
+            Command::Remind(args) => self.handle_remind(args)?,
+            Command::Review(args) => self.handle_review(args)?,
+            Command::Random(args) => self.handle_random(args)?,
+            Command::OnThisDay(args) => self.handle_onthisday(args)?,
+            Command::Stats(args) => self.handle_stats(args)?,
+            Command::Wc(args) => self.handle_wc(args)?,
+            Command::Log(args) => self.handle_log(args)?,
+            Command::Append(args) => self.handle_append(args)?,
+            Command::Mood(args) => self.handle_mood(args)?,
+            Command::Todo(args) => self.handle_todo(args)?,
             _ => bail!("unsupport here"),
         };
 
         Ok(())
     }
 
-    fn handle_open(&self, args: OpenArgs, print: bool) -> CmdResult {
-        let dir = self.get_workspace(&args.workspace);
-        let filepath = self.find_journal(dir, &args.name)?;
+    fn handle_open(&self, mut args: OpenArgs, print: bool) -> CmdResult {
+        if let (None, Some((workspace, name))) = (
+            &args.workspace,
+            args.name.as_deref().and_then(split_address),
+        ) {
+            args.workspace = Some(workspace);
+            args.name = Some(name);
+        }
+        let dir = self.get_workspace(&args.workspace)?;
+        let key = get_key(args.key);
+        if let Some(remote) = self.remote_workspace(&dir)?.filter(|_| !args.any_workspace) {
+            let name = match &args.name {
+                Some(name) => name,
+                None => bail!("journal name required for remote workspace"),
+            };
+            let create = !print && (args.create || self.setup().config.create_missing);
+            return self.open_remote(&remote, name, key, print, create);
+        }
+        let mode = if args.exact {
+            MatchMode::Exact
+        } else if args.fuzzy || self.setup().config.fuzzy_match {
+            MatchMode::Fuzzy
+        } else {
+            MatchMode::Substring
+        };
+        let filepath = match (args.index, &args.name) {
+            (None, Some(name)) if args.any_workspace => {
+                let mut files = Vec::new();
+                for (workspace, ws) in self.list_workspaces_files(key.as_ref())? {
+                    files.extend(
+                        ws.files
+                            .into_iter()
+                            .map(|entry| (Some(workspace.clone()), entry)),
+                    );
+                }
+                match self.pick_match(files, name, mode)? {
+                    Some(filepath) => filepath,
+                    None => bail!("no journal matching {} in any workspace", name),
+                }
+            }
+            (Some(index), _) => {
+                let state = State::load(&self.setup().state_file)?;
+                let name = state.listed_journal(&self.workspace_name(&dir), index)?;
+                self.find_journal(dir, name, key.as_ref(), MatchMode::Exact)?
+            }
+            (None, Some(name)) if !print && (args.create || self.setup().config.create_missing) => {
+                match self.try_find_journal(dir.clone(), name, key.as_ref(), mode)? {
+                    Some(filepath) => filepath,
+                    None => {
+                        self.create_workspace_dir(&dir)?;
+                        return self.create_journal(&dir, name, key, |filepath| {
+                            self.template_for(filepath)
+                        });
+                    }
+                }
+            }
+            (None, Some(name)) => match self.try_find_journal(dir, name, key.as_ref(), mode)? {
+                Some(filepath) => filepath,
+                None => bail!(
+                    "no journal matching: {} (hint: use --any-workspace to search all workspaces)",
+                    name
+                ),
+            },
+            (None, None) => bail!("journal name or index required"),
+        };
 
         if !filepath.exists() {
             bail!("journal doesn't exists (hint: jn create --help)")
         }
-        let journal = Journal::open(&filepath, get_key(args.key))?;
+        let journal = Journal::open(&filepath, key.clone())?;
+        if !print && !journal.encrypted() {
+            self.check_new_key(key.as_ref())?;
+        }
 
         if print {
-            let bytes = journal.bytes()?;
-            let mut stdout = stdout();
-            stdout.write_all(&bytes)?;
-        } else {
-            journal.edit()?;
+            if !args.reveal && !self.confirm_reveal(&filepath, &journal)? {
+                return Ok(());
+            }
+            let mut stdout = stdout().lock();
+            journal.write_to(&mut stdout)?;
+            stdout.flush()?;
+            return Ok(());
         }
 
-        Ok(())
+        match self.opener_for(&filepath) {
+            Some(command) => self.view_journal(&filepath, &journal, command),
+            None => self.edit_journal(&filepath, &journal, Editor::new()),
+        }
     }
 
-    fn handle_create(&self, args: CreateArgs) -> CmdResult {
-        let dir = self.get_workspace(&args.workspace);
-        dir.mkdir()?;
-
-        let filepath = dir.push(&args.name);
-        if filepath.exists() {
-            bail!(
-                "filepath {} already exists (hint: jn open --help)",
-                filepath
-            );
+    /// Opens the journal like `jn open --create`, or with `-` saves stdin as its
+    /// content and writes it to stdout. Existing journals stay encrypted, or
+    /// plaintext, as they were, and new ones are encrypted if a key is given.
+    fn handle_edit(&self, args: EditArgs) -> CmdResult {
+        if args.input.is_none() {
+            let args = OpenArgs {
+                name: Some(args.name),
+                index: None,
+                workspace: args.workspace,
+                any_workspace: false,
+                key: args.key,
+                exact: false,
+                fuzzy: false,
+                reveal: false,
+                create: true,
+            };
+            return self.handle_open(args, false);
         }
 
-        let tmp = match filepath.extension() {
-            None => None,
-            Some(ext) => match &self.config.template {
-                Some(templates) => templates.get(&ext),
-                None => None,
-            },
+        let (workspace, name) = match (&args.workspace, split_address(&args.name)) {
+            (None, Some((workspace, name))) => (Some(workspace), name),
+            _ => (args.workspace, args.name),
         };
+        let dir = self.get_workspace(&workspace)?;
+        if self.remote_workspace(&dir)?.is_some() {
+            bail!("editing from stdin isn't supported in remote workspaces");
+        }
+        let key = get_key(args.key);
+        let mut content = Vec::new();
+        stdin().read_to_end(&mut content)?;
 
-        let content = template::create(tmp);
-        Journal::create(&filepath, get_key(args.key), content.as_bytes())?;
+        // Only the journal is written to stdout
+        self.quiet.set(true);
+        self.save_input(&dir, &name, key, &content)?;
 
+        let mut stdout = stdout().lock();
+        stdout.write_all(&content)?;
+        stdout.flush()?;
         Ok(())
     }
 
-    fn handle_list(&self, args: ListArgs) -> CmdResult {
-        let mut dirs: Vec<FileEntry> = Vec::new();
-
-        if args.all {
-            let ds = list_dirs(self.workspaces_dir.as_ref())?;
-            dirs.extend(ds);
-        } else {
-            let d = self.get_workspace(&args.workspace);
-            dirs.push(d);
-        }
-
-        for w in dirs {
-            let entries = self.list_in_dir(&w)?;
-            if !entries.is_empty() {
-                let output = Output::WorkspaceJournals(w.filename(), entries);
-                self.output(output);
+    /// Saves `content` as the journal `name` in `dir`, creating it if it doesn't
+    /// exist, without opening the editor.
+    fn save_input(
+        &self,
+        dir: &FileEntry,
+        name: &str,
+        key: Option<String>,
+        content: &[u8],
+    ) -> CmdResult {
+        match self.try_find_journal(dir.clone(), name, key.as_ref(), MatchMode::Exact)? {
+            Some(filepath) => {
+                let journal = Journal::open(&filepath, key.clone())?;
+                if journal.bytes()? != content {
+                    let key = if journal.encrypted() {
+                        key.as_ref()
+                    } else {
+                        None
+                    };
+                    Journal::save(&filepath, key, content)?;
+                    self.update_meta(|store| store.record(&meta_key(&filepath), &filepath))?;
+                    self.auto_export(&filepath, key);
+                }
+            }
+            None => {
+                self.create_workspace_dir(dir)?;
+                self.write_journal(dir, name, key, content)?;
             }
         }
-
         Ok(())
     }
 
-    fn list_in_dir(&self, dir: &FileEntry) -> Result<Vec<FileEntry>> {
-        if !dir.exists() {
-            return Ok(vec![]);
-        }
-
-        let entries = list_files(dir.as_ref())?;
-        Ok(entries)
+    /// The command configured in `open` for the extension of `entry`,
+    /// unless it's opened in the editor.
+    fn opener_for(&self, entry: &FileEntry) -> Option<&str> {
+        let ext = entry.extension()?.to_lowercase();
+        let command = self.setup().config.open.as_ref()?.get(&ext)?;
+        Some(command.as_str()).filter(|command| *command != editor::EDITOR)
     }
 
-    fn find_journal(&self, dir: FileEntry, name: &str) -> Result<FileEntry> {
-        let files = list_files(dir.as_ref())?;
-        let mut matches: Vec<FileEntry> = files
-            .into_iter()
-            .filter(|entry| entry.filename().contains(name))
-            .collect();
+    /// Opens `journal` with `command` instead of editing it, e.g. a PDF in a viewer.
+    /// Encrypted journals are decrypted to a temporary file only readable by the user,
+    /// which is removed when the command exits, or when the user is done viewing it
+    /// with the system opener, since it returns as soon as the application started.
+    fn view_journal(&self, entry: &FileEntry, journal: &Journal, command: &str) -> CmdResult {
+        let mut state = State::load(&self.setup().state_file)?;
+        state.set_opened(&meta_key(entry), chrono::Local::now());
+        state.save(&self.setup().state_file)?;
 
-        if matches.is_empty() {
-            bail!("no journal matching: {}", name);
+        if !journal.encrypted() {
+            return editor::open_with(command, entry.path());
         }
 
-        if matches.len() == 1 {
-            return Ok(matches.pop().unwrap());
+        let temp = TempFile::create(&entry.filename(), &journal.bytes()?)?;
+        editor::open_with(command, temp.path())?;
+        if command == editor::SYSTEM_OPENER && stdin().is_terminal() {
+            eprint!("Press Enter when done viewing {}", entry.filename());
+            stdin().read_line(&mut String::new())?;
         }
+        Ok(())
+    }
 
-        let entry = inquire::Select::new("Select journal", matches).prompt()?;
-        Ok(entry)
+    /// The server of the workspace `dir`, if its journals are stored remotely.
+    fn remote_workspace(&self, dir: &FileEntry) -> Result<Option<Remote>> {
+        match self.setup().config.workspace(&dir.filename()) {
+            Some(ws) => match &ws.remote {
+                Some(url) => Ok(Some(Remote::new(url, ws.ssh_key.clone())?)),
+                None => Ok(None),
+            },
+            None => Ok(None),
+        }
     }
 
-    fn handle_remove(&self, args: RemoveArgs) -> CmdResult {
-        if args.remove_workspace {
-            // Removes workspace instead of journal
-            let dir = self.workspaces_dir.push(&args.name);
-            if !dir.exists() {
-                bail!("workspace does not exists: {}", dir);
+    /// Prints or edits the journal `name` of a remote workspace, or creates it if
+    /// `create` is set and it doesn't exist. It's downloaded to a temporary file
+    /// only readable by the user, which is uploaded again if it was changed.
+    fn open_remote(
+        &self,
+        remote: &Remote,
+        name: &str,
+        key: Option<String>,
+        print: bool,
+        create: bool,
+    ) -> CmdResult {
+        let original = remote.download(name)?;
+        if original.is_none() && !create {
+            bail!("no journal at {}", remote.location(name));
+        }
+
+        let temp = FileEntry::new(&editor::temp_path(name)?);
+        let result = (|| -> Result<bool> {
+            match &original {
+                Some(bytes) => fs::write(temp.path(), bytes)?,
+                None => {
+                    self.check_new_key(key.as_ref())?;
+                    let content = self.template_for(&FileEntry::from(name));
+                    return Journal::create(
+                        &temp,
+                        key,
+                        content.as_bytes(),
+                        self.setup().config.discard_empty,
+                    );
+                }
+            }
+
+            let journal = Journal::open(&temp, key.clone())?;
+            if print {
+                if self.confirm_reveal(&temp, &journal)? {
+                    let mut stdout = stdout().lock();
+                    journal.write_to(&mut stdout)?;
+                    stdout.flush()?;
+                }
+                return Ok(false);
             }
+            if !journal.encrypted() && key.is_some() {
+                self.check_new_key(key.as_ref())?;
+            }
+            journal.edit()
+        })();
 
-            fs::remove_dir_all(dir.as_ref())?;
+        let changed = match result {
+            Ok(changed) => changed,
+            Err(err) => {
+                if temp.exists() {
+                    fs::remove_file(temp.path())?;
+                }
+                return Err(err);
+            }
+        };
+        if !changed {
+            if temp.exists() {
+                fs::remove_file(temp.path())?;
+            }
+            if !print {
+                self.output_info("no changes");
+            }
             return Ok(());
         }
 
-        let dir = self.get_workspace(&args.workspace);
-        let filepath = dir.push(&args.name);
-
-        if filepath.exists() {
-            fs::remove_file(filepath.as_ref())?;
-        } else {
-            let err = format!(
-                "journal named '{}' not found in workspace '{}'",
-                args.name,
-                dir.filename()
+        // Never overwrite changes made on another machine meanwhile
+        if remote.download(name)? != original {
+            bail!(
+                "{} was modified while editing, changes kept in {}",
+                remote.location(name),
+                temp.path().display()
             );
-            self.output_error(&err);
         }
+        remote.upload(name, &temp.read_bytes()?)?;
+        fs::remove_file(temp.path())?;
 
+        if original.is_none() {
+            self.output(Output::Created(remote.location(name)));
+        }
         Ok(())
     }
 
-    fn handle_rename(&self, args: RenameArgs) -> CmdResult {
-        if args.rename_workspace {
-            // Renames a workspace instead of journal
-            let old = self.workspaces_dir.push(&args.old);
-            let new = self.workspaces_dir.push(&args.new);
-
-            if !old.exists() {
-                bail!("workspace does not exists: {}", old);
-            }
+    /// Opens `journal` at `entry` in `editor`, recording when it was opened.
+    fn edit_journal(&self, entry: &FileEntry, journal: &Journal, editor: Editor) -> CmdResult {
+        let mut state = State::load(&self.setup().state_file)?;
+        state.set_opened(&meta_key(entry), chrono::Local::now());
+        state.save(&self.setup().state_file)?;
 
-            fs::rename(old.as_ref(), new.as_ref())?;
-            return Ok(());
+        if !journal.edit_with(editor)? {
+            self.output_info("no changes");
+        } else {
+            self.update_meta(|store| store.record(&meta_key(entry), entry))?;
+            self.auto_export(entry, journal.key());
+            self.report_word_goal(journal.key());
         }
 
-        let dir = self.get_workspace(&args.workspace);
-        let filepath = dir.push(&args.old);
+        Ok(())
+    }
 
-        if filepath.exists() {
-            let new = dir.push(&args.new);
-            fs::rename(filepath.as_ref(), new.as_ref())?;
-        } else {
-            let err = format!(
-                "journal named '{}' not found in workspace '{}'",
-                args.old,
-                dir.filename()
+    /// Checks the strength of a key that journals are about to be encrypted with.
+    /// Very weak keys are refused unless --allow-weak-key is given, and weak ones warned about.
+    fn check_new_key(&self, key: Option<&String>) -> Result<()> {
+        let strength = match key {
+            Some(key) => strength::estimate(key),
+            None => return Ok(()),
+        };
+
+        if strength == Strength::VeryWeak && !self.allow_weak_key.get() {
+            bail!(
+                "key is too weak, e.g. a common password or repeated characters \
+                 (hint: generate one with jn keygen, or use --allow-weak-key)"
             );
-            self.output_error(&err);
         }
-
+        if strength <= Strength::Weak {
+            self.output_warning("key is weak (hint: use a longer passphrase, or jn keygen)");
+        }
         Ok(())
     }
 
-    fn handle_search(&self, args: SearchArgs) -> CmdResult {
-        let re = RegexBuilder::new(&args.pattern)
-            .case_insensitive(args.case_insensitive)
-            .build()?;
+    /// Whether to print the decrypted content of `journal`, which is asked if it
+    /// has a tag in `sensitive-tags`. It's an error when the user can't be asked.
+    fn confirm_reveal(&self, filepath: &FileEntry, journal: &Journal) -> Result<bool> {
+        let sensitive = match &self.setup().config.sensitive_tags {
+            Some(tags) if journal.encrypted() && !tags.is_empty() => tags,
+            _ => return Ok(true),
+        };
 
-        let mut workspaces: Workspaces = Workspaces::new();
-        match args.workspace {
-            Some(w) => {
-                let p = self.workspaces_dir.push(&w);
-                let files = list_files(p.as_ref())?;
-                workspaces.insert(w.to_string(), Workspace::new(w, files));
-            }
-            None => {
-                let ws = self.list_workspaces_files()?;
-                workspaces.extend(ws);
-            }
+        let content = journal.text()?;
+        let tags = frontmatter::tags(&frontmatter::parse(&content));
+        if !tags.iter().any(|tag| sensitive.contains(tag)) {
+            return Ok(true);
         }
 
-        let key = get_key(args.key);
+        let name = filepath.filename();
+        if !stdin().is_terminal() || !stdout().is_terminal() {
+            bail!(
+                "{} is tagged as sensitive (hint: use --reveal to print it)",
+                name
+            );
+        }
+        let msg = format!("{} is tagged as sensitive. Print it anyway?", name);
+        Ok(inquire::Confirm::new(&msg).with_default(false).prompt()?)
+    }
 
-        for (name, workspace) in workspaces {
-            for jn in workspace.files {
-                let filename = jn.filename();
-                let journal = Journal::open(&jn, key.clone())?;
+    fn handle_create(&self, mut args: CreateArgs) -> CmdResult {
+        if let (None, Some((workspace, name))) = (
+            &args.workspace,
+            args.name.as_deref().and_then(split_address),
+        ) {
+            args.workspace = Some(workspace);
+            args.name = Some(name);
+        }
+        let dir = self.get_workspace(&args.workspace)?;
+        let key = get_key(args.key);
+        if let Some(remote) = self.remote_workspace(&dir)? {
+            let name = match &args.name {
+                Some(name) => name,
+                None => bail!("journal name required for remote workspace"),
+            };
+            if remote.download(name)?.is_some() {
+                bail!(
+                    "{} already exists (hint: jn open --help)",
+                    remote.location(name)
+                );
+            }
+            return self.open_remote(&remote, name, key, false, true);
+        }
+        self.create_workspace_dir(&dir)?;
 
-                let bytes = match journal.bytes() {
-                    Ok(bytes) => bytes,
-                    // We may get an error due to encryption
-                    Err(_) => continue,
-                };
+        let name = match (&args.name, &args.pattern, args.from, args.to) {
+            (Some(name), _, _, _) => name,
+            (None, Some(pattern), Some(from), Some(to)) => {
+                return self.create_range(&dir, pattern, from, to, key)
+            }
+            _ => bail!("journal name or --pattern with --from and --to required"),
+        };
 
-                let content = match String::from_utf8(bytes) {
-                    Ok(s) => s,
-                    Err(_) => continue,
-                };
+        self.create_journal(&dir, name, key, |filepath| self.template_for(filepath))
+    }
 
-                let matches: Vec<String> = content
-                    .lines()
-                    .enumerate()
-                    .filter(|(_, line)| re.is_match(line))
-                    .map(|(num, line)| {
-                        let linenum = format!("{}", num + 1);
-                        format!("{}: {}", linenum.green(), line)
-                    })
-                    .collect();
+    /// Creates the journal `name` in `dir` and opens it in the editor,
+    /// with the content given by `content` for its path.
+    fn create_journal(
+        &self,
+        dir: &FileEntry,
+        name: &str,
+        key: Option<String>,
+        content: impl FnOnce(&FileEntry) -> String,
+    ) -> CmdResult {
+        let (filepath, index) = self.new_journal_path(dir, name, key.as_ref())?;
 
-                if !matches.is_empty() {
-                    println!(
-                        "{}/{}",
-                        name.to_string().bold().magenta(),
-                        filename.to_string().bold().magenta()
-                    );
+        let content = content(&filepath);
+        let created = Journal::create(
+            &filepath,
+            key.clone(),
+            content.as_bytes(),
+            self.setup().config.discard_empty,
+        )?;
 
-                    for line in matches {
-                        println!("{}", line);
-                    }
-                }
-            }
+        if !created {
+            self.output_info("empty journal discarded");
+            return Ok(());
         }
 
-        Ok(())
+        self.journal_created(dir, name, &filepath, index, key.as_ref())
     }
 
-    fn handle_export(&self, args: ExportArgs) -> CmdResult {
-        let workspaces = self.list_workspaces_files()?;
+    /// Creates the journal `name` in `dir` with `content`, without the editor.
+    fn write_journal(
+        &self,
+        dir: &FileEntry,
+        name: &str,
+        key: Option<String>,
+        content: &[u8],
+    ) -> CmdResult {
+        let (filepath, index) = self.new_journal_path(dir, name, key.as_ref())?;
+        Journal::save_new(&filepath, key.as_ref(), content)?;
+        self.journal_created(dir, name, &filepath, index, key.as_ref())
+    }
 
-        // FIXME: encrypted files must be decrypted before getting exported.
+    /// The path of the new journal `name` in `dir`, and the index of the names
+    /// of the workspace if it encrypts them, which is saved once it's created.
+    fn new_journal_path(
+        &self,
+        dir: &FileEntry,
+        name: &str,
+        key: Option<&String>,
+    ) -> Result<(FileEntry, Option<NameIndex>)> {
+        self.check_new_key(key)?;
+        if key.is_none() && self.encrypts_journals(dir) {
+            bail!(
+                "key required for encrypted workspace: {} (hint: use --key)",
+                dir.filename()
+            );
+        }
 
-        let output = match args.target.trim() {
-            "zip" => zip::export(args.dir, workspaces, get_key(args.key))?,
-            target => bail!("unknown export target: {}", target),
-        };
+        if self.encrypts_filenames(dir) {
+            let mut index = NameIndex::load(dir, key)?;
+            let filepath = index.insert(dir, name)?;
+            return Ok((filepath, Some(index)));
+        }
 
-        self.output(output);
+        let filepath = dir.push(name);
+        if filepath.exists() {
+            bail!(
+                "filepath {} already exists (hint: jn open --help)",
+                filepath
+            );
+        }
+        create_parent_dirs(&filepath)?;
+        Ok((filepath, None))
+    }
+
+    /// Records the journal `name` created at `filepath` in `dir`.
+    fn journal_created(
+        &self,
+        dir: &FileEntry,
+        name: &str,
+        filepath: &FileEntry,
+        index: Option<NameIndex>,
+        key: Option<&String>,
+    ) -> CmdResult {
+        if let Some(index) = index {
+            index.save(dir, key)?;
+        }
 
+        self.update_meta(|store| store.record(&meta_key(filepath), filepath))?;
+        self.output(Output::Created(format!(
+            "{}/{}",
+            self.workspace_name(dir),
+            name
+        )));
+        self.auto_export(filepath, key);
+        self.report_word_goal(key);
         Ok(())
     }
-}
 
-impl Handler {
-    fn output(&self, output: Output) {
-        println!("{}", self.formatter.format(output));
-    }
+    /// Creates a journal from the template for every date from `from` to `to`,
+    /// named by `pattern` with `{date}` replaced, skipping existing journals.
+    /// The journals aren't opened in the editor.
+    fn create_range(
+        &self,
+        dir: &FileEntry,
+        pattern: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+        key: Option<String>,
+    ) -> CmdResult {
+        self.check_new_key(key.as_ref())?;
+        if !pattern.contains("{date}") {
+            bail!("pattern must contain {{date}}, e.g. '{{date}}.md'");
+        }
+        if from > to {
+            bail!("--from {} is after --to {}", from, to);
+        }
 
-    fn output_error(&self, err: &str) {
-        eprintln!("{}: {}", "error".red(), err)
-    }
+        let mut index = if self.encrypts_filenames(dir) {
+            Some(NameIndex::load(dir, key.as_ref())?)
+        } else {
+            None
+        };
+        let existing: HashSet<String> = self
+            .workspace_journals(dir, key.as_ref())?
+            .iter()
+            .map(|e| e.filename())
+            .collect();
 
-    /// Creates a list of tuples with workspace name and it's files.
-    fn list_workspaces_files(&self) -> Result<Workspaces> {
-        let mut xs: Workspaces = HashMap::new();
+        let mut created = Vec::new();
+        let mut skipped = Vec::new();
+        for date in from.iter_days().take_while(|date| *date <= to) {
+            // Validated as names given on the command line are
+            let name = valid_journal_path(
+                &pattern.replace("{date}", &date.format("%Y-%m-%d").to_string()),
+            )?;
+            let path = format!("{}/{}", dir.filename(), name);
+            // Hidden and ignored journals aren't listed, but are never overwritten
+            if existing.contains(&name) || (index.is_none() && dir.push(&name).exists()) {
+                skipped.push(path);
+                continue;
+            }
 
-        let dirs = list_dirs(self.workspaces_dir.as_ref())?;
-        for d in dirs {
-            let f = d.filename();
-            let files = list_files(d.as_ref())?;
-            xs.insert(f.to_string(), Workspace::new(f, files));
+            let filepath = match index.as_mut() {
+                Some(index) => index.insert(dir, &name)?,
+                None => {
+                    let filepath = dir.push(&name);
+                    create_parent_dirs(&filepath)?;
+                    filepath
+                }
+            };
+            let content = self.template_for_date(&filepath, date);
+            Journal::save_new(&filepath, key.as_ref(), content.as_bytes())?;
+            self.update_meta(|store| store.record(&meta_key(&filepath), &filepath))?;
+            created.push(path);
         }
 
-        Ok(xs)
+        if let Some(index) = index {
+            index.save(dir, key.as_ref())?;
+        }
+
+        self.output(Output::Files {
+            title: "Created journals",
+            files: created,
+            skipped,
+        });
+        Ok(())
     }
 
-    fn get_workspace(&self, workspace: &Option<String>) -> FileEntry {
+    /// Creates the workspace `dir` for a new journal if it doesn't exist, unless
+    /// `create-workspaces` is `never`, or it's `prompt` and the user declines.
+    /// The default workspace is always created.
+    fn create_workspace_dir(&self, dir: &FileEntry) -> Result<()> {
+        if dir.exists() {
+            return Ok(());
+        }
+
+        let name = self.workspace_name(dir);
+        if dir.path() != self.setup().default_workspace_dir.path() {
+            match self.setup().config.create_workspaces {
+                CreateWorkspaces::Always => {}
+                CreateWorkspaces::Prompt if stdin().is_terminal() => {
+                    let msg = format!("Workspace {} doesn't exist. Create it?", name);
+                    if !inquire::Confirm::new(&msg).with_default(false).prompt()? {
+                        return Err(ExitStatus(1).into());
+                    }
+                }
+                _ => bail!(
+                    "workspace does not exists: {} (hint: jn workspace create {})",
+                    name,
+                    name
+                ),
+            }
+        }
+
+        self.make_workspace_dir(dir)
+    }
+
+    fn make_workspace_dir(&self, dir: &FileEntry) -> Result<()> {
+        dir.mkdir()?;
+        let meta = WorkspaceMeta {
+            created: Some(util::get_date(self.setup().zone)),
+            ..Default::default()
+        };
+        meta.save(dir)?;
+        self.output(Output::Created(format!("{}/", self.workspace_name(dir))));
+        Ok(())
+    }
+
+    /// The content of a new journal, from the template for its extension if there is one.
+    fn template_for(&self, filepath: &FileEntry) -> String {
+        self.template_at(filepath, self.setup().zone.now())
+    }
+
+    /// The content of a new journal for `date`, used for the date placeholders.
+    fn template_for_date(&self, filepath: &FileEntry, date: NaiveDate) -> String {
+        self.template_at(filepath, self.setup().zone.midnight(date))
+    }
+
+    fn template_at(&self, filepath: &FileEntry, now: DateTime<FixedOffset>) -> String {
+        let tmp = match filepath.extension() {
+            None => None,
+            Some(ext) => match self
+                .setup()
+                .config
+                .template
+                .as_ref()
+                .and_then(|t| t.get(&ext))
+            {
+                Some(tmp) => Some(tmp.as_str()),
+                None => template::default_template(&ext),
+            },
+        };
+
+        let prompts = tmp.map(|tmp| self.prompts_for(tmp)).unwrap_or_default();
+        template::create(tmp, &filepath.filename(), &prompts, now)
+    }
+
+    /// The prompts of the `prompts-file`, if `tmp` uses the prompt of the day.
+    fn prompts_for(&self, tmp: &str) -> Vec<String> {
+        let mut prompts = Vec::new();
+        if let Some(path) = &self.setup().config.prompts_file {
+            if tmp.contains("{{PROMPT_OF_DAY}}") {
+                match read_file(Path::new(path)) {
+                    Ok(content) => prompts.extend(
+                        content
+                            .lines()
+                            .map(|line| line.trim())
+                            .filter(|line| !line.is_empty())
+                            .map(|line| line.to_string()),
+                    ),
+                    Err(err) => {
+                        self.output_hint(&format!("failed to read prompts file {}: {}", path, err))
+                    }
+                }
+            }
+        }
+        prompts
+    }
+
+    fn handle_list(&self, args: ListArgs) -> CmdResult {
+        let mut dirs: Vec<FileEntry> = Vec::new();
+
+        if args.all {
+            if !self.setup().workspaces_dir.exists() {
+                self.output_hint("no workspaces yet (hint: jn create --help)");
+                return Ok(());
+            }
+
+            let ds = self.workspace_dirs()?;
+            dirs.extend(ds);
+        } else if args.all_roots {
+            dirs.extend(self.all_root_workspace_dirs()?);
+        } else if args.workspace.is_empty() {
+            dirs.push(self.setup().default_workspace_dir.clone());
+        } else {
+            dirs.extend(self.resolve_workspaces(&args.workspace)?);
+        }
+
+        let key = get_key(args.key.clone());
+        if args.count || args.summary {
+            return self.list_counts(dirs, key.as_ref(), &args);
+        }
+        if let Some(by) = &args.group_by {
+            return self.list_groups(dirs, key.as_ref(), &args, by);
+        }
+
+        let mut state = State::load(&self.setup().state_file)?;
+        let mut store = self.meta_store()?;
+        let mut rows = Vec::new();
+        let mut conflicts = 0;
+
+        for w in dirs {
+            let name = self.workspace_name(&w);
+            let mut entries = self.list_in_dir(&w, key.as_ref())?;
+            conflicts += self
+                .workspace_files(&w, key.as_ref())?
+                .iter()
+                .filter(|entry| conflicts::is_conflict(&entry.filename()))
+                .count();
+            self.check_quotas(&name, &entries)?;
+            match args.sort.as_deref() {
+                Some("name") => entries.sort_by_key(|e| e.filename()),
+                // Never opened journals sort first, as None < Some
+                Some("opened") => entries.sort_by_key(|e| state.last_opened(&meta_key(e))),
+                _ => {}
+            }
+            if !entries.is_empty() {
+                let mut journals = Vec::with_capacity(entries.len());
+                for entry in entries {
+                    let encrypted = Journal::is_encrypted(&entry)?;
+                    if (args.encrypted_only && !encrypted) || (args.plaintext_only && encrypted) {
+                        continue;
+                    }
+                    journals.push(ListedJournal { entry, encrypted });
+                }
+
+                // Remember the order shown so journals can be opened by index.
+                let names = journals.iter().map(|j| j.entry.filename()).collect();
+                state.set_listing(&name, names);
+
+                if args.format == "text" {
+                    let meta = WorkspaceMeta::load(&w)?;
+                    let output = Output::WorkspaceJournals(name.clone(), meta, journals);
+                    self.output(output);
+                } else {
+                    for journal in journals {
+                        let row = self.journal_row(&name, journal, key.clone(), store.as_mut())?;
+                        rows.push(row);
+                    }
+                }
+            }
+        }
+
+        state.save(&self.setup().state_file)?;
+        if let Some(store) = store {
+            store.save()?;
+        }
+        if conflicts > 0 && args.format == "text" {
+            self.output_hint(&format!(
+                "{} conflicting copies of journals not listed (hint: jn conflicts)",
+                conflicts
+            ));
+        }
+
+        let delimiter = match args.format.as_str() {
+            "csv" => ',',
+            "tsv" => '\t',
+            _ => return Ok(()),
+        };
+        self.output(Output::JournalTable { delimiter, rows });
+        Ok(())
+    }
+
+    /// Outputs the number of journals in `dirs`, and their sizes with --summary.
+    fn list_counts(
+        &self,
+        dirs: Vec<FileEntry>,
+        key: Option<&String>,
+        args: &ListArgs,
+    ) -> CmdResult {
+        let mut workspaces = Vec::new();
+        for dir in dirs {
+            let (mut count, mut size) = (0, 0);
+            for entry in self.list_in_dir(&dir, key)? {
+                if args.encrypted_only || args.plaintext_only {
+                    let encrypted = Journal::is_encrypted(&entry)?;
+                    if (args.encrypted_only && !encrypted) || (args.plaintext_only && encrypted) {
+                        continue;
+                    }
+                }
+                count += 1;
+                if args.summary {
+                    size += entry.size()?;
+                }
+            }
+            workspaces.push((self.workspace_name(&dir), count, size));
+        }
+        if args.all {
+            workspaces.sort();
+        }
+
+        self.output(Output::JournalCounts {
+            workspaces,
+            sizes: args.summary,
+        });
+        Ok(())
+    }
+
+    /// Outputs the number of journals in `dirs` and their sizes, grouped `by`
+    /// workspace, extension or month.
+    fn list_groups(
+        &self,
+        dirs: Vec<FileEntry>,
+        key: Option<&String>,
+        args: &ListArgs,
+        by: &str,
+    ) -> CmdResult {
+        let mut groups: BTreeMap<String, (usize, u64)> = BTreeMap::new();
+        for dir in dirs {
+            let workspace = self.workspace_name(&dir);
+            for entry in self.list_in_dir(&dir, key)? {
+                if args.encrypted_only || args.plaintext_only {
+                    let encrypted = Journal::is_encrypted(&entry)?;
+                    if (args.encrypted_only && !encrypted) || (args.plaintext_only && encrypted) {
+                        continue;
+                    }
+                }
+                let group = groups
+                    .entry(group_name(by, &workspace, &entry, key)?)
+                    .or_default();
+                group.0 += 1;
+                group.1 += entry.size()?;
+            }
+        }
+
+        let groups = groups
+            .into_iter()
+            .map(|(name, (journals, size))| Group {
+                name,
+                journals,
+                size: Some(size),
+                matches: None,
+            })
+            .collect();
+        self.output(Output::Groups {
+            by: by.to_string(),
+            groups,
+        });
+        Ok(())
+    }
+
+    /// Collects the metadata of a listed journal. The words and tags
+    /// are left out if the journal can't be decrypted.
+    /// Those of plaintext journals are taken from the metadata store, if any.
+    fn journal_row(
+        &self,
+        workspace: &str,
+        journal: ListedJournal,
+        key: Option<String>,
+        store: Option<&mut MetaStore>,
+    ) -> Result<JournalRow> {
+        let entry = journal.entry;
+        let modified: chrono::DateTime<chrono::Local> = entry.modified()?.into();
+
+        if let (Some(store), false) = (store, journal.encrypted) {
+            let meta = store.get(&meta_key(&entry), &entry)?;
+            return Ok(JournalRow {
+                workspace: workspace.to_string(),
+                name: entry.filename(),
+                size: fs::metadata(entry.path())?.len(),
+                modified: modified.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+                encrypted: false,
+                words: meta.words,
+                tags: meta.tags,
+            });
+        }
+
+        let content = Journal::open(&entry, key)?
+            .bytes()
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok());
+        let words = content
+            .as_ref()
+            .map(|c| frontmatter::body(c).split_whitespace().count());
+        let tags = content
+            .map(|c| frontmatter::tags(&frontmatter::parse(&c)))
+            .unwrap_or_default();
+
+        Ok(JournalRow {
+            workspace: workspace.to_string(),
+            name: entry.filename(),
+            size: fs::metadata(entry.path())?.len(),
+            modified: modified.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            encrypted: journal.encrypted,
+            words,
+            tags,
+        })
+    }
+
+    fn list_in_dir(&self, dir: &FileEntry, key: Option<&String>) -> Result<Vec<FileEntry>> {
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+
+        self.workspace_journals(dir, key)
+    }
+
+    fn find_journal(
+        &self,
+        dir: FileEntry,
+        name: &str,
+        key: Option<&String>,
+        mode: MatchMode,
+    ) -> Result<FileEntry> {
+        match self.try_find_journal(dir, name, key, mode)? {
+            Some(entry) => Ok(entry),
+            None => bail!("no journal matching: {}", name),
+        }
+    }
+
+    /// Finds the journal matching `name` like `find_journal`,
+    /// but returns `None` if no journal matches.
+    fn try_find_journal(
+        &self,
+        dir: FileEntry,
+        name: &str,
+        key: Option<&String>,
+        mode: MatchMode,
+    ) -> Result<Option<FileEntry>> {
+        let files = self.workspace_journals(&dir, key)?;
+        self.pick_match(
+            files.into_iter().map(|entry| (None, entry)).collect(),
+            name,
+            mode,
+        )
+    }
+
+    /// The journal of `files` matching `name`, picked by the user if several
+    /// match. Files are given along with their workspace if they are from
+    /// more than one workspace, which is then shown when picking.
+    fn pick_match(
+        &self,
+        files: Vec<(Option<String>, FileEntry)>,
+        name: &str,
+        mode: MatchMode,
+    ) -> Result<Option<FileEntry>> {
+        let mut matches: Vec<(Option<String>, FileEntry)> = match mode {
+            MatchMode::Exact => files
+                .into_iter()
+                .filter(|(_, entry)| entry.filename() == name)
+                .collect(),
+            MatchMode::Substring => {
+                let mut ranked: Vec<(Rank, (Option<String>, FileEntry))> = files
+                    .into_iter()
+                    .filter_map(|file| Rank::of(&file.1.filename(), name).map(|r| (r, file)))
+                    .collect();
+                ranked.sort_by_key(|(rank, _)| *rank);
+
+                // An exact or prefix match is opened immediately
+                // unless another journal matches equally well.
+                let best: Vec<Rank> = ranked.iter().take(2).map(|(rank, _)| *rank).collect();
+                if let [a @ (Rank::Exact | Rank::Prefix), b] = best.as_slice() {
+                    if a < b {
+                        ranked.truncate(1);
+                    }
+                }
+
+                ranked.into_iter().map(|(_, file)| file).collect()
+            }
+            MatchMode::Fuzzy => {
+                let matcher = SkimMatcherV2::default().ignore_case();
+                let mut scored: Vec<(i64, (Option<String>, FileEntry))> = files
+                    .into_iter()
+                    .filter_map(|file| {
+                        matcher
+                            .fuzzy_match(&file.1.filename(), name)
+                            .map(|score| (score, file))
+                    })
+                    .collect();
+                scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+                scored.into_iter().map(|(_, file)| file).collect()
+            }
+        };
+
+        if matches.len() <= 1 {
+            return Ok(matches.pop().map(|(_, entry)| entry));
+        }
+
+        let appearance = self.appearance();
+        let icon = appearance.encrypted_icon();
+        let candidates: Vec<Candidate> = matches
+            .into_iter()
+            .map(|(workspace, entry)| {
+                let encrypted = Journal::is_encrypted(&entry).unwrap_or_default();
+                Candidate {
+                    entry,
+                    workspace,
+                    encrypted_icon: (encrypted && !icon.is_empty()).then(|| icon.to_string()),
+                }
+            })
+            .collect();
+        let candidate = inquire::Select::new("Select journal", candidates).prompt()?;
+        Ok(Some(candidate.entry))
+    }
+
+    fn handle_remove(&self, args: RemoveArgs) -> CmdResult {
+        if args.remove_workspace {
+            // Removes workspace instead of journal
+            let dir = self.workspace_dir(&args.name)?;
+            if !dir.exists() {
+                bail!("workspace does not exists: {}", dir);
+            }
+            if args.dry_run {
+                let files = list_files_recursive(dir.as_ref())?.files;
+                let mut bytes = 0;
+                for entry in &files {
+                    bytes += fs::metadata(entry.path())?.len();
+                }
+                self.output(Output::DryRun(vec![PlannedChange::Remove {
+                    path: format!("{}/", args.name),
+                    files: files.len(),
+                    bytes,
+                }]));
+                return Ok(());
+            }
+
+            // Kept in the backups until undone
+            let backup_dir = self.backup_dir();
+            backup_dir.mkdir()?;
+            fs::rename(dir.as_ref(), backup_dir.push(&args.name).as_ref())?;
+            self.update_meta(|store| store.remove_workspace(&args.name))?;
+            self.record_change(Change::RemovedWorkspace {
+                workspace: args.name.clone(),
+                backup: backup_dir.path().display().to_string(),
+            })?;
+            self.output(Output::Removed(format!("{}/", args.name)));
+            return Ok(());
+        }
+
+        let dir = self.get_workspace(&args.workspace)?;
+
+        if self.encrypts_filenames(&dir) {
+            let key = get_key(args.key);
+            let mut index = NameIndex::load(&dir, key.as_ref())?;
+            match index.remove(&dir, &args.name) {
+                Some(filepath) if args.dry_run => {
+                    return self.output_removal(&dir, &args.name, &filepath);
+                }
+                Some(filepath) => {
+                    let backup_dir = self.backup(&[(dir.filename(), filepath.clone())])?;
+                    index.save(&dir, key.as_ref())?;
+                    fs::remove_file(filepath.as_ref())?;
+                    self.record_change(Change::Removed {
+                        workspace: self.workspace_name(&dir),
+                        name: args.name.clone(),
+                        filename: disk_filename(&filepath),
+                        backup: backup_dir.path().display().to_string(),
+                    })?;
+                    self.update_meta(|store| store.remove(&meta_key(&filepath)))?;
+                    self.forget_opened(&filepath)?;
+                }
+                None => return self.nothing_found(&dir, &args.name),
+            }
+            self.output(Output::Removed(format!(
+                "{}/{}",
+                self.workspace_name(&dir),
+                args.name
+            )));
+            return Ok(());
+        }
+
+        let filepath = dir.push(&args.name);
+        if !filepath.exists() {
+            return self.nothing_found(&dir, &args.name);
+        }
+        if args.dry_run {
+            return self.output_removal(&dir, &args.name, &filepath);
+        }
+
+        let backup_dir = self.backup(&[(dir.filename(), filepath.clone())])?;
+        fs::remove_file(filepath.as_ref())?;
+        remove_empty_dirs(&filepath, &dir);
+        self.update_meta(|store| store.remove(&meta_key(&filepath)))?;
+        self.forget_opened(&filepath)?;
+        self.record_change(Change::Removed {
+            workspace: self.workspace_name(&dir),
+            name: args.name.clone(),
+            filename: disk_filename(&filepath),
+            backup: backup_dir.path().display().to_string(),
+        })?;
+        self.output(Output::Removed(format!(
+            "{}/{}",
+            self.workspace_name(&dir),
+            args.name
+        )));
+        Ok(())
+    }
+
+    /// Shows that the journal `name` in `dir`, stored as `filepath`, would be removed.
+    fn output_removal(&self, dir: &FileEntry, name: &str, filepath: &FileEntry) -> CmdResult {
+        self.output(Output::DryRun(vec![PlannedChange::Remove {
+            path: format!("{}/{}", self.workspace_name(dir), name),
+            files: 1,
+            bytes: fs::metadata(filepath.path())?.len(),
+        }]));
+        Ok(())
+    }
+
+    /// Reports that there's no journal `name` in `dir`, exiting with status 1.
+    fn nothing_found(&self, dir: &FileEntry, name: &str) -> CmdResult {
+        let output = Output::NothingFound {
+            workspace: self.workspace_name(dir),
+            name: name.to_string(),
+        };
+        self.output_error(&self.setup().formatter.format(output));
+        Err(ExitStatus(1).into())
+    }
+
+    fn handle_rename(&self, args: RenameArgs) -> CmdResult {
+        if args.rename_workspace {
+            // Renames a workspace instead of journal
+            let old = self.workspace_dir(&args.old)?;
+            let new = self.workspace_dir(&args.new)?;
+
+            if !old.exists() {
+                bail!("workspace does not exists: {}", old);
+            }
+            if args.dry_run {
+                self.output(Output::DryRun(vec![PlannedChange::Rename {
+                    from: format!("{}/", args.old),
+                    to: format!("{}/", args.new),
+                }]));
+                return Ok(());
+            }
+
+            fs::rename(old.as_ref(), new.as_ref())?;
+            self.update_meta(|store| store.rename_workspace(&args.old, &args.new))?;
+            self.record_change(Change::Renamed {
+                workspace: None,
+                from: args.old.clone(),
+                to: args.new.clone(),
+            })?;
+            self.output(Output::Renamed {
+                from: format!("{}/", args.old),
+                to: format!("{}/", args.new),
+            });
+            return Ok(());
+        }
+
+        let dir = self.get_workspace(&args.workspace)?;
+        let key = get_key(args.key.clone());
+        let new_name = links::keep_extension(&args.old, &args.new);
+
+        if self.encrypts_filenames(&dir) {
+            // Only the index changes, the journal keeps its random filename.
+            let mut index = NameIndex::load(&dir, key.as_ref())?;
+            index.rename(&args.old, &new_name)?;
+            if args.dry_run {
+                return self.output_rename_plan(&dir, &args, &new_name, key.as_ref());
+            }
+            index.save(&dir, key.as_ref())?;
+            self.record_change(Change::Renamed {
+                workspace: Some(self.workspace_name(&dir)),
+                from: args.old.clone(),
+                to: new_name.clone(),
+            })?;
+            self.output_renamed(&dir, &args.old, &new_name);
+            if args.update_links {
+                self.update_links(&dir, &args.old, &new_name, key.as_ref(), false)?;
+            }
+            return Ok(());
+        }
+
+        let filepath = dir.push(&args.old);
+        if !filepath.exists() {
+            return self.nothing_found(&dir, &args.old);
+        }
+
+        let new = dir.push(&new_name);
+        if new.exists() {
+            bail!("journal {} already exists", new_name);
+        }
+        if args.dry_run {
+            return self.output_rename_plan(&dir, &args, &new_name, key.as_ref());
+        }
+        create_parent_dirs(&new)?;
+        fs::rename(filepath.as_ref(), new.as_ref())?;
+        remove_empty_dirs(&filepath, &dir);
+        self.update_meta(|store| store.rename(&meta_key(&filepath), &meta_key(&new)))?;
+
+        let mut state = State::load(&self.setup().state_file)?;
+        state.move_opened(&meta_key(&filepath), Some(&meta_key(&new)));
+        state.set_last_change(Change::Renamed {
+            workspace: Some(self.workspace_name(&dir)),
+            from: args.old.clone(),
+            to: new_name.clone(),
+        });
+        state.save(&self.setup().state_file)?;
+        self.output_renamed(&dir, &args.old, &new_name);
+        if args.update_links {
+            self.update_links(&dir, &args.old, &new_name, key.as_ref(), false)?;
+        }
+        Ok(())
+    }
+
+    /// Shows that the journal `args.old` in `dir` would be renamed to `new`,
+    /// and the journals whose links would be updated with --update-links.
+    fn output_rename_plan(
+        &self,
+        dir: &FileEntry,
+        args: &RenameArgs,
+        new: &str,
+        key: Option<&String>,
+    ) -> CmdResult {
+        let mut changes = vec![PlannedChange::Rename {
+            from: format!("{}/{}", dir.filename(), args.old),
+            to: format!("{}/{}", dir.filename(), new),
+        }];
+        if args.update_links {
+            changes.extend(self.update_links(dir, &args.old, new, key, true)?);
+        }
+        self.output(Output::DryRun(changes));
+        Ok(())
+    }
+
+    /// Rewrites the wiki-links to the renamed journal `old` in the journals
+    /// of the workspace `dir` to link to `new`. Journals that can't be
+    /// decrypted are skipped, and encrypted journals stay encrypted.
+    /// With `dry_run` nothing is rewritten, and the journals that would be are returned.
+    fn update_links(
+        &self,
+        dir: &FileEntry,
+        old: &str,
+        new: &str,
+        key: Option<&String>,
+        dry_run: bool,
+    ) -> Result<Vec<PlannedChange>> {
+        if !dry_run {
+            // Listed after the rename
+            self.cache.borrow_mut().clear();
+        }
+
+        let mut planned = Vec::new();
+        let mut updated = 0;
+        for entry in self.workspace_journals(dir, key)? {
+            let journal = Journal::open(&entry, key.cloned())?;
+            let content = match journal.try_bytes()?.map(String::from_utf8) {
+                Some(Ok(content)) => content,
+                _ => continue,
+            };
+
+            if let Some(rewritten) = links::rewrite(&content, old, new) {
+                if dry_run {
+                    planned.push(PlannedChange::Write {
+                        path: format!("{}/{}", dir.filename(), entry.filename()),
+                        bytes: rewritten.len() as u64,
+                        replaced: Some(content.len() as u64),
+                    });
+                    continue;
+                }
+                let content = rewritten;
+                let key = if journal.encrypted() { key } else { None };
+                Journal::save(&entry, key, content.as_bytes())?;
+                self.update_meta(|store| store.record(&meta_key(&entry), &entry))?;
+                updated += 1;
+            }
+        }
+
+        if updated > 0 {
+            self.output_info(&format!("updated links in {} journals", updated));
+        }
+        Ok(planned)
+    }
+
+    fn handle_undo(&self, args: UndoArgs) -> CmdResult {
+        let change = match State::load(&self.setup().state_file)?.take_last_change() {
+            Some(change) => change,
+            None => bail!("nothing to undo"),
+        };
+
+        match change {
+            Change::Removed {
+                workspace,
+                name,
+                filename,
+                backup,
+            } => {
+                let dir = self.workspace_dir(&workspace)?;
+                let filepath = dir.push(&filename);
+                if filepath.exists() {
+                    bail!("journal already exists: {}/{}", workspace, name);
+                }
+
+                create_parent_dirs(&filepath)?;
+                // Backed up by the workspace's name in its root
+                let copy = Path::new(&backup).join(dir.filename()).join(&filename);
+                fs::copy(copy, filepath.path())?;
+                if name != filename {
+                    // Removed from a workspace with encrypted filenames
+                    let key = get_key(args.key);
+                    let mut index = NameIndex::load(&dir, key.as_ref())?;
+                    index.restore(&name, &filename)?;
+                    index.save(&dir, key.as_ref())?;
+                }
+                self.update_meta(|store| store.record(&meta_key(&filepath), &filepath))?;
+                self.output(Output::Restored(format!("{}/{}", workspace, name)));
+            }
+            Change::RemovedWorkspace { workspace, backup } => {
+                let dir = self.workspace_dir(&workspace)?;
+                if dir.exists() {
+                    bail!("workspace already exists: {}", workspace);
+                }
+
+                fs::rename(Path::new(&backup).join(&workspace), dir.path())?;
+                self.output(Output::Restored(format!("{}/", workspace)));
+            }
+            Change::Renamed {
+                workspace,
+                from,
+                to,
+            } => {
+                self.handle_rename(RenameArgs {
+                    old: to,
+                    new: from,
+                    rename_workspace: workspace.is_none(),
+                    workspace,
+                    key: args.key,
+                    update_links: false,
+                    dry_run: false,
+                })?;
+            }
+            Change::Overwritten { journals, backup } => {
+                for (workspace, filename) in journals {
+                    let filepath = self.workspace_dir(&workspace)?.push(&filename);
+                    let copy = Path::new(&backup).join(&workspace).join(&filename);
+                    fs::copy(copy, filepath.path())?;
+                    self.output(Output::Restored(format!("{}/{}", workspace, filename)));
+                }
+            }
+        }
+
+        // Reloaded since reverting a rename records the reverse rename
+        let mut state = State::load(&self.setup().state_file)?;
+        state.take_last_change();
+        state.save(&self.setup().state_file)?;
+        Ok(())
+    }
+
+    /// Records `change` as the one reverted by `jn undo`.
+    fn record_change(&self, change: Change) -> Result<()> {
+        let mut state = State::load(&self.setup().state_file)?;
+        state.set_last_change(change);
+        state.save(&self.setup().state_file)
+    }
+
+    fn output_renamed(&self, dir: &FileEntry, old: &str, new: &str) {
+        let ws = self.workspace_name(dir);
+        self.output(Output::Renamed {
+            from: format!("{}/{}", ws, old),
+            to: format!("{}/{}", ws, new),
+        });
+    }
+
+    fn handle_search(&self, args: SearchArgs) -> CmdResult {
+        let mut pattern = if args.fixed_strings {
+            regex::escape(&args.pattern)
+        } else {
+            args.pattern.clone()
+        };
+        if args.word {
+            pattern = format!(r"\b(?:{})\b", pattern);
+        }
+
+        let re = RegexBuilder::new(&pattern)
+            .case_insensitive(args.case_insensitive)
+            .dot_matches_new_line(args.multiline)
+            .build()?;
+
+        let key = get_key(args.key);
+
+        let mut workspaces: Workspaces = Workspaces::new();
+        if let Some(name) = &args.name {
+            let dir = match args.workspace.as_slice() {
+                [] => self.setup().default_workspace_dir.clone(),
+                [workspace] if !util::is_glob(workspace) => self.workspace_dir(workspace)?,
+                _ => bail!("--name requires a single workspace"),
+            };
+            let mode = if self.setup().config.fuzzy_match {
+                MatchMode::Fuzzy
+            } else {
+                MatchMode::Substring
+            };
+
+            let entry = self.find_journal(dir.clone(), name, key.as_ref(), mode)?;
+            let ws = self.workspace_name(&dir);
+            workspaces.insert(ws.clone(), Workspace::new(ws, vec![entry]));
+        } else if args.all_roots {
+            for dir in self.all_root_workspace_dirs()? {
+                if key.is_none() && self.encrypts_filenames(&dir) {
+                    continue;
+                }
+                let name = self.workspace_name(&dir);
+                let files = self.workspace_journals(&dir, key.as_ref())?;
+                workspaces.insert(name.clone(), Workspace::new(name, files));
+            }
+        } else if args.workspace.is_empty()
+            && !args.all
+            && self.setup().config.search_scope == SearchScope::DefaultWorkspace
+        {
+            let dir = self.setup().default_workspace_dir.clone();
+            let files = self.workspace_journals(&dir, key.as_ref())?;
+            workspaces.insert(dir.filename(), Workspace::new(dir.filename(), files));
+        } else if args.workspace.is_empty() {
+            let ws = self.list_workspaces_files(key.as_ref())?;
+            workspaces.extend(ws);
+        } else {
+            for dir in self.resolve_workspaces(&args.workspace)? {
+                let name = self.workspace_name(&dir);
+                let files = self.workspace_journals(&dir, key.as_ref())?;
+                workspaces.insert(name.clone(), Workspace::new(name, files));
+            }
+        }
+
+        if workspaces.values().all(|ws| ws.files.is_empty()) {
+            self.output_hint("no journals to search (hint: jn create --help)");
+            return Err(ExitStatus(1).into());
+        }
+        // Results only have the names of the journals, which are grouped by their files
+        let entries: HashMap<(String, String), FileEntry> = match &args.group_by {
+            Some(_) => workspaces
+                .values()
+                .flat_map(|ws| {
+                    ws.files
+                        .iter()
+                        .map(|entry| ((ws.name.clone(), entry.filename()), entry.clone()))
+                })
+                .collect(),
+            None => HashMap::new(),
+        };
+
+        let (mut results, stats) = {
+            let _cancellation = cancel::install()?;
+            self.search_journals(&re, workspaces, key.clone(), args.multiline)?
+        };
+        let cancelled = cancel::requested();
+        if cancelled {
+            self.output_hint(&format!(
+                "search cancelled after {} journals, showing their matches",
+                stats.searched
+            ));
+        }
+        // Exit with 1 when nothing matches, as grep does, for scripts
+        if results.is_empty() && cancelled {
+            return Err(ExitStatus(cancel::INTERRUPTED).into());
+        }
+        if results.is_empty() {
+            if args.stats {
+                self.output(Output::SearchStats(stats));
+            }
+            return Err(ExitStatus(1).into());
+        }
+        if args.open {
+            return self.open_search_result(results, key);
+        }
+        if let Some(by) = &args.group_by {
+            let output = search_groups(by, &results, &entries, key.as_ref())?;
+            self.output(output);
+        } else {
+            // Workspaces are unordered, so sort to make the limits predictable.
+            results.sort_by(|a, b| a.workspace.cmp(&b.workspace));
+
+            let total_matches = results.iter().map(|r| r.matches.len()).sum();
+            let total_journals = results.len();
+
+            let mut remaining = args.max_results.unwrap_or(usize::MAX);
+            for result in results.iter_mut() {
+                let limit = args.max_per_file.unwrap_or(usize::MAX).min(remaining);
+                result.matches.truncate(limit);
+                remaining -= result.matches.len();
+            }
+            results.retain(|r| !r.matches.is_empty());
+
+            let output = Output::SearchResults {
+                results,
+                total_matches,
+                total_journals,
+            };
+            self.output_paged(output, !args.no_pager);
+        }
+        if args.stats {
+            self.output(Output::SearchStats(stats));
+        }
+
+        if cancelled {
+            return Err(ExitStatus(cancel::INTERRUPTED).into());
+        }
+        Ok(())
+    }
+
+    /// Lets the user select one of the journals in `results`, unless there's only
+    /// one, and opens it in the editor at the line of its first match.
+    fn open_search_result(&self, mut results: Vec<SearchResult>, key: Option<String>) -> CmdResult {
+        let result = if results.len() == 1 {
+            results.remove(0)
+        } else {
+            let candidates: Vec<String> = results
+                .iter()
+                .map(|r| format!("{}/{}", r.workspace, r.journal))
+                .collect();
+            let selected = inquire::Select::new("Select journal", candidates.clone()).prompt()?;
+            let index = candidates.iter().position(|c| *c == selected).unwrap_or(0);
+            results.remove(index)
+        };
+
+        let dir = self.workspace_dir(&result.workspace)?;
+        let entry = self.find_journal(dir, &result.journal, key.as_ref(), MatchMode::Exact)?;
+        let journal = Journal::open(&entry, key)?;
+
+        let mut editor = Editor::new();
+        if let Some(m) = result.matches.first() {
+            let args = self
+                .setup()
+                .config
+                .editor_line_args
+                .as_ref()
+                .and_then(|args| args.get(&editor.name()));
+            editor = editor.at_line(m.line, args.map(String::as_str));
+        }
+        self.edit_journal(&entry, &journal, editor)
+    }
+
+    /// Searches the journals of `workspaces` for lines matching `re`,
+    /// or for matches anywhere in the content if `multiline` is set.
+    /// Journals that can't be decrypted, are too large or look binary are skipped.
+    pub(crate) fn search_journals(
+        &self,
+        re: &Regex,
+        workspaces: Workspaces,
+        key: Option<String>,
+        multiline: bool,
+    ) -> Result<(Vec<SearchResult>, SearchStats)> {
+        let mut results = Vec::new();
+        let mut stats = SearchStats::default();
+        let max_size = self
+            .setup()
+            .config
+            .search_max_file_size
+            .unwrap_or(DEFAULT_SEARCH_MAX_FILE_SIZE);
+
+        let mut cache = match &key {
+            Some(key) if self.setup().config.search_cache => {
+                Some(SearchCache::load(&self.setup().search_cache_file, key)?)
+            }
+            _ => None,
+        };
+        let searched_workspaces: HashSet<String> = workspaces.keys().cloned().collect();
+        let mut seen = HashSet::new();
+
+        'workspaces: for workspace in workspaces.into_values() {
+            for jn in workspace.files {
+                // The journals searched so far are still cached and reported
+                if cancel::requested() {
+                    break 'workspaces;
+                }
+
+                let name = format!("{}/{}", workspace.name, jn.filename());
+                if fs::metadata(jn.path())?.len() > max_size {
+                    stats.oversized.push(name);
+                    continue;
+                }
+
+                let journal = Journal::open(&jn, key.clone())?;
+
+                // Sniff plaintext journals before reading them fully
+                if !journal.encrypted() && looks_binary(&jn.read_prefix(BINARY_SNIFF_LENGTH)?) {
+                    stats.binary.push(name);
+                    continue;
+                }
+
+                // Encrypted journals are cached by the digest of their ciphertext
+                let cache_digest = match &cache {
+                    Some(_) if journal.encrypted() => Some(jn.digest()?),
+                    _ => None,
+                };
+                let cached = match (&cache, &cache_digest) {
+                    (Some(cache), Some(digest)) => cache.get(&name, digest).map(str::to_string),
+                    _ => None,
+                };
+                if cache_digest.is_some() {
+                    seen.insert(name.clone());
+                }
+
+                let content = match cached {
+                    Some(content) => content,
+                    None => {
+                        let bytes = match journal.bytes() {
+                            Ok(bytes) => bytes,
+                            // We may get an error due to encryption
+                            Err(_) => {
+                                stats.undecryptable.push(name);
+                                continue;
+                            }
+                        };
+
+                        if looks_binary(&bytes[..bytes.len().min(BINARY_SNIFF_LENGTH)]) {
+                            stats.binary.push(name);
+                            continue;
+                        }
+                        let content = match String::from_utf8(bytes) {
+                            Ok(s) => s,
+                            Err(_) => {
+                                stats.binary.push(name);
+                                continue;
+                            }
+                        };
+                        if let (Some(cache), Some(digest)) = (cache.as_mut(), &cache_digest) {
+                            cache.insert(&name, digest, &content);
+                        }
+                        content
+                    }
+                };
+                stats.searched += 1;
+
+                let matches = perf::time("search", || {
+                    if multiline {
+                        multiline_matches(re, &content)
+                    } else {
+                        content
+                            .lines()
+                            .enumerate()
+                            .filter(|(_, line)| re.is_match(line))
+                            .map(|(num, line)| LineMatch {
+                                line: num + 1,
+                                text: line.to_string(),
+                            })
+                            .collect()
+                    }
+                });
+
+                if !matches.is_empty() {
+                    results.push(SearchResult {
+                        workspace: workspace.name.clone(),
+                        journal: jn.filename(),
+                        matches,
+                    });
+                }
+            }
+        }
+
+        if let (Some(mut cache), Some(key)) = (cache, &key) {
+            // Forget journals that are gone from the searched workspaces
+            cache.retain(|name| {
+                seen.contains(name)
+                    || name
+                        .split_once('/')
+                        .is_none_or(|(ws, _)| !searched_workspaces.contains(ws))
+            });
+            cache.save(&self.setup().search_cache_file, key)?;
+        }
+
+        Ok((results, stats))
+    }
+
+    fn handle_replace(&self, args: ReplaceArgs) -> CmdResult {
+        let re = RegexBuilder::new(&args.pattern)
+            .case_insensitive(args.case_insensitive)
+            .build()?;
+
+        let key = get_key(args.key);
+
+        let mut journals: Vec<(String, FileEntry)> = Vec::new();
+        match (&args.name, &args.workspace) {
+            (Some(name), _) => {
+                let dir = self.get_workspace(&args.workspace)?;
+                let entry =
+                    self.find_journal(dir.clone(), name, key.as_ref(), MatchMode::Substring)?;
+                journals.push((dir.filename(), entry));
+            }
+            (None, Some(w)) => {
+                let dir = self.workspace_dir(w)?;
+                for entry in self.workspace_journals(&dir, key.as_ref())? {
+                    journals.push((w.to_string(), entry));
+                }
+            }
+            (None, None) => {
+                for (name, ws) in self.list_workspaces_files(key.as_ref())? {
+                    for entry in ws.files {
+                        journals.push((name.clone(), entry));
+                    }
+                }
+                journals.sort_by(|a, b| a.0.cmp(&b.0));
+            }
+        }
+
+        let mut replacements = Vec::new();
+        // The journals to write: workspace, journal, new content and if it's encrypted
+        let mut pending = Vec::new();
+        let mut planned = Vec::new();
+        for (workspace, entry) in journals {
+            let journal = Journal::open(&entry, key.clone())?;
+            let content = match journal.bytes().map(String::from_utf8) {
+                Ok(Ok(content)) => content,
+                // Skip journals that can't be decrypted or aren't text
+                _ => continue,
+            };
+
+            let mut changes = Vec::new();
+            let lines: Vec<String> = content
+                .split('\n')
+                .enumerate()
+                .map(|(num, line)| {
+                    let new = re.replace_all(line, args.replacement.as_str());
+                    if new != line {
+                        changes.push(LineChange {
+                            line: num + 1,
+                            old: line.to_string(),
+                            new: new.to_string(),
+                        });
+                    }
+                    new.to_string()
+                })
+                .collect();
+
+            if !changes.is_empty() {
+                let replaced = lines.join("\n");
+                planned.push(PlannedChange::Write {
+                    path: format!("{}/{}", workspace, entry.filename()),
+                    bytes: replaced.len() as u64,
+                    replaced: Some(content.len() as u64),
+                });
+                replacements.push(Replacement {
+                    workspace: workspace.clone(),
+                    journal: entry.filename(),
+                    changes,
+                });
+                pending.push((workspace, entry, replaced, journal.encrypted()));
+            }
+        }
+
+        if pending.is_empty() {
+            self.output_hint("no matches to replace");
+            return Ok(());
+        }
+
+        let changed: usize = replacements.iter().map(|r| r.changes.len()).sum();
+        let count = pending.len();
+        self.output(Output::ReplacePreview(replacements));
+        if args.dry_run {
+            self.output(Output::DryRun(planned));
+            return Ok(());
+        }
+
+        if !args.yes {
+            let msg = format!("Replace {} lines in {} journals?", changed, count);
+            if !inquire::Confirm::new(&msg).with_default(false).prompt()? {
+                return Ok(());
+            }
+        }
+
+        let journals: Vec<(String, FileEntry)> = pending
+            .iter()
+            .map(|(workspace, entry, _, _)| (workspace.clone(), entry.clone()))
+            .collect();
+        let backup_dir = self.backup(&journals)?;
+
+        for (_, entry, content, encrypted) in pending {
+            let key = if encrypted { key.as_ref() } else { None };
+            Journal::save(&entry, key, content.as_bytes())?;
+        }
+        self.record_change(Change::Overwritten {
+            journals: journals
+                .iter()
+                .map(|(workspace, entry)| (workspace.clone(), disk_filename(entry)))
+                .collect(),
+            backup: backup_dir.path().display().to_string(),
+        })?;
+
+        self.output_hint(&format!("backups saved in {}", backup_dir.path().display()));
+        Ok(())
+    }
+
+    /// Copies the journals, by workspace, to a new directory in the backups
+    /// directory named by the current time, which is returned.
+    fn backup(&self, journals: &[(String, FileEntry)]) -> Result<FileEntry> {
+        let backup_dir = self.backup_dir();
+        for (workspace, entry) in journals {
+            let path = backup_dir.path().join(workspace).join(disk_filename(entry));
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::copy(entry.path(), path)?;
+        }
+        Ok(backup_dir)
+    }
+
+    /// A new directory in the backups directory, named by the current time.
+    fn backup_dir(&self) -> FileEntry {
+        self.setup()
+            .backups_dir
+            .push(&chrono::Local::now().format("%Y%m%d%H%M%S%3f").to_string())
+    }
+
+    fn handle_split(&self, args: SplitArgs) -> CmdResult {
+        let dir = self.get_workspace(&args.workspace)?;
+        let key = get_key(args.key);
+        let mode = if self.setup().config.fuzzy_match {
+            MatchMode::Fuzzy
+        } else {
+            MatchMode::Substring
+        };
+
+        let entry = self.find_journal(dir.clone(), &args.name, key.as_ref(), mode)?;
+        let journal = Journal::open(&entry, key.clone())?;
+        let encrypted = journal.encrypted();
+        let content = match String::from_utf8(journal.bytes()?) {
+            Ok(content) => content,
+            Err(_) => bail!("journal is not text: {}", entry.filename()),
+        };
+
+        let (preamble, sections) = split::sections(&content, args.level as usize);
+        if sections.is_empty() {
+            bail!(
+                "no level {} headings to split {} at",
+                args.level,
+                entry.filename()
+            );
+        }
+
+        // Name the new journals after their headings, with the extension of the journal
+        let ext = entry
+            .extension()
+            .map(|ext| format!(".{}", ext))
+            .unwrap_or_default();
+        let mut taken: HashSet<String> = self
+            .workspace_journals(&dir, key.as_ref())?
+            .iter()
+            .map(|e| e.filename())
+            .collect();
+        // Hidden and ignored journals aren't listed, but are never overwritten
+        let encrypts_filenames = self.encrypts_filenames(&dir);
+        let taken_on_disk = |name: &str| !encrypts_filenames && dir.push(name).exists();
+        let mut created = Vec::with_capacity(sections.len());
+        for (i, section) in sections.iter().enumerate() {
+            let mut slug = util::slugify(&section.title);
+            if slug.is_empty() {
+                slug = format!("section-{}", i + 1);
+            }
+
+            let mut name = format!("{}{}", slug, ext);
+            let mut n = 2;
+            while taken.contains(&name) || taken_on_disk(&name) {
+                name = format!("{}-{}{}", slug, n, ext);
+                n += 1;
+            }
+            taken.insert(name.clone());
+            created.push((name, section.content.lines().count()));
+        }
+
+        if !args.dry_run {
+            let backup_dir = self.backup(&[(dir.filename(), entry.clone())])?;
+            // The new journals are encrypted only if the journal was
+            let journal_key = if encrypted { key.as_ref() } else { None };
+
+            let mut index = if encrypts_filenames {
+                Some(NameIndex::load(&dir, key.as_ref())?)
+            } else {
+                None
+            };
+            for ((name, _), section) in created.iter().zip(&sections) {
+                let filepath = match index.as_mut() {
+                    Some(index) => index.insert(&dir, name)?,
+                    None => dir.push(name),
+                };
+                Journal::save_new(&filepath, journal_key, section.content.as_bytes())?;
+            }
+
+            // Only the content before the first heading is left in the journal
+            if preamble.trim().is_empty() {
+                fs::remove_file(entry.path())?;
+                if let Some(index) = index.as_mut() {
+                    index.remove(&dir, &entry.filename());
+                }
+            } else {
+                Journal::save(&entry, journal_key, preamble.as_bytes())?;
+            }
+            if let Some(index) = index {
+                index.save(&dir, key.as_ref())?;
+            }
+            self.cache.borrow_mut().clear();
+
+            self.output_hint(&format!("backup saved in {}", backup_dir.path().display()));
+        }
+
+        self.output(Output::Split {
+            journal: self.journal_path(&dir, &entry),
+            created,
+            dry_run: args.dry_run,
+        });
+        Ok(())
+    }
+
+    fn handle_watch(&self, args: WatchArgs) -> CmdResult {
+        let key = match get_key(args.key) {
+            Some(key) => key,
+            None => bail!("key required to re-encrypt journals"),
+        };
+        self.check_new_key(Some(&key))?;
+
+        let watched = match &args.workspace {
+            Some(w) => {
+                let dir = self.workspace_dir(w)?;
+                if !dir.exists() {
+                    bail!("workspace does not exists: {}", w);
+                }
+                dir
+            }
+            None => {
+                let dir = self.setup().workspaces_dir.clone();
+                if !dir.exists() {
+                    bail!("no workspaces to watch (hint: jn workspace create <name>)");
+                }
+                dir
+            }
+        };
+
+        let settle = Duration::from_secs(args.interval.max(1));
+        // Journals that should stay encrypted.
+        let mut encrypted: HashSet<PathBuf> = HashSet::new();
+        // When journals were last handled, so that writing a
+        // re-encrypted journal isn't taken as another change.
+        let mut seen: HashMap<PathBuf, SystemTime> = HashMap::new();
+
+        for (path, (_, entry, encrypts_names)) in self.watched_journals(&key, &args.workspace)? {
+            let journal = Journal::open(&entry, Some(key.clone()))?;
+            if journal.encrypted() || encrypts_names {
+                encrypted.insert(path.clone());
+            }
+            seen.insert(path, entry.modified()?);
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        let root = fs::canonicalize(watched.path())?;
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+
+        // Changed paths by when they last changed. They're handled once they
+        // stop changing, so that we don't read a file while it's being written.
+        let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+        self.output_info("Watching for changes, press ctrl-c to stop");
+
+        loop {
+            match rx.recv_timeout(settle) {
+                Ok(Ok(event)) => {
+                    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                        for path in event.paths {
+                            pending.insert(path, Instant::now());
+                        }
+                    }
+                }
+                Ok(Err(err)) => self.output_error(&format!("failed to watch: {}", err)),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => bail!("stopped watching for changes"),
+            }
+
+            let settled: Vec<PathBuf> = pending
+                .iter()
+                .filter(|(_, changed)| changed.elapsed() >= settle)
+                .map(|(path, _)| path.clone())
+                .collect();
+            if settled.is_empty() {
+                continue;
+            }
+            pending.retain(|path, _| !settled.contains(path));
+
+            let journals = self.watched_journals(&key, &args.workspace)?;
+            for path in settled {
+                // Other files, such as the temporary files of editors, are skipped.
+                let Some((name, entry, encrypts_names)) = journals.get(&path) else {
+                    continue;
+                };
+                if seen.get(&path) == Some(&entry.modified()?) {
+                    continue;
+                }
+
+                let journal = Journal::open(entry, Some(key.clone()))?;
+                if !journal.encrypted() && (*encrypts_names || encrypted.contains(&path)) {
+                    let content = journal.bytes()?;
+                    Journal::save(entry, Some(&key), &content)?;
+                    self.output_info(&format!("Re-encrypted {}", name.clone().green()));
+                } else {
+                    self.output_info(&format!("Changed {}", name));
+                }
+
+                if journal.encrypted() || *encrypts_names {
+                    encrypted.insert(path.clone());
+                }
+                seen.insert(path, entry.modified()?);
+
+                if let Some(cmd) = &args.exec {
+                    if let Err(err) = shell_command(cmd).status() {
+                        self.output_error(&format!("failed to run {}: {}", cmd, err));
+                    }
+                }
+            }
+        }
+    }
+
+    /// The journals watched by `jn watch` by their canonical path, with their name
+    /// and whether their workspace encrypts filenames.
+    fn watched_journals(
+        &self,
+        key: &String,
+        workspace: &Option<String>,
+    ) -> Result<HashMap<PathBuf, (String, FileEntry, bool)>> {
+        let mut workspaces = self.list_workspaces_files(Some(key))?;
+        if let Some(w) = workspace {
+            workspaces.retain(|name, _| name == w);
+        }
+
+        let mut journals = HashMap::new();
+        for workspace in workspaces.into_values() {
+            let dir = self.workspace_dir(&workspace.name)?;
+            let encrypts_names = self.encrypts_filenames(&dir);
+
+            for entry in workspace.files {
+                let path = fs::canonicalize(entry.path())?;
+                let name = format!("{}/{}", workspace.name, entry.filename());
+                journals.insert(path, (name, entry, encrypts_names));
+            }
+        }
+        Ok(journals)
+    }
+
+    fn handle_diff(&self, args: DiffArgs) -> CmdResult {
+        let dir = self.get_workspace(&args.workspace)?;
+        let key = get_key(args.key);
+        let mode = if self.setup().config.fuzzy_match {
+            MatchMode::Fuzzy
+        } else {
+            MatchMode::Substring
+        };
+
+        let entry = self.find_journal(dir.clone(), &args.name, key.as_ref(), mode)?;
+        let (old, new) = match (args.other, args.backup) {
+            (Some(other), _) => {
+                let other = self.find_journal(dir.clone(), &other, key.as_ref(), mode)?;
+                (entry, other)
+            }
+            (None, Some(n)) => (self.find_backup(&dir, &entry, n)?, entry),
+            (None, None) => bail!("journal name or backup required"),
+        };
+
+        let read =
+            |entry: &FileEntry| -> Result<String> { Journal::open(entry, key.clone())?.text() };
+        let edits = diff::diff(&read(&old)?, &read(&new)?);
+
+        let old_name = self.journal_path(&dir, &old);
+        let new_name = self.journal_path(&dir, &new);
+
+        let output = if args.stat {
+            let count = |f: fn(&diff::Edit) -> bool| edits.iter().filter(|e| f(e)).count();
+            Output::DiffStat {
+                old: old_name,
+                new: new_name,
+                insertions: count(|e| matches!(e, diff::Edit::Insert(_))),
+                deletions: count(|e| matches!(e, diff::Edit::Delete(_))),
+            }
+        } else {
+            let hunks = diff::hunks(&edits, DIFF_CONTEXT);
+            if hunks.is_empty() {
+                self.output_info("no differences");
+                return Ok(());
+            }
+            Output::Diff {
+                old: old_name,
+                new: new_name,
+                hunks,
+            }
+        };
+
+        self.output_paged(output, std::io::stdout().is_terminal());
+        Ok(())
+    }
+
+    /// The `n`th latest backup of the journal at `entry`, starting at 1.
+    fn find_backup(&self, dir: &FileEntry, entry: &FileEntry, n: usize) -> Result<FileEntry> {
+        let name = disk_filename(entry);
+
+        let mut backups: Vec<FileEntry> = if self.setup().backups_dir.exists() {
+            list_dirs(self.setup().backups_dir.as_ref())?
+                .into_iter()
+                .map(|backup| FileEntry::new(&backup.path().join(dir.filename()).join(&name)))
+                .filter(|backup| backup.exists())
+                .collect()
+        } else {
+            vec![]
+        };
+
+        // Backup directories are named by their timestamp
+        backups.sort_by(|a, b| b.path().cmp(a.path()));
+        let count = backups.len();
+        match n.checked_sub(1).and_then(|i| backups.into_iter().nth(i)) {
+            Some(backup) => Ok(backup.with_alias(&format!("{} (backup {})", entry.filename(), n))),
+            None if count == 0 => bail!("no backups of {}", entry.filename()),
+            None => bail!(
+                "no backup {} of {}, the oldest is backup {}",
+                n,
+                entry.filename(),
+                count
+            ),
+        }
+    }
+
+    /// The path of a journal shown to the user, i.e. {workspace}/{name}.
+    fn journal_path(&self, dir: &FileEntry, entry: &FileEntry) -> String {
+        format!("{}/{}", dir.filename(), entry.filename())
+    }
+
+    fn handle_log(&self, args: LogArgs) -> CmdResult {
+        let dir = self.get_workspace(&args.workspace)?;
+        self.create_workspace_dir(&dir)?;
+
+        let key = get_key(args.key);
+        let name = format!("{}.md", util::get_date(self.setup().zone));
+
+        let existing = self
+            .workspace_journals(&dir, key.as_ref())?
+            .into_iter()
+            .find(|entry| entry.filename() == name);
+
+        let mut index = None;
+        let (filepath, content, key) = match existing {
+            Some(filepath) => {
+                let journal = Journal::open(&filepath, key.clone())?;
+                let content = String::from_utf8(journal.bytes()?)?;
+                // Keep the journal encrypted, or plaintext, as it was
+                let key = if journal.encrypted() { key } else { None };
+                (filepath, content, key)
+            }
+            None => {
+                let (filepath, idx) = self.new_journal_path(&dir, &name, key.as_ref())?;
+                index = idx;
+                let content = self.template_for(&filepath);
+                (filepath, content, key)
+            }
+        };
+
+        let mut content = content;
+        if let Some(mood) = &args.mood {
+            content = frontmatter::set(&content, "mood", mood);
+        }
+        if let Some(message) = &args.message {
+            content = self.append_entry(&filepath, &content, message);
+        }
+        Journal::save(&filepath, key.as_ref(), content.as_bytes())?;
+        self.update_meta(|store| store.record(&meta_key(&filepath), &filepath))?;
+        if let Some(index) = index {
+            index.save(&dir, key.as_ref())?;
+        }
+
+        let what = if args.mood.is_some() {
+            "mood recorded in"
+        } else {
+            "added to"
+        };
+        self.output_hint(&format!("{} {}/{}", what, dir.filename(), name));
+        Ok(())
+    }
+
+    fn handle_append(&self, args: AppendArgs) -> CmdResult {
+        let dir = self.get_workspace(&args.workspace)?;
+        let key = get_key(args.key);
+        let mode = if self.setup().config.fuzzy_match {
+            MatchMode::Fuzzy
+        } else {
+            MatchMode::Substring
+        };
+        let filepath = self.find_journal(dir, &args.name, key.as_ref(), mode)?;
+
+        let text = match args.text {
+            Some(text) => text,
+            None => {
+                let mut text = String::new();
+                stdin().read_to_string(&mut text)?;
+                text
+            }
+        };
+        if text.trim().is_empty() {
+            bail!("nothing to add");
+        }
+
+        let journal = Journal::open(&filepath, key.clone())?;
+        let content = String::from_utf8(journal.bytes()?)?;
+        // Keep the journal encrypted, or plaintext, as it was
+        let key = if journal.encrypted() { key } else { None };
+
+        let content = self.append_entry(&filepath, &content, &text);
+        Journal::save(&filepath, key.as_ref(), content.as_bytes())?;
+        self.update_meta(|store| store.record(&meta_key(&filepath), &filepath))?;
+        Ok(())
+    }
+
+    /// Adds `text` to the end of `content`, as formatted by `append-template`.
+    fn append_entry(&self, filepath: &FileEntry, content: &str, text: &str) -> String {
+        let tmp = self.setup().config.append_template.as_deref();
+        let prompts = tmp.map(|tmp| self.prompts_for(tmp)).unwrap_or_default();
+
+        let mut content = content.to_string();
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&template::entry(
+            tmp,
+            &filepath.filename(),
+            &prompts,
+            self.setup().zone.now(),
+            text,
+        ));
+        content
+    }
+
+    fn handle_mood(&self, args: MoodArgs) -> CmdResult {
+        let key = get_key(args.key);
+
+        let mut journals: Vec<FileEntry> = Vec::new();
+        if args.workspace.is_empty() {
+            for workspace in self.list_workspaces_files(key.as_ref())?.into_values() {
+                journals.extend(workspace.files);
+            }
+        } else {
+            for dir in self.resolve_workspaces(&args.workspace)? {
+                journals.extend(self.workspace_journals(&dir, key.as_ref())?);
+            }
+        }
+
+        let format = if args.by == "month" {
+            "%Y-%m"
+        } else {
+            "%G-W%V"
+        };
+        let mut periods: BTreeMap<String, (u32, usize)> = BTreeMap::new();
+
+        for entry in journals {
+            let journal = Journal::open(&entry, key.clone())?;
+            let content = match journal.bytes().map(String::from_utf8) {
+                Ok(Ok(content)) => content,
+                // Skip journals that can't be decrypted or aren't text
+                _ => continue,
+            };
+
+            let fields = frontmatter::parse(&content);
+            let score = match fields.get("mood").and_then(|m| frontmatter::mood_score(m)) {
+                Some(score) => score,
+                None => continue,
+            };
+
+            let date = journal_date(&fields, &entry)?;
+
+            let period = periods.entry(date.format(format).to_string()).or_default();
+            period.0 += score as u32;
+            period.1 += 1;
+        }
+
+        if periods.is_empty() {
+            self.output_hint("no moods recorded (hint: jn log --mood 4)");
+            return Ok(());
+        }
+
+        let periods = periods
+            .into_iter()
+            .map(|(period, (sum, entries))| MoodPeriod {
+                period,
+                average: sum as f64 / entries as f64,
+                entries,
+            })
+            .collect();
+        self.output(Output::MoodReport(periods));
+        Ok(())
+    }
+
+    fn handle_todo(&self, args: TodoArgs) -> CmdResult {
+        let key = get_key(args.key);
+
+        let mut workspaces: Vec<(String, Vec<FileEntry>)> = Vec::new();
+        if args.workspace.is_empty() {
+            for (name, workspace) in self.list_workspaces_files(key.as_ref())? {
+                workspaces.push((name, workspace.files));
+            }
+        } else {
+            for dir in self.resolve_workspaces(&args.workspace)? {
+                let files = self.workspace_journals(&dir, key.as_ref())?;
+                workspaces.push((dir.filename(), files));
+            }
+        }
+        workspaces.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut results = Vec::new();
+        for (workspace, files) in workspaces {
+            for entry in files {
+                let journal = Journal::open(&entry, key.clone())?;
+                let content = match journal.bytes().map(String::from_utf8) {
+                    Ok(Ok(content)) => content,
+                    // Skip journals that can't be decrypted or aren't text
+                    _ => continue,
+                };
+
+                let org = entry.extension().as_deref() == Some("org");
+                let matches = todo::extract(&content, org);
+                if !matches.is_empty() {
+                    results.push(SearchResult {
+                        workspace: workspace.clone(),
+                        journal: entry.filename(),
+                        matches,
+                    });
+                }
+            }
+        }
+
+        if results.is_empty() {
+            self.output_hint("nothing to do");
+            return Ok(());
+        }
+
+        self.output(Output::Todos(results));
+        Ok(())
+    }
+
+    fn handle_remind(&self, args: RemindArgs) -> CmdResult {
+        let config = self.setup().config.remind.as_ref();
+        let entry_days = args
+            .entry_days
+            .or(config.and_then(|c| c.entry_days))
+            .unwrap_or(3);
+        let export_days = args
+            .export_days
+            .or(config.and_then(|c| c.export_days))
+            .unwrap_or(7);
+        let notify = args.notify || config.map(|c| c.notify).unwrap_or_default();
+
+        let now = chrono::Local::now();
+        let mut reminders: Vec<String> = Vec::new();
+
+        let mut workspaces = self.list_workspaces_files(None)?;
+        if let Some(names) = config.and_then(|c| c.workspaces.as_ref()) {
+            workspaces.retain(|name, _| names.contains(name));
+        }
+
+        for workspace in workspaces.into_values() {
+            let mut last: Option<SystemTime> = None;
+            for entry in &workspace.files {
+                let modified = entry.modified()?;
+                last = last.max(Some(modified));
+            }
+
+            let days = match last {
+                Some(last) => (now - chrono::DateTime::<chrono::Local>::from(last)).num_days(),
+                None => i64::MAX,
+            };
+
+            if days >= entry_days {
+                let msg = match last {
+                    Some(_) => format!(
+                        "Nothing written in workspace {} for {} days",
+                        workspace.name, days
+                    ),
+                    None => format!("Nothing written in workspace {}", workspace.name),
+                };
+                reminders.push(msg);
+            }
+        }
+
+        match State::load(&self.setup().state_file)?.last_export() {
+            Some(last) => {
+                let days = (now - last).num_days();
+                if days >= export_days {
+                    reminders.push(format!("Last export was {} days ago", days));
+                }
+            }
+            None => reminders.push("No export has been made".to_string()),
+        }
+
+        if reminders.is_empty() {
+            return Ok(());
+        }
+
+        for reminder in &reminders {
+            self.output_info(reminder);
+            if notify {
+                send_notification(reminder);
+            }
+        }
+
+        Err(ExitStatus(1).into())
+    }
+
+    fn handle_review(&self, args: ReviewArgs) -> CmdResult {
+        if let Some(ReviewCommand::Generate(args)) = args.command {
+            return self.handle_review_generate(args);
+        }
+
+        let key = get_key(args.key);
+        let workspaces = self.selected_workspaces(&args.workspace, key.as_ref())?;
+
+        let now = chrono::Local::now();
+        let state = State::load(&self.setup().state_file)?;
+        let mut journals = Vec::new();
+
+        for workspace in workspaces.into_values() {
+            for entry in &workspace.files {
+                let (last, opened) = match state.last_opened(&meta_key(entry)) {
+                    Some(time) => (time, true),
+                    None => (entry.modified()?.into(), false),
+                };
+
+                let days = (now - last).num_days();
+                if days >= args.days {
+                    journals.push(ReviewJournal {
+                        workspace: workspace.name.clone(),
+                        name: entry.filename(),
+                        days,
+                        opened,
+                    });
+                }
+            }
+        }
+
+        if journals.is_empty() {
+            self.output_hint(&format!(
+                "all journals opened in the last {} days",
+                args.days
+            ));
+            return Ok(());
+        }
+
+        journals.sort_by(|a, b| {
+            b.days
+                .cmp(&a.days)
+                .then_with(|| (&a.workspace, &a.name).cmp(&(&b.workspace, &b.name)))
+        });
+        self.output(Output::Review(journals));
+        Ok(())
+    }
+
+    /// Opens or prints a random journal of the workspaces, of those with the tag
+    /// and dated before --older-than if given. Encrypted journals are only
+    /// picked if they can be decrypted. Exits with 1 if none match.
+    fn handle_random(&self, args: RandomArgs) -> CmdResult {
+        let key = get_key(args.key);
+        let workspaces = self.selected_workspaces(&args.workspace, key.as_ref())?;
+        let zone = self.setup().zone;
+        let before = match &args.older_than {
+            Some(s) => Some(util::parse_since(s, zone, zone.now())?.date_naive()),
+            None => None,
+        };
+
+        let mut names: Vec<&String> = workspaces.keys().collect();
+        names.sort();
+        let mut candidates = Vec::new();
+        for name in names {
+            for entry in &workspaces[name].files {
+                // They can't be opened without the key
+                if key.is_none() && Journal::is_encrypted(entry)? {
+                    continue;
+                }
+                // Only read the journals when filtering by their front matter
+                let fields = match (&args.tag, before) {
+                    (None, None) => HashMap::new(),
+                    _ => match Journal::open(entry, key.clone())?.try_text()? {
+                        Some(content) => frontmatter::parse(&content),
+                        None => continue,
+                    },
+                };
+                if let Some(tag) = &args.tag {
+                    if !frontmatter::tags(&fields).contains(tag) {
+                        continue;
+                    }
+                }
+                if let Some(before) = before {
+                    if journal_date(&fields, entry)? >= before {
+                        continue;
+                    }
+                }
+                candidates.push((name.clone(), entry.filename()));
+            }
+        }
+
+        if candidates.is_empty() {
+            self.output_hint("no journals to pick from");
+            return Err(ExitStatus(1).into());
+        }
+        let random = crypto::random_bytes(8)?;
+        let index = u64::from_le_bytes(random.try_into().unwrap_or_default());
+        let (workspace, name) = candidates.swap_remove((index % candidates.len() as u64) as usize);
+
+        self.output_hint(&format!("{}/{}", workspace, name));
+        let print = args.print;
+        let args = OpenArgs {
+            name: Some(name),
+            index: None,
+            workspace: Some(workspace),
+            any_workspace: false,
+            key,
+            exact: true,
+            fuzzy: false,
+            reveal: args.reveal,
+            create: false,
+        };
+        self.handle_open(args, print)
+    }
+
+    /// Prints the journals dated on the same day as today, or --date, in earlier
+    /// years, or months with --monthly, oldest first. Journals dated only by
+    /// when they were modified are left out, as are those that aren't text.
+    fn handle_onthisday(&self, args: OnThisDayArgs) -> CmdResult {
+        let key = get_key(args.key);
+        let workspaces = self.selected_workspaces(&args.workspace, key.as_ref())?;
+        let today = args
+            .date
+            .unwrap_or_else(|| self.setup().zone.now().date_naive());
+        let sensitive_tags = self
+            .setup()
+            .config
+            .sensitive_tags
+            .clone()
+            .unwrap_or_default();
+
+        let mut journals = Vec::new();
+        for workspace in workspaces.into_values() {
+            for entry in &workspace.files {
+                let journal = Journal::open(entry, key.clone())?;
+                let content = journal.try_text()?;
+                if content.as_ref().is_some_and(|c| looks_binary(c.as_bytes())) {
+                    continue;
+                }
+                let fields = content
+                    .as_deref()
+                    .map(frontmatter::parse)
+                    .unwrap_or_default();
+                let date = match named_date(&fields, entry) {
+                    Some(date) => date,
+                    None => continue,
+                };
+                if date >= today
+                    || date.day() != today.day()
+                    || (!args.monthly && date.month() != today.month())
+                {
+                    continue;
+                }
+
+                let sensitive = journal.encrypted()
+                    && frontmatter::tags(&fields)
+                        .iter()
+                        .any(|tag| sensitive_tags.contains(tag));
+                journals.push(PastJournal {
+                    date,
+                    workspace: workspace.name.clone(),
+                    name: entry.filename(),
+                    content: content.filter(|_| !sensitive || args.reveal),
+                    sensitive,
+                });
+            }
+        }
+
+        if journals.is_empty() {
+            let earlier = if args.monthly { "months" } else { "years" };
+            self.output_hint(&format!("no journals from this day in earlier {}", earlier));
+            return Ok(());
+        }
+
+        journals
+            .sort_by(|a, b| (a.date, &a.workspace, &a.name).cmp(&(b.date, &b.workspace, &b.name)));
+        self.output_paged(Output::OnThisDay { today, journals }, !args.no_pager);
+        Ok(())
+    }
+
+    /// Counts the lines, words and characters of the named journals, or of all
+    /// journals in the workspace, leaving out those that can't be decrypted or
+    /// aren't text when counting all of them.
+    fn handle_wc(&self, args: WcArgs) -> CmdResult {
+        let dir = self.get_workspace(&args.workspace)?;
+        let key = get_key(args.key);
+        let mode = if self.setup().config.fuzzy_match {
+            MatchMode::Fuzzy
+        } else {
+            MatchMode::Substring
+        };
+
+        let mut counts = Vec::new();
+        if !args.name.is_empty() {
+            for name in &args.name {
+                let entry = self.find_journal(dir.clone(), name, key.as_ref(), mode)?;
+                let content = Journal::open(&entry, key.clone())?.text()?;
+                counts.push(WordCount::of(&entry.filename(), &content));
+            }
+        } else {
+            let mut skipped = 0;
+            for entry in self.workspace_journals(&dir, key.as_ref())? {
+                match Journal::open(&entry, key.clone())?.try_text()? {
+                    Some(content) if !looks_binary(content.as_bytes()) => {
+                        counts.push(WordCount::of(&entry.filename(), &content))
+                    }
+                    _ => skipped += 1,
+                }
+            }
+            if skipped > 0 {
+                let journals = if skipped == 1 { "journal" } else { "journals" };
+                self.output_hint(&format!(
+                    "skipped {} encrypted or binary {} (hint: use --key for those encrypted)",
+                    skipped, journals
+                ));
+            }
+        }
+
+        if counts.is_empty() {
+            self.output_hint("no journals to count (hint: jn create --help)");
+            return Ok(());
+        }
+        self.output(Output::WordCounts {
+            counts,
+            total: args.total,
+        });
+        Ok(())
+    }
+
+    fn handle_stats(&self, args: StatsArgs) -> CmdResult {
+        let key = get_key(args.key);
+        let patterns = match (&args.workspace, &self.setup().config.word_goal) {
+            (patterns, _) if !patterns.is_empty() => patterns.clone(),
+            (_, Some(goal)) => goal.workspaces.clone().unwrap_or_default(),
+            (_, None) => vec![],
+        };
+
+        let stats = self.word_stats(&patterns, key.as_ref())?;
+        self.output(Output::WordStats {
+            stats,
+            today: args.today,
+        });
+        Ok(())
+    }
+
+    /// Counts the journals in the workspaces matching `patterns`, or all workspaces,
+    /// and the words in them, except the front matter. Those dated today, by their
+    /// front matter, name or modification time, count as written today.
+    /// Journals that can't be decrypted with `key` aren't counted.
+    fn word_stats(&self, patterns: &[String], key: Option<&String>) -> Result<WordStats> {
+        let today = self.setup().zone.now().date_naive();
+        let mut stats = WordStats {
+            journals: 0,
+            words: 0,
+            today_journals: 0,
+            today_words: 0,
+            goal: self
+                .setup()
+                .config
+                .word_goal
+                .as_ref()
+                .map(|goal| goal.words),
+        };
+
+        for workspace in self.selected_workspaces(patterns, key)?.into_values() {
+            for entry in &workspace.files {
+                let content = match Journal::open(entry, key.cloned())?.try_text()? {
+                    Some(content) if !looks_binary(content.as_bytes()) => content,
+                    _ => continue,
+                };
+                let words = frontmatter::body(&content).split_whitespace().count();
+                stats.journals += 1;
+                stats.words += words;
+                if journal_date(&frontmatter::parse(&content), entry)? == today {
+                    stats.today_journals += 1;
+                    stats.today_words += words;
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Shows the words written today towards `word-goal`, after a journal was saved.
+    fn report_word_goal(&self, key: Option<&String>) {
+        let goal = match &self.setup().config.word_goal {
+            Some(goal) => goal,
+            None => return,
+        };
+        let patterns = goal.workspaces.clone().unwrap_or_default();
+        match self.word_stats(&patterns, key) {
+            Ok(stats) => self.output(Output::WordStats { stats, today: true }),
+            Err(err) => self.output_warning(&format!("failed to count words: {}", err)),
+        }
+    }
+
+    /// Creates a review journal with the journals dated in the last days,
+    /// by their front matter, name or modification time.
+    fn handle_review_generate(&self, args: ReviewGenerateArgs) -> CmdResult {
+        if args.days < 1 {
+            bail!("--days must be at least 1");
+        }
+
+        let key = get_key(args.key);
+        let workspaces = self.selected_workspaces(&args.workspace, key.as_ref())?;
+
+        let now = self.setup().zone.now();
+        let to = now.date_naive();
+        let from = to - chrono::Duration::days(args.days - 1);
+
+        let dir = self.get_workspace(&args.into)?;
+        let name = args
+            .name
+            .unwrap_or_else(|| format!("review-{}.md", util::get_date(self.setup().zone)));
+
+        let mut entries = Vec::new();
+        let mut skipped = 0;
+        for workspace in workspaces.into_values() {
+            for entry in &workspace.files {
+                if workspace.name == dir.filename() && entry.filename() == name {
+                    continue;
+                }
+
+                let bytes = match Journal::open(entry, key.clone())?.try_bytes()? {
+                    Some(bytes) => bytes,
+                    None => {
+                        skipped += 1;
+                        continue;
+                    }
+                };
+                let content = match String::from_utf8(bytes) {
+                    Ok(content) => content,
+                    Err(_) => continue,
+                };
+
+                let date = journal_date(&frontmatter::parse(&content), entry)?;
+                if date < from || date > to {
+                    continue;
+                }
+                entries.push(review::Entry {
+                    title: format!("{}/{}", workspace.name, entry.filename()),
+                    date,
+                    org: entry.extension().as_deref() == Some("org"),
+                    content: frontmatter::body(&content).to_string(),
+                });
+            }
+        }
+
+        if skipped > 0 {
+            self.output_hint(&format!(
+                "skipped {} encrypted journals (hint: use --key to include them)",
+                skipped
+            ));
+        }
+        if entries.is_empty() {
+            self.output_hint(&format!("no journals from the last {} days", args.days));
+            return Ok(());
+        }
+        entries.sort_by(|a, b| (a.date, &a.title).cmp(&(b.date, &b.title)));
+
+        self.create_workspace_dir(&dir)?;
+        let tmp = self
+            .setup()
+            .config
+            .review_template
+            .as_deref()
+            .unwrap_or(review::DEFAULT_TEMPLATE);
+        let prompts = self.prompts_for(tmp);
+        self.create_journal(&dir, &name, key, |filepath| {
+            let text = template::create(Some(tmp), &filepath.filename(), &prompts, now);
+            review::generate(&text, from, to, &entries, args.summary)
+        })
+    }
+
+    fn handle_dedupe(&self, args: DedupeArgs) -> CmdResult {
+        if !(0.0..=1.0).contains(&args.threshold) {
+            bail!("--threshold must be from 0 to 1");
+        }
+
+        let key = get_key(args.key);
+        let mut workspaces: Vec<Workspace> = self
+            .selected_workspaces(&args.workspace, key.as_ref())?
+            .into_values()
+            .collect();
+        workspaces.sort_by(|a, b| a.name.cmp(&b.name));
+
+        // The workspace directory, journal and if it's encrypted, by index
+        let mut journals: Vec<(FileEntry, FileEntry, bool)> = Vec::new();
+        let mut contents: Vec<(String, String)> = Vec::new();
+        let mut skipped = 0;
+        for workspace in workspaces {
+            let dir = self.workspace_dir(&workspace.name)?;
+            for entry in workspace.files {
+                let journal = Journal::open(&entry, key.clone())?;
+                let content = match journal.try_bytes()?.map(String::from_utf8) {
+                    Some(Ok(content)) => content,
+                    Some(Err(_)) => continue,
+                    None => {
+                        skipped += 1;
+                        continue;
+                    }
+                };
+                contents.push((digest(content.as_bytes())?, content));
+                journals.push((dir.clone(), entry, journal.encrypted()));
+            }
+        }
+
+        if skipped > 0 {
+            self.output_hint(&format!(
+                "skipped {} encrypted journals (hint: use --key)",
+                skipped
+            ));
+        }
+
+        let duplicates = dedupe::find(&contents, args.threshold);
+        if duplicates.exact.is_empty() && duplicates.near.is_empty() {
+            self.output_hint("no duplicates found");
+            return Ok(());
+        }
+
+        let name = |i: usize| self.journal_path(&journals[i].0, &journals[i].1);
+        self.output(Output::Duplicates {
+            exact: duplicates
+                .exact
+                .iter()
+                .map(|group| group.iter().map(|i| name(*i)).collect())
+                .collect(),
+            near: duplicates
+                .near
+                .iter()
+                .map(|(a, b, similarity)| (name(*a), name(*b), *similarity))
+                .collect(),
+        });
+        if !args.interactive {
+            return Ok(());
+        }
+
+        // Journals to remove, and (into, from) pairs to merge, by index
+        let mut removed: HashSet<usize> = HashSet::new();
+        let mut merges: Vec<(usize, usize)> = Vec::new();
+        for group in &duplicates.exact {
+            let mut options = vec!["Keep all".to_string()];
+            options.extend(group.iter().map(|i| format!("Keep only {}", name(*i))));
+            let selected = inquire::Select::new("Exact duplicates", options.clone()).prompt()?;
+            if let Some(keep) = options
+                .iter()
+                .position(|o| *o == selected)
+                .filter(|p| *p > 0)
+            {
+                removed.extend(group.iter().filter(|i| **i != group[keep - 1]));
+            }
+        }
+        // Journals merged, into another or from another, aren't offered again
+        let mut merged: HashSet<usize> = HashSet::new();
+        for (a, b, _) in &duplicates.near {
+            if [a, b]
+                .iter()
+                .any(|i| removed.contains(i) || merged.contains(i))
+            {
+                continue;
+            }
+            let options = vec![
+                "Keep both".to_string(),
+                format!("Remove {}", name(*a)),
+                format!("Remove {}", name(*b)),
+                format!("Merge {} into {}", name(*b), name(*a)),
+                format!("Merge {} into {}", name(*a), name(*b)),
+            ];
+            let selected = inquire::Select::new("Near duplicates", options.clone()).prompt()?;
+            match options.iter().position(|o| *o == selected) {
+                Some(1) => {
+                    removed.insert(*a);
+                }
+                Some(2) => {
+                    removed.insert(*b);
+                }
+                Some(3) => {
+                    merges.push((*a, *b));
+                    merged.extend([*a, *b]);
+                }
+                Some(4) => {
+                    merges.push((*b, *a));
+                    merged.extend([*a, *b]);
+                }
+                _ => {}
+            }
+        }
+        // Merged journals are removed once merged
+        removed.extend(merges.iter().map(|(_, from)| *from));
+        if removed.is_empty() {
+            return Ok(());
+        }
+
+        let mut changed: Vec<usize> = removed.iter().copied().collect();
+        changed.extend(merges.iter().map(|(into, _)| *into));
+        let backup: Vec<(String, FileEntry)> = changed
+            .iter()
+            .map(|i| (journals[*i].0.filename(), journals[*i].1.clone()))
+            .collect();
+        let backup_dir = self.backup(&backup)?;
+
+        for (into, content) in dedupe::merge_all(&contents, &merges) {
+            let (_, entry, encrypted) = &journals[into];
+            // Encrypted if any of the journals was, to not reveal encrypted content
+            let encrypt = *encrypted
+                || merges
+                    .iter()
+                    .any(|(i, from)| *i == into && journals[*from].2);
+            Journal::save(entry, key.as_ref().filter(|_| encrypt), content.as_bytes())?;
+            self.update_meta(|store| store.record(&meta_key(entry), entry))?;
+        }
+        for i in &removed {
+            let (dir, entry, _) = &journals[*i];
+            self.remove_journal(dir, entry, key.as_ref())?;
+        }
+
+        self.output_hint(&format!("backup saved in {}", backup_dir.path().display()));
+        Ok(())
+    }
+
+    fn handle_conflicts(&self, args: ConflictsArgs) -> CmdResult {
+        let key = get_key(args.key);
+        let dirs = if args.workspace.is_empty() {
+            self.workspace_dirs()?
+        } else {
+            self.resolve_workspaces(&args.workspace)?
+        };
+
+        // The workspace directory, conflicting copy and the journal it's a copy of.
+        // Sync tools rename files on disk, so copies of journals with encrypted
+        // filenames have no names in the index, and aren't found.
+        let mut found: Vec<(FileEntry, FileEntry, FileEntry)> = Vec::new();
+        for dir in dirs {
+            if self.encrypts_filenames(&dir) {
+                continue;
+            }
+            for entry in self.workspace_files(&dir, key.as_ref())? {
+                if let Some(original) = conflicts::original_name(&entry.filename()) {
+                    found.push((dir.clone(), entry, dir.push(&original)));
+                }
+            }
+        }
+        if found.is_empty() {
+            self.output_hint("no conflicts found");
+            return Ok(());
+        }
+
+        // The content of a journal, empty if it doesn't exist,
+        // or None if it's encrypted and can't be decrypted
+        let read = |entry: &FileEntry| -> Result<Option<String>> {
+            if !entry.exists() {
+                return Ok(Some(String::new()));
+            }
+            Journal::open(entry, key.clone())?.try_text()
+        };
+        let mut contents: Vec<Option<(String, String)>> = Vec::new();
+        let mut listed = Vec::new();
+        for (dir, conflict, original) in &found {
+            let content = match (read(original)?, read(conflict)?) {
+                (Some(old), Some(new)) => Some((old, new)),
+                _ => None,
+            };
+            let changes = content.as_ref().map(|(old, new)| {
+                let edits = diff::diff(old, new);
+                let count = |f: fn(&diff::Edit) -> bool| edits.iter().filter(|e| f(e)).count();
+                (
+                    count(|e| matches!(e, diff::Edit::Insert(_))),
+                    count(|e| matches!(e, diff::Edit::Delete(_))),
+                )
+            });
+            listed.push(Conflict {
+                copy: self.journal_path(dir, conflict),
+                original: self.journal_path(dir, original),
+                changes,
+            });
+            contents.push(content);
+        }
+        self.output(Output::Conflicts(listed));
+        if !args.interactive {
+            return Ok(());
+        }
+
+        let mut backup: Vec<(String, FileEntry)> = Vec::new();
+        for (dir, conflict, original) in &found {
+            backup.push((dir.filename(), conflict.clone()));
+            if original.exists() {
+                backup.push((dir.filename(), original.clone()));
+            }
+        }
+        let backup_dir = self.backup(&backup)?;
+
+        let mut resolved = 0;
+        for ((dir, conflict, original), content) in found.iter().zip(contents) {
+            let conflict_name = self.journal_path(dir, conflict);
+            let original_name = self.journal_path(dir, original);
+            let Some((old, new)) = content else {
+                self.output_hint(&format!(
+                    "skipped {}, since it's encrypted (hint: use --key)",
+                    conflict_name
+                ));
+                continue;
+            };
+
+            if !original.exists() {
+                let options = vec![
+                    "Keep both".to_string(),
+                    format!("Rename to {}", original_name),
+                    format!("Remove {}", conflict_name),
+                ];
+                let prompt = format!("{} is a copy of a removed journal", conflict_name);
+                let selected = inquire::Select::new(&prompt, options.clone()).prompt()?;
+                match options.iter().position(|o| *o == selected) {
+                    Some(1) => self.replace_journal(dir, conflict, original, key.as_ref())?,
+                    Some(2) => self.remove_journal(dir, conflict, key.as_ref())?,
+                    _ => continue,
+                }
+                resolved += 1;
+                continue;
+            }
+
+            let hunks = diff::hunks(&diff::diff(&old, &new), DIFF_CONTEXT);
+            let mut options = vec!["Keep both".to_string()];
+            if hunks.is_empty() {
+                self.output_info(&format!("{} has no differences", conflict_name));
+                options.push(format!("Remove {}", conflict_name));
+            } else {
+                self.output(Output::Diff {
+                    old: original_name.clone(),
+                    new: conflict_name.clone(),
+                    hunks,
+                });
+                options.extend([
+                    format!("Keep {}, removing the copy", original_name),
+                    format!("Keep the copy, replacing {}", original_name),
+                    format!("Merge the copy into {}", original_name),
+                ]);
+            }
+
+            let prompt = format!("Resolve {}", conflict_name);
+            let selected = inquire::Select::new(&prompt, options.clone()).prompt()?;
+            match options.iter().position(|o| *o == selected) {
+                Some(1) => self.remove_journal(dir, conflict, key.as_ref())?,
+                Some(2) => self.replace_journal(dir, conflict, original, key.as_ref())?,
+                Some(3) => {
+                    let merged = dedupe::merge(&old, &new);
+                    // Encrypted if either journal was, to not reveal encrypted content
+                    let encrypt =
+                        Journal::is_encrypted(original)? || Journal::is_encrypted(conflict)?;
+                    Journal::save(
+                        original,
+                        key.as_ref().filter(|_| encrypt),
+                        merged.as_bytes(),
+                    )?;
+                    self.update_meta(|store| store.record(&meta_key(original), original))?;
+                    self.remove_journal(dir, conflict, key.as_ref())?;
+                }
+                _ => continue,
+            }
+            resolved += 1;
+        }
+
+        if resolved > 0 {
+            self.output_hint(&format!("backup saved in {}", backup_dir.path().display()));
+        }
+        Ok(())
+    }
+
+    /// Replaces the journal at `entry` in the workspace `dir` with `other`,
+    /// as it's stored, e.g. with a conflicting copy of it.
+    fn replace_journal(
+        &self,
+        dir: &FileEntry,
+        other: &FileEntry,
+        entry: &FileEntry,
+        key: Option<&String>,
+    ) -> Result<()> {
+        fs::copy(other.path(), entry.path())?;
+        self.update_meta(|store| store.record(&meta_key(entry), entry))?;
+        self.remove_journal(dir, other, key)
+    }
+
+    /// Removes the journal at `entry` in the workspace `dir`, along with
+    /// its name in the index, its metadata and when it was opened.
+    fn remove_journal(
+        &self,
+        dir: &FileEntry,
+        entry: &FileEntry,
+        key: Option<&String>,
+    ) -> Result<()> {
+        fs::remove_file(entry.path())?;
+        if self.encrypts_filenames(dir) {
+            let mut index = NameIndex::load(dir, key)?;
+            index.remove(dir, &entry.filename());
+            index.save(dir, key)?;
+        }
+        self.cache.borrow_mut().clear();
+
+        self.update_meta(|store| store.remove(&meta_key(entry)))?;
+        self.forget_opened(entry)
+    }
+
+    fn handle_checksum(&self, args: ChecksumArgs) -> CmdResult {
+        let key = get_key(args.key);
+        let workspaces = self.selected_workspaces(&args.workspace, key.as_ref())?;
+
+        let mut digests = Vec::new();
+        let mut skipped = 0;
+        for workspace in workspaces.into_values() {
+            for entry in &workspace.files {
+                let bytes = if args.plaintext {
+                    match Journal::open(entry, key.clone())?.bytes() {
+                        Ok(bytes) => bytes,
+                        Err(_) => {
+                            skipped += 1;
+                            continue;
+                        }
+                    }
+                } else {
+                    entry.read_bytes()?
+                };
+                digests.push((digest(&bytes)?, meta_key(entry)));
+            }
+        }
+
+        if skipped > 0 {
+            self.output_hint(&format!(
+                "skipped {} encrypted journals (hint: use --key)",
+                skipped
+            ));
+        }
+        if digests.is_empty() {
+            self.output_hint("no journals (hint: jn create --help)");
+            return Ok(());
+        }
+
+        digests.sort_by(|a, b| a.1.cmp(&b.1));
+        self.output(Output::Checksums(digests));
+        Ok(())
+    }
+
+    fn handle_tui(&self, args: TuiArgs) -> CmdResult {
+        Tui::new(self, get_key(args.key)).run()
+    }
+
+    fn handle_workspace(&self, args: WorkspaceArgs) -> CmdResult {
+        match args.command {
+            WorkspaceCommand::List => {
+                let mut workspaces = Vec::new();
+                for dir in self.workspace_dirs()? {
+                    let meta = WorkspaceMeta::load(&dir)?;
+                    // Encrypted filenames can't be counted without a key
+                    let count = self.workspace_journals(&dir, None).ok().map(|j| j.len());
+                    workspaces.push((dir.filename(), meta, count));
+                }
+                workspaces.sort_by(|a, b| a.0.cmp(&b.0));
+
+                if workspaces.is_empty() {
+                    self.output_hint("no workspaces yet (hint: jn create --help)");
+                } else {
+                    self.output(Output::Workspaces(workspaces));
+                }
+            }
+            WorkspaceCommand::Create(args) => {
+                let dir = self.workspace_dir(&args.name)?;
+                if dir.exists() {
+                    bail!("workspace already exists: {}", args.name);
+                }
+                self.make_workspace_dir(&dir)?;
+            }
+            WorkspaceCommand::Describe(args) => {
+                let dir = self.workspace_dir(&args.name)?;
+                if !dir.exists() {
+                    bail!("workspace does not exists: {}", args.name);
+                }
+
+                if let Some(color) = &args.color {
+                    if Color::try_from(color.as_str()).is_err() {
+                        bail!("unknown color: {}", color);
+                    }
+                }
+
+                let mut meta = WorkspaceMeta::load(&dir)?;
+                if args.description.is_some() {
+                    meta.description = args.description;
+                }
+                if args.icon.is_some() {
+                    meta.icon = args.icon;
+                }
+                if args.color.is_some() {
+                    meta.color = args.color;
+                }
+                meta.save(&dir)?;
+            }
+            WorkspaceCommand::Status(args) => self.workspace_status(args)?,
+        }
+
+        Ok(())
+    }
+
+    fn workspace_status(&self, args: WorkspaceStatusArgs) -> CmdResult {
+        let mut keys = args.key;
+        if keys.is_empty() {
+            keys.extend(get_key(None));
+        }
+
+        let mut dirs = if args.workspace.is_empty() {
+            self.workspace_dirs()?
+        } else {
+            self.resolve_workspaces(&args.workspace)?
+        };
+        dirs.sort_by_key(|dir| dir.filename());
+
+        let mut statuses = Vec::new();
+        for dir in dirs {
+            let mut status = WorkspaceStatus {
+                name: dir.filename(),
+                counts: None,
+                keys: vec![0; keys.len()],
+                locked: 0,
+                plaintext_flagged: Vec::new(),
+            };
+
+            let journals = if self.encrypts_filenames(&dir) {
+                // Listed with the first key that opens the index
+                keys.iter()
+                    .find_map(|key| self.workspace_journals(&dir, Some(key)).ok())
+            } else {
+                Some(self.workspace_journals(&dir, None)?)
+            };
+            let journals = match journals {
+                Some(journals) => journals,
+                None => {
+                    statuses.push(status);
+                    continue;
+                }
+            };
+
+            let (mut encrypted, mut plaintext) = (0, 0);
+            for entry in journals {
+                if !Journal::is_encrypted(&entry)? {
+                    plaintext += 1;
+                    if self.encrypts_journals(&dir) {
+                        status.plaintext_flagged.push(entry.filename());
+                    }
+                    continue;
+                }
+
+                encrypted += 1;
+                let mut decrypted = false;
+                for (i, key) in keys.iter().enumerate() {
+                    let journal = Journal::open(&entry, Some(key.clone()))?;
+                    if journal.try_bytes()?.is_some() {
+                        status.keys[i] += 1;
+                        decrypted = true;
+                        break;
+                    }
+                }
+                if !decrypted {
+                    status.locked += 1;
+                }
+            }
+            status.counts = Some((encrypted, plaintext));
+            statuses.push(status);
+        }
+
+        if statuses.is_empty() {
+            self.output_hint("no workspaces yet (hint: jn create --help)");
+        } else {
+            self.output(Output::WorkspaceStatus(statuses));
+        }
+        Ok(())
+    }
+
+    fn handle_export(&self, args: ExportArgs) -> CmdResult {
+        if args.install_timer {
+            return self.install_export_timer(args);
+        }
+
+        // Encrypted journals were already handled when picking the export
+        let (args, target, ask_key) = match args.target.clone() {
+            Some(target) => (args, target, true),
+            None => match self.pick_export(args)? {
+                Some((args, target)) => (args, target, false),
+                None => return Ok(()),
+            },
+        };
+
+        let mut key = get_key(args.key.clone());
+        let mut workspaces = self.list_workspaces_files(key.as_ref())?;
+        if key.is_none()
+            && ask_key
+            && !args.keep_encrypted
+            && !args.non_interactive
+            && stdin().is_terminal()
+        {
+            key = self.prompt_export_key(&workspaces)?;
+            if key.is_some() {
+                workspaces = self.list_workspaces_files(key.as_ref())?;
+            }
+        }
+        if !args.workspace.is_empty() {
+            let selected: HashSet<String> = self
+                .resolve_workspaces(&args.workspace)?
+                .iter()
+                .map(|dir| dir.filename())
+                .collect();
+            workspaces.retain(|name, _| selected.contains(name));
+        }
+        if let Some(since) = &args.since {
+            // Copies of journals that aren't exported would be removed from the mirror
+            if target.trim() == "plaintext-mirror" {
+                bail!("--since isn't supported by the plaintext-mirror target");
+            }
+            let zone = self.setup().zone;
+            let cutoff = SystemTime::from(util::parse_since(since, zone, zone.now())?);
+            for ws in workspaces.values_mut() {
+                ws.files
+                    .retain(|entry| entry.modified().is_ok_and(|modified| modified >= cutoff));
+            }
+            workspaces.retain(|_, ws| !ws.files.is_empty());
+            if workspaces.is_empty() {
+                self.output_hint(&format!("no journals modified since {}", since));
+                return Ok(());
+            }
+        }
+
+        if workspaces.is_empty() {
+            self.output_hint("no workspaces to export (hint: jn create --help)");
+            return Ok(());
+        }
+
+        let mut names: Vec<&String> = workspaces.keys().collect();
+        names.sort();
+        for name in names {
+            self.check_quotas(name, &workspaces[name].files)?;
+        }
+
+        let remote = target.trim() == "azure" || target.starts_with("sftp://");
+        if args.dry_run && !remote {
+            bail!("--dry-run is only supported by remote targets");
+        }
+
+        let started = chrono::Local::now();
+        let timer = Instant::now();
+        let dir = args.dir.clone().unwrap_or_else(|| ".".to_string());
+        let mut destination = fs::canonicalize(&dir)
+            .map(|dir| dir.display().to_string())
+            .unwrap_or(dir);
+        let cancellation = cancel::install()?;
+        let output = match target.trim() {
+            "zip" => zip::export(
+                args.dir,
+                workspaces,
+                key,
+                &util::get_date(self.setup().zone),
+                !args.non_interactive,
+            )?,
+            "pdf" => pdf::export(
+                args.dir,
+                workspaces,
+                key,
+                &util::get_date(self.setup().zone),
+                args.combined,
+            )?,
+            "plaintext-mirror" => mirror::export(args.dir, workspaces, key)?,
+            target if remote => {
+                let (output, remote_destination) =
+                    self.export_remote(target, workspaces, key, args.keep_encrypted, args.dry_run)?;
+                destination = remote_destination;
+                output
+            }
+            target => bail!("unknown export target: {}", target),
+        };
+        drop(cancellation);
+        let cancelled = cancel::requested();
+
+        if args.dry_run {
+            self.output_hint("dry run, nothing was uploaded");
+            self.output(output);
+            return Ok(());
+        }
+
+        if let (Some(path), Output::ExportResult { exported, skipped }) = (&args.report, &output) {
+            let report = Report {
+                target: target.trim(),
+                destination,
+                started: started.to_rfc3339(),
+                duration_ms: timer.elapsed().as_millis() as u64,
+                exported,
+                skipped,
+            };
+            report.write(Path::new(path))?;
+        }
+
+        if cancelled {
+            let count = match &output {
+                Output::ExportResult { exported, .. } => exported.len(),
+                _ => 0,
+            };
+            self.output(output);
+            self.output_hint(&format!(
+                "export cancelled after exporting {} journals",
+                count
+            ));
+            return Err(ExitStatus(cancel::INTERRUPTED).into());
+        }
+
+        let mut state = State::load(&self.setup().state_file)?;
+        state.set_last_export(chrono::Local::now());
+        state.save(&self.setup().state_file)?;
+
+        self.output(output);
+
+        Ok(())
+    }
+
+    /// Asks for what to export when no target is given: the target, the
+    /// workspaces and what to do with encrypted journals, then confirms.
+    /// Returns the export and its target, or `None` if it isn't confirmed.
+    fn pick_export(&self, mut args: ExportArgs) -> Result<Option<(ExportArgs, String)>> {
+        if args.non_interactive || !stdin().is_terminal() {
+            bail!("no export target given (hint: use --target)");
+        }
+
+        let mut targets = vec!["zip", "pdf", "plaintext-mirror"];
+        if self
+            .setup()
+            .config
+            .export
+            .as_ref()
+            .and_then(|e| e.azure.as_ref())
+            .is_some()
+        {
+            targets.push("azure");
+        }
+        targets.push("sftp");
+        let mut target = inquire::Select::new("Export to", targets)
+            .prompt()?
+            .to_string();
+        if target == "sftp" {
+            let url =
+                inquire::Text::new("Destination, sftp://[user@]host[:port]/path:").prompt()?;
+            target = valid_export_target(&url)?;
+        }
+        if target == "plaintext-mirror" && args.dir.is_none() {
+            args.dir = Some(inquire::Text::new("Directory to mirror the journals to:").prompt()?);
+        }
+
+        let mut names: Vec<String> = self
+            .workspace_dirs()?
+            .iter()
+            .map(|dir| dir.filename())
+            .collect();
+        names.sort();
+        args.workspace = inquire::MultiSelect::new("Workspaces", names)
+            .with_all_selected_by_default()
+            .prompt()?;
+        if args.workspace.is_empty() {
+            self.output_hint("no workspaces selected");
+            return Ok(None);
+        }
+
+        let remote = target == "azure" || target.starts_with("sftp://");
+        let mut choices = vec!["Decrypt them", "Skip them"];
+        if remote {
+            choices.push("Upload them as stored");
+        }
+        match inquire::Select::new("Encrypted journals", choices).prompt()? {
+            "Decrypt them" => {
+                let key = inquire::Password::new("Key:")
+                    .without_confirmation()
+                    .prompt()?;
+                args.key = Some(key);
+            }
+            "Upload them as stored" => args.keep_encrypted = true,
+            _ => {}
+        }
+
+        let destination = match (&args.dir, remote) {
+            (Some(dir), false) => format!("{} in {}", target, dir),
+            _ => target.clone(),
+        };
+        let msg = format!("Export {} to {}?", args.workspace.join(", "), destination);
+        if !inquire::Confirm::new(&msg).with_default(true).prompt()? {
+            return Ok(None);
+        }
+        Ok(Some((args, target)))
+    }
+
+    /// Asks once for a key to decrypt journals with, if there are encrypted
+    /// journals or workspaces with encrypted filenames. An empty key skips them.
+    fn prompt_export_key(&self, workspaces: &Workspaces) -> Result<Option<String>> {
+        let mut encrypted = self
+            .workspace_dirs()?
+            .iter()
+            .any(|dir| self.encrypts_filenames(dir));
+        for ws in workspaces.values() {
+            for file in &ws.files {
+                encrypted = encrypted || Journal::is_encrypted(file)?;
+            }
+        }
+        if !encrypted {
+            return Ok(None);
+        }
+
+        let key = inquire::Password::new("Key for encrypted journals (empty to skip them):")
+            .without_confirmation()
+            .prompt()?;
+        Ok(Some(key).filter(|key| !key.is_empty()))
+    }
+
+    /// Uploads the journals to Azure or an SFTP server, skipping those unchanged since
+    /// the last export according to the manifest of the target. Returns the output
+    /// and where the journals were uploaded to.
+    fn export_remote(
+        &self,
+        target: &str,
+        workspaces: Workspaces,
+        key: Option<String>,
+        keep_encrypted: bool,
+        dry_run: bool,
+    ) -> Result<(Output, String)> {
+        if target == "azure" {
+            let config = match self
+                .setup()
+                .config
+                .export
+                .as_ref()
+                .and_then(|e| e.azure.as_ref())
+            {
+                Some(config) => config,
+                None => bail!("no Azure settings (hint: add [export.azure] to the config)"),
+            };
+            let destination = format!(
+                "https://{}.blob.core.windows.net/{}/{}",
+                config.account,
+                config.container,
+                config.prefix.as_deref().unwrap_or("")
+            );
+
+            let id = util::slugify(&format!("azure {}", destination));
+            let path = self.setup().manifests_dir.push(&format!("{}.json", id));
+            let mut manifest = Manifest::load(&path)?;
+            let output = azure::export(
+                config,
+                workspaces,
+                key,
+                keep_encrypted,
+                &mut manifest,
+                dry_run,
+            )?;
+            if !dry_run {
+                manifest.save(&path)?;
+            }
+            return Ok((output, destination));
+        }
+
+        let sftp_target = sftp::Target::parse(target)?;
+        let id = util::slugify(&format!("sftp {}", target));
+        let path = self.setup().manifests_dir.push(&format!("{}.json", id));
+        let mut manifest = Manifest::load(&path)?;
+        let output = sftp::export(
+            &sftp_target,
+            workspaces,
+            key,
+            keep_encrypted,
+            &mut manifest,
+            dry_run,
+        )?;
+        if !dry_run {
+            manifest.save(&path)?;
+        }
+        Ok((output, target.to_string()))
+    }
+
+    /// Exports the journal `entry` to the targets in `auto-export`, after it was
+    /// created or edited. Failures are warned about, since the journal was saved.
+    /// Removed journals are kept on the targets, as exports never delete from them.
+    fn auto_export(&self, entry: &FileEntry, key: Option<&String>) {
+        let targets = match &self.setup().config.auto_export {
+            Some(targets) if !targets.is_empty() => targets,
+            _ => return,
+        };
+        let workspace = match meta_key(entry).split_once('/') {
+            Some((workspace, _)) => workspace.to_string(),
+            None => return,
+        };
+
+        for target in targets {
+            let target = target.trim();
+            if target != "azure" && !target.starts_with("sftp://") {
+                self.output_warning(&format!(
+                    "can't auto-export to {}, only to azure and sftp:// targets",
+                    target
+                ));
+                continue;
+            }
+
+            let mut workspaces = Workspaces::new();
+            let ws = Workspace::new(workspace.clone(), vec![entry.clone()]);
+            workspaces.insert(workspace.clone(), ws);
+            match self.export_remote(target, workspaces, key.cloned(), false, false) {
+                Ok((Output::ExportResult { exported, skipped }, _)) => {
+                    for journal in exported {
+                        self.output_info(&format!("Exported {} to {}", journal.name, target));
+                    }
+                    for journal in skipped {
+                        if journal.status != ExportStatus::SkippedUnchanged {
+                            self.output_warning(&format!(
+                                "failed to auto-export {} to {}: {}",
+                                journal.name,
+                                target,
+                                journal.reason.unwrap_or_default()
+                            ));
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    self.output_warning(&format!("failed to auto-export to {}: {}", target, err))
+                }
+            }
+        }
+    }
+
+    /// Installs a timer running the export given by `args`. Paths are made absolute,
+    /// since the timer doesn't run in the current directory. Keys aren't stored,
+    /// so encrypted journals are skipped unless JOURNAL_KEY is set for the timer.
+    fn install_export_timer(&self, args: ExportArgs) -> CmdResult {
+        let absolute = |path: &str| -> Result<String> {
+            let path = env::current_dir()?.join(path);
+            let path = fs::canonicalize(&path).unwrap_or(path);
+            Ok(path.display().to_string())
+        };
+
+        let mut export_args: Vec<String> = vec![
+            "export".into(),
+            "--target".into(),
+            args.target.clone().unwrap_or_default(),
+            "--dir".into(),
+            absolute(args.dir.as_deref().unwrap_or("."))?,
+            "--non-interactive".into(),
+        ];
+        for workspace in &args.workspace {
+            export_args.extend(["--workspace".into(), workspace.clone()]);
+        }
+        if args.combined {
+            export_args.push("--combined".into());
+        }
+        if args.keep_encrypted {
+            export_args.push("--keep-encrypted".into());
+        }
+        if let Some(since) = &args.since {
+            export_args.extend(["--since".into(), since.clone()]);
+        }
+        if let Some(report) = &args.report {
+            export_args.extend(["--report".into(), absolute(report)?]);
+        }
+
+        let exe = env::current_exe()?;
+        let files = timer::install(&exe, &export_args, &args.schedule)?;
+        for file in files {
+            self.output_info(&format!("Wrote {}", file.display()));
+        }
+        self.output_info(&format!("Installed {} export timer", args.schedule));
+        if args.key.is_some() {
+            self.output_hint("the key isn't stored in the timer (hint: set JOURNAL_KEY for it)");
+        }
+        Ok(())
+    }
+
+    fn handle_config(&self, args: ConfigArgs) -> CmdResult {
+        match args.command {
+            // The REPL replaces its handler instead, see main.rs
+            ConfigCommand::Reload => {
+                self.output_hint("the configuration is loaded by every command outside the REPL")
+            }
+            ConfigCommand::Secret(args) => self.handle_config_secret(args)?,
+            ConfigCommand::Export(args) => {
+                let file = FileEntry::from(args.file.as_str());
+                let (files, left_out) =
+                    setup::export(&self.config_path, &file, args.include_secrets)?;
+                self.output(Output::Files {
+                    title: "Exported files",
+                    files,
+                    skipped: vec![],
+                });
+                if left_out > 0 {
+                    let secrets = if left_out == 1 { "secret" } else { "secrets" };
+                    self.output_hint(&format!(
+                        "left out {} {} (hint: use --include-secrets to include them)",
+                        left_out, secrets
+                    ));
+                }
+            }
+            ConfigCommand::Import(args) => {
+                let file = FileEntry::from(args.file.as_str());
+                let files = setup::import(&file, &self.config_path, args.force)?;
+                self.output(Output::Files {
+                    title: "Imported files",
+                    files,
+                    skipped: vec![],
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints the config value of a secret, read from the prompt or stdin,
+    /// that's either stored in the keyring or encrypted with the key.
+    fn handle_config_secret(&self, args: SecretArgs) -> CmdResult {
+        let value = match &args.keyring {
+            Some(reference) => {
+                let (service, account) = secrets::keyring_reference(reference)?;
+                keygen::store_in_keyring(service, account, &read_secret()?)?;
+                format!("{}{}", secrets::KEYRING_PREFIX, reference)
+            }
+            None => match get_key(args.key) {
+                Some(key) => secrets::encrypt(&read_secret()?, &key)?,
+                None => bail!("key required to encrypt the secret (hint: or use --keyring)"),
+            },
+        };
+
+        println!("{}", value);
+        self.output_hint("use it in the config in place of the secret, e.g. sas-token = \"…\"");
+        Ok(())
+    }
+
+    fn handle_bundle(&self, args: BundleArgs) -> CmdResult {
+        match args.command {
+            BundleCommand::Create(args) => {
+                let key = match get_key(args.key) {
+                    Some(key) => key,
+                    None => bail!("key required to encrypt bundle"),
+                };
+                self.check_new_key(Some(&key))?;
+
+                let workspaces = if args.workspace.is_empty() {
+                    self.workspace_dirs()?
+                } else {
+                    self.resolve_workspaces(&args.workspace)?
+                };
+                if workspaces.is_empty() {
+                    self.output_hint("no workspaces to bundle (hint: jn create --help)");
+                    return Ok(());
+                }
+
+                let file = FileEntry::from(args.file.as_str());
+                let files = bundle::create(&file, &workspaces, &key)?;
+                self.output(Output::Files {
+                    title: "Bundled files",
+                    files,
+                    skipped: vec![],
+                });
+            }
+            BundleCommand::Apply(args) => {
+                let key = match get_key(args.key) {
+                    Some(key) => key,
+                    None => bail!("key required to decrypt bundle"),
+                };
+
+                let file = FileEntry::from(args.file.as_str());
+                let changes = bundle::apply(
+                    &file,
+                    &self.setup().workspaces_dir,
+                    &key,
+                    args.force,
+                    args.dry_run,
+                )?;
+                self.cache.borrow_mut().clear();
+
+                if changes
+                    .iter()
+                    .any(|change| matches!(change, PlannedChange::Skip { .. }))
+                {
+                    self.output_hint(
+                        "existing files were skipped (hint: use --force to replace them)",
+                    );
+                }
+                if args.dry_run {
+                    self.output(Output::DryRun(changes));
+                    return Ok(());
+                }
+
+                let (mut files, mut skipped) = (Vec::new(), Vec::new());
+                for change in changes {
+                    match change {
+                        PlannedChange::Write { path, .. } => files.push(path),
+                        PlannedChange::Skip { path } => skipped.push(path),
+                        _ => {}
+                    }
+                }
+                self.output(Output::Files {
+                    title: "Imported files",
+                    files,
+                    skipped,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Handler {
+    fn handle_keygen(&self, args: KeygenArgs) -> CmdResult {
+        let key = keygen::generate(&args.format)?;
+
+        let usage = if let Some(output) = &args.output {
+            if Path::new(output).exists() {
+                bail!("file already exists: {}", output);
+            }
+            keygen::write_file(Path::new(output), &key)?;
+            self.output(Output::GeneratedKey {
+                key: None,
+                stored: Some(output.clone()),
+            });
+            format!("Use the key with: export JOURNAL_KEY=\"$(cat {})\"", output)
+        } else if args.keyring {
+            keygen::store_in_keyring(keygen::KEYRING_SERVICE, &args.account, &key)?;
+            self.output(Output::GeneratedKey {
+                key: None,
+                stored: Some(format!("the keyring, account '{}'", args.account)),
+            });
+            format!(
+                "Use the key with: export JOURNAL_KEY=\"$({})\"",
+                keygen::keyring_lookup(&args.account)
+            )
+        } else {
+            self.output_secret(Output::GeneratedKey {
+                key: Some(key),
+                stored: None,
+            });
+            "Use the key with --key or by setting JOURNAL_KEY.".to_string()
+        };
+
+        self.output_hint(&usage);
+        self.output_hint(
+            "Keep a copy somewhere safe, e.g. in a password manager or on paper: \
+             journals encrypted with it can't be recovered without it.",
+        );
+        Ok(())
+    }
+
+    fn handle_share(&self, args: ShareArgs) -> CmdResult {
+        if let Some(ShareCommand::Open(args)) = args.command {
+            let file = FileEntry::from(args.file.as_str());
+            if !file.exists() {
+                bail!("no such file: {}", args.file);
+            }
+
+            let passphrase = match args.passphrase {
+                Some(passphrase) => Some(passphrase),
+                None if share::is_age(&file)? => None,
+                None => Some(
+                    inquire::Password::new("Passphrase:")
+                        .without_confirmation()
+                        .prompt()?,
+                ),
+            };
+            let content = share::open(&file, passphrase.as_deref(), args.identity.as_deref())?;
+            stdout().write_all(&content)?;
+            return Ok(());
+        }
+
+        let name = args.name.unwrap_or_default();
+        let dir = self.get_workspace(&args.workspace)?;
+        let key = get_key(args.key);
+        let mode = if self.setup().config.fuzzy_match {
+            MatchMode::Fuzzy
+        } else {
+            MatchMode::Substring
+        };
+
+        let entry = self.find_journal(dir, &name, key.as_ref(), mode)?;
+        let content = Journal::open(&entry, key)?.bytes()?;
+
+        let output = match args.output {
+            Some(output) => output,
+            None => {
+                let filename = entry.filename();
+                let stem = Path::new(&filename)
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or(filename);
+                format!("{}.{}", stem, share::EXTENSION)
+            }
+        };
+        let file = FileEntry::from(output.as_str());
+        if file.exists() {
+            bail!("file already exists: {}", output);
+        }
+
+        let passphrase = match &args.recipient {
+            Some(recipient) => {
+                share::create(&file, &content, &Secret::Recipient(recipient))?;
+                None
+            }
+            None => {
+                let passphrase = share::passphrase()?;
+                share::create(&file, &content, &Secret::Passphrase(&passphrase))?;
+                Some(passphrase)
+            }
+        };
+
+        // The file can't be opened without the generated passphrase
+        let generated = passphrase.is_some();
+        let output = Output::Shared {
+            file: output,
+            passphrase,
+        };
+        if generated {
+            self.output_secret(output);
+        } else {
+            self.output(output);
+        }
+        Ok(())
+    }
+}
+
+/// Body of `PUT /journals/{workspace}/{name}`.
+#[derive(Deserialize)]
+struct PutJournal {
+    content: String,
+}
+
+/// Body of `POST /search`.
+#[derive(Deserialize)]
+struct SearchQuery {
+    pattern: String,
+    workspace: Option<String>,
+    #[serde(default)]
+    case_insensitive: bool,
+}
+
+impl Handler {
+    fn handle_serve(&self, args: ServeArgs) -> CmdResult {
+        let configured = match &self.setup().config.api_token {
+            Some(token) => Some(secrets::resolve(token, get_key(None).as_deref())?),
+            None => None,
+        };
+        let token = match args.token.or(configured) {
+            Some(token) => token,
+            None => {
+                let token = HEXLOWER.encode(&crypto::random_bytes(16)?);
+                println!("Generated token: {}", token.clone().bold());
+                token
+            }
+        };
+
+        let addr = format!("127.0.0.1:{}", args.port);
+        self.output_info(&format!("Listening on http://{}", addr));
+
+        server::serve(&addr, &token, |req| match self.route(req) {
+            Ok(res) => res,
+            Err(err) => Response::error(400, &err.to_string()),
+        })
+    }
+
+    /// Routes an API request. The key used for decryption
+    /// can be given in the `X-Journal-Key` header.
+    fn route(&self, req: &Request) -> Result<Response> {
+        let key = get_key(req.header("x-journal-key").map(String::from));
+        let segments = req.segments();
+        let segments: Vec<&str> = segments.iter().map(|s| s.as_str()).collect();
+
+        let res = match (req.method.as_str(), segments.as_slice()) {
+            ("GET", ["workspaces"]) => self.api_workspaces(key)?,
+            ("GET", ["journals", ws, name]) => self.api_get_journal(ws, name, key)?,
+            ("PUT", ["journals", ws, name]) => self.api_put_journal(ws, name, key, &req.body)?,
+            ("POST", ["search"]) => self.api_search(key, &req.body)?,
+            (_, ["workspaces"] | ["journals", _, _] | ["search"]) => {
+                Response::error(405, "method not allowed")
+            }
+            _ => Response::error(404, "not found"),
+        };
+
+        Ok(res)
+    }
+
+    fn api_workspaces(&self, key: Option<String>) -> Result<Response> {
+        let mut workspaces: Vec<serde_json::Value> = self
+            .list_workspaces_files(key.as_ref())?
+            .into_values()
+            .map(|ws| {
+                let journals: Vec<String> = ws.files.iter().map(|f| f.filename()).collect();
+                serde_json::json!({ "name": ws.name, "journals": journals })
+            })
+            .collect();
+        workspaces.sort_by_key(|ws| ws["name"].to_string());
+
+        Ok(Response::json(200, &workspaces))
+    }
+
+    fn api_get_journal(&self, ws: &str, name: &str, key: Option<String>) -> Result<Response> {
+        let dir = self.workspace_dir(&valid_workspace_name(ws)?)?;
+        if !dir.exists() {
+            return Ok(Response::error(404, "workspace not found"));
+        }
+
+        let entry = self
+            .workspace_journals(&dir, key.as_ref())?
+            .into_iter()
+            .find(|entry| entry.filename() == name);
+
+        let entry = match entry {
+            Some(entry) => entry,
+            None => return Ok(Response::error(404, "journal not found")),
+        };
+
+        let bytes = Journal::open(&entry, key)?.bytes()?;
+        let content = String::from_utf8_lossy(&bytes);
+        Ok(Response::json(
+            200,
+            &serde_json::json!({ "workspace": ws, "name": name, "content": content }),
+        ))
+    }
+
+    fn api_put_journal(
+        &self,
+        ws: &str,
+        name: &str,
+        key: Option<String>,
+        body: &[u8],
+    ) -> Result<Response> {
+        if name.contains(['/', '\\']) || name.starts_with('.') {
+            bail!("invalid journal name: {}", name);
+        }
+
+        let body: PutJournal = serde_json::from_slice(body)?;
+        let dir = self.workspace_dir(&valid_workspace_name(ws)?)?;
+        if !dir.exists() {
+            // Nobody can answer the prompt of `create-workspaces` here
+            let create = dir.path() == self.setup().default_workspace_dir.path()
+                || self.setup().config.create_workspaces == CreateWorkspaces::Always;
+            if !create {
+                return Ok(Response::error(404, "workspace not found"));
+            }
+            self.make_workspace_dir(&dir)?;
+        }
+
+        let existing = self
+            .workspace_journals(&dir, key.as_ref())?
+            .into_iter()
+            .find(|entry| entry.filename() == name);
+
+        let status = match existing {
+            Some(entry) => {
+                // Never replace an encrypted journal with plaintext, or another key.
+                let journal = Journal::open(&entry, key.clone())?;
+                if journal.encrypted() {
+                    if key.is_none() {
+                        bail!("key required for encrypted file");
+                    }
+                    let matches = match journal.key_matches()? {
+                        Some(matches) => matches,
+                        // Encrypted before headers had a key check
+                        None => journal.try_bytes()?.is_some(),
+                    };
+                    if !matches {
+                        return Ok(Response::error(401, "wrong key for encrypted journal"));
+                    }
+                }
+                Journal::save(&entry, key.as_ref(), body.content.as_bytes())?;
+                200
+            }
+            None if self.encrypts_filenames(&dir) => {
+                let mut index = NameIndex::load(&dir, key.as_ref())?;
+                let entry = index.insert(&dir, name)?;
+                Journal::save(&entry, key.as_ref(), body.content.as_bytes())?;
+                index.save(&dir, key.as_ref())?;
+                201
+            }
+            None => {
+                Journal::save(&dir.push(name), key.as_ref(), body.content.as_bytes())?;
+                201
+            }
+        };
+
+        Ok(Response::json(
+            status,
+            &serde_json::json!({ "workspace": ws, "name": name }),
+        ))
+    }
+
+    fn api_search(&self, key: Option<String>, body: &[u8]) -> Result<Response> {
+        let query: SearchQuery = serde_json::from_slice(body)?;
+        let re = RegexBuilder::new(&query.pattern)
+            .case_insensitive(query.case_insensitive)
+            .build()?;
+
+        let mut workspaces = self.list_workspaces_files(key.as_ref())?;
+        if let Some(ws) = &query.workspace {
+            workspaces.retain(|name, _| name == ws);
+        }
+
+        let (results, _) = self.search_journals(&re, workspaces, key, false)?;
+        Ok(Response::json(200, &results))
+    }
+}
+
+impl Handler {
+    fn output(&self, output: Output) {
+        if !self.quiet.get() {
+            println!("{}", self.setup().formatter.format(output));
+        }
+    }
+
+    /// Outputs even with --quiet, for generated secrets that would be lost otherwise.
+    fn output_secret(&self, output: Output) {
+        println!("{}", self.setup().formatter.format(output));
+    }
+
+    /// Outputs using the pager in $PAGER, or `less -R`, if `pager` is set
+    /// and the output doesn't fit in the terminal. Falls back to printing
+    /// directly if the pager can't be started.
+    fn output_paged(&self, output: Output, pager: bool) {
+        if self.quiet.get() {
+            return;
+        }
+
+        let text = self.setup().formatter.format(output);
+        if pager && stdout().is_terminal() {
+            let rows = crossterm::terminal::size().map_or(u16::MAX, |(_, rows)| rows);
+            if text.lines().count() >= rows as usize && page(&text).is_ok() {
+                return;
+            }
+        }
+
+        println!("{}", text);
+    }
+
+    fn output_error(&self, err: &str) {
+        eprintln!("{}: {}", "error".red(), err)
+    }
+
+    fn appearance(&self) -> Appearance {
+        self.setup().config.appearance.clone().unwrap_or_default()
+    }
+
+    /// Forgets when the removed journal at `filepath` was last opened.
+    fn forget_opened(&self, filepath: &FileEntry) -> Result<()> {
+        let mut state = State::load(&self.setup().state_file)?;
+        state.move_opened(&meta_key(filepath), None);
+        state.save(&self.setup().state_file)
+    }
+
+    /// Loads the metadata store, or `None` if it's not enabled.
+    fn meta_store(&self) -> Result<Option<MetaStore>> {
+        if !self.setup().config.metadata_store {
+            return Ok(None);
+        }
+        MetaStore::open(&self.setup().meta_file).map(Some)
+    }
+
+    /// Updates the metadata store with `update`, if it's enabled.
+    fn update_meta(&self, update: impl FnOnce(&mut MetaStore) -> Result<()>) -> Result<()> {
+        if let Some(mut store) = self.meta_store()? {
+            update(&mut store)?;
+            store.save()?;
+        }
+        Ok(())
+    }
+
+    /// Outputs a message about what was done, unless quiet.
+    fn output_info(&self, info: &str) {
+        if !self.quiet.get() {
+            println!("{}", info);
+        }
+    }
+
+    /// Shows how long the parts of a command took, on stderr so that
+    /// it's not mixed with the output, even with --quiet.
+    fn output_timings(&self, timings: Vec<perf::Timing>) {
+        for timing in timings {
+            let line = format!(
+                "{:<8} {:>10.1} ms {:>6}x",
+                timing.label,
+                timing.total.as_secs_f64() * 1000.0,
+                timing.count
+            );
+            eprintln!("{}", line.dark_grey());
+        }
+    }
+
+    fn output_hint(&self, hint: &str) {
+        if !self.quiet.get() {
+            eprintln!("{}", hint.dark_grey())
+        }
+    }
+
+    fn output_warning(&self, warning: &str) {
+        if !self.quiet.get() {
+            eprintln!("{}: {}", "warning".yellow(), warning)
+        }
+    }
+
+    /// Warns about the journals in `workspace` and the workspace
+    /// itself if they are larger than the configured quotas.
+    fn check_quotas(&self, workspace: &str, entries: &[FileEntry]) -> Result<()> {
+        let quotas = match &self.setup().config.quotas {
+            Some(quotas) => quotas,
+            None => return Ok(()),
+        };
+
+        let journals = entries
+            .iter()
+            .map(|entry| Ok((entry.filename(), entry.size()?)))
+            .collect::<Result<Vec<(String, u64)>>>()?;
+        for warning in quotas.warnings(workspace, &journals) {
+            self.output_warning(&warning);
+        }
+        Ok(())
+    }
+
+    /// Lists the workspace directories.
+    /// Nothing has been created on a fresh install, which isn't an error.
+    fn workspace_dirs(&self) -> Result<Vec<FileEntry>> {
+        if !self.setup().workspaces_dir.exists() {
+            return Ok(vec![]);
+        }
+
+        perf::time("walk", || list_dirs(self.setup().workspaces_dir.as_ref()))
+    }
+
+    /// Creates a list of tuples with workspace name and it's files.
+    /// Workspaces with encrypted filenames are skipped if no key is given.
+    pub(crate) fn list_workspaces_files(&self, key: Option<&String>) -> Result<Workspaces> {
+        let mut xs: Workspaces = HashMap::new();
+
+        for d in self.workspace_dirs()? {
+            if key.is_none() && self.encrypts_filenames(&d) {
+                continue;
+            }
+
+            let f = self.workspace_name(&d);
+            let files = self.workspace_journals(&d, key)?;
+            xs.insert(f.to_string(), Workspace::new(f, files));
+        }
+
+        Ok(xs)
+    }
+
+    /// Lists the journals in the workspace `dir`, resolving their names if the
+    /// workspace has encrypted filenames, except those ignored by .journalignore
+    /// and hidden journals unless --hidden is given. Symlinked directories
+    /// aren't followed, and broken symlinks are warned about. Conflicting
+    /// copies made by sync tools are left out, see `jn conflicts`.
+    fn workspace_journals(&self, dir: &FileEntry, key: Option<&String>) -> Result<Vec<FileEntry>> {
+        let mut files = self.workspace_files(dir, key)?;
+        files.retain(|entry| !conflicts::is_conflict(&entry.filename()));
+        Ok(files)
+    }
+
+    /// Lists the journals in the workspace `dir` like `workspace_journals`,
+    /// including conflicting copies.
+    fn workspace_files(&self, dir: &FileEntry, key: Option<&String>) -> Result<Vec<FileEntry>> {
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+
+        // Changes to the journals of a workspace update the modification time
+        // of its directory, or of the index if the filenames are encrypted.
+        let encrypted_names = self.encrypts_filenames(dir);
+        let mut modified = dirs_modified(dir.path())?;
+        if encrypted_names {
+            if let Ok(index_modified) = dir.push(INDEX_FILENAME).modified() {
+                modified = modified.max(index_modified);
+            }
+        }
+        for ignore_dir in [&self.setup().root_dir, dir] {
+            if let Ok(ignore_modified) = ignore_dir.push(IGNORE_FILENAME).modified() {
+                modified = modified.max(ignore_modified);
+            }
+        }
+
+        // Hidden journals are cached too, so that --hidden doesn't list again
+        let hidden = self.hidden.get();
+        let cache_key = (dir.path().to_path_buf(), key.cloned());
+        if let Some(cached) = self.cache.borrow().get(&cache_key) {
+            if cached.modified == modified {
+                let mut files = cached.journals.clone();
+                files.retain(|entry| hidden || !entry.is_hidden());
+                return Ok(files);
+            }
+        }
+
+        let ignore = Ignore::load(&[self.setup().root_dir.path(), dir.path()])?;
+        let mut files: Vec<FileEntry> = if encrypted_names {
+            perf::time("walk", || NameIndex::load(dir, key))?.entries(dir)
+        } else {
+            let listing = perf::time("walk", || list_files_recursive(dir.as_ref()))?;
+            for link in &listing.broken_links {
+                self.output_warning(&format!(
+                    "broken symlink: {}/{}",
+                    dir.filename(),
+                    link.filename()
+                ));
+            }
+            listing
+                .files
+                .into_iter()
+                .filter(|entry| {
+                    let name = entry.filename();
+                    name != INDEX_FILENAME && name != WORKSPACE_META_FILENAME
+                })
+                .collect()
+        };
+        files.retain(|entry| !ignore.is_ignored(&entry.filename()));
+
+        let cached = CachedJournals {
+            modified,
+            journals: files.clone(),
+        };
+        self.cache.borrow_mut().insert(cache_key, cached);
+        files.retain(|entry| hidden || !entry.is_hidden());
+        Ok(files)
+    }
+
+    /// The journals of the workspaces matching `patterns`, or of all workspaces
+    /// if there are none. Only then are workspaces with encrypted filenames
+    /// skipped without a key.
+    fn selected_workspaces(&self, patterns: &[String], key: Option<&String>) -> Result<Workspaces> {
+        if patterns.is_empty() {
+            return self.list_workspaces_files(key);
+        }
+
+        let mut workspaces = Workspaces::new();
+        for dir in self.resolve_workspaces(patterns)? {
+            let name = self.workspace_name(&dir);
+            let files = self.workspace_journals(&dir, key)?;
+            workspaces.insert(name.clone(), Workspace::new(name, files));
+        }
+        Ok(workspaces)
+    }
+
+    /// Resolves workspace names and glob patterns to workspace directories.
+    /// It's an error if a pattern doesn't match any existing workspace.
+    fn resolve_workspaces(&self, patterns: &[String]) -> Result<Vec<FileEntry>> {
+        let mut dirs: Vec<FileEntry> = Vec::new();
+
+        for pattern in patterns {
+            if !util::is_glob(pattern) {
+                dirs.push(self.workspace_dir(pattern)?);
+                continue;
+            }
+
+            // Patterns with a root only match the workspaces of other roots
+            let candidates = if pattern.contains(':') {
+                self.all_root_workspace_dirs()?
+            } else {
+                self.workspace_dirs()?
+            };
+            let matched: Vec<FileEntry> = candidates
+                .into_iter()
+                .filter(|dir| util::glob_match(pattern, &self.workspace_name(dir)))
+                .collect();
+            if matched.is_empty() {
+                bail!("no workspace matching: {}", pattern);
+            }
+            dirs.extend(matched);
+        }
+
+        let mut seen = HashSet::new();
+        dirs.retain(|dir| seen.insert(dir.path().to_path_buf()));
+        Ok(dirs)
+    }
+
+    fn encrypts_filenames(&self, dir: &FileEntry) -> bool {
+        match self.setup().config.workspace(&dir.filename()) {
+            Some(workspace) => workspace.encrypt_filenames,
+            None => false,
+        }
+    }
+
+    /// Whether the journals of the workspace `dir` are meant to be encrypted.
+    fn encrypts_journals(&self, dir: &FileEntry) -> bool {
+        match self.setup().config.workspace(&dir.filename()) {
+            Some(workspace) => workspace.encrypt || workspace.encrypt_filenames,
+            None => false,
+        }
+    }
+
+    fn get_workspace(&self, workspace: &Option<String>) -> Result<FileEntry> {
         match &workspace {
-            Some(w) => self.workspaces_dir.push(w),
-            None => self.default_workspace_dir.clone(),
+            Some(w) => self.workspace_dir(w),
+            None => Ok(self.setup().default_workspace_dir.clone()),
+        }
+    }
+
+    /// The directory of the workspace `name`, which is in another root
+    /// if it's named `{root}:{workspace}`.
+    fn workspace_dir(&self, name: &str) -> Result<FileEntry> {
+        match name.split_once(':') {
+            Some((root, workspace)) => match self.setup().roots.get(root) {
+                Some(dir) => Ok(dir.push(workspace)),
+                None => bail!(
+                    "no root named '{}' (hint: add [roots.{}] to the config)",
+                    root,
+                    root
+                ),
+            },
+            None => Ok(self.setup().workspaces_dir.push(name)),
+        }
+    }
+
+    /// The name of the workspace in `dir`, prefixed by its root if it's in another root.
+    fn workspace_name(&self, dir: &FileEntry) -> String {
+        let parent = dir.path().parent();
+        match self
+            .setup()
+            .roots
+            .iter()
+            .find(|(_, root)| parent == Some(root.path()))
+        {
+            Some((root, _)) => format!("{}:{}", root, dir.filename()),
+            None => dir.filename(),
+        }
+    }
+
+    /// The workspace directories of the root directory, followed by those of the other roots.
+    fn all_root_workspace_dirs(&self) -> Result<Vec<FileEntry>> {
+        let mut dirs = self.workspace_dirs()?;
+        let mut roots: Vec<_> = self.setup().roots.iter().collect();
+        roots.sort_by(|a, b| a.0.cmp(b.0));
+        for (_, root) in roots {
+            if root.exists() {
+                dirs.extend(perf::time("walk", || list_dirs(root.as_ref()))?);
+            }
+        }
+        Ok(dirs)
+    }
+}
+
+fn base_dirs() -> Result<directories::BaseDirs> {
+    match directories::BaseDirs::new() {
+        Some(basedir) => Ok(basedir),
+        None => bail!("failed to resolver user base directories"),
+    }
+}
+
+/// Splits `{root}:{workspace}/{journal}` into the workspace, named
+/// `{root}:{workspace}`, and the journal.
+fn split_address(name: &str) -> Option<(String, String)> {
+    let (root, rest) = name.split_once(':')?;
+    let (workspace, journal) = rest.split_once('/')?;
+    Some((format!("{}:{}", root, workspace), journal.to_string()))
+}
+
+/// Journals larger than this aren't searched unless configured otherwise.
+const DEFAULT_SEARCH_MAX_FILE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Number of bytes inspected when checking if a journal is binary.
+const BINARY_SNIFF_LENGTH: usize = 8192;
+
+/// Files with NUL bytes in the first block are taken to be binary,
+/// such as images attached to a workspace.
+fn looks_binary(prefix: &[u8]) -> bool {
+    prefix.contains(&0)
+}
+
+/// Maximum number of characters in snippets of multiline matches.
+const SNIPPET_LENGTH: usize = 80;
+
+/// Unchanged lines shown around the changes in a diff.
+const DIFF_CONTEXT: usize = 3;
+
+/// Finds the matches of `re` in the whole `content`, so that matches may span lines.
+/// They are reported by their starting line, with the whitespace collapsed.
+fn multiline_matches(re: &Regex, content: &str) -> Vec<LineMatch> {
+    re.find_iter(content)
+        .filter(|m| !m.as_str().is_empty())
+        .map(|m| {
+            let line = content[..m.start()].matches('\n').count() + 1;
+            let mut text = m.as_str().split_whitespace().collect::<Vec<_>>().join(" ");
+            if let Some((i, _)) = text.char_indices().nth(SNIPPET_LENGTH) {
+                text.truncate(i);
+                text.push('…');
+            }
+            LineMatch { line, text }
+        })
+        .collect()
+}
+
+/// The `date` in the front matter of a journal, or the date its name starts with.
+fn named_date(fields: &HashMap<String, String>, entry: &FileEntry) -> Option<chrono::NaiveDate> {
+    let parse = |s: &str| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").ok();
+    fields
+        .get("date")
+        .and_then(|d| parse(d))
+        .or_else(|| entry.filename().get(..10).and_then(parse))
+}
+
+/// The number of journals and matches in `results`, grouped `by` workspace,
+/// extension or month, where `entries` are the files of the journals by name.
+fn search_groups(
+    by: &str,
+    results: &[SearchResult],
+    entries: &HashMap<(String, String), FileEntry>,
+    key: Option<&String>,
+) -> Result<Output> {
+    let mut groups: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for result in results {
+        let name = match entries.get(&(result.workspace.clone(), result.journal.clone())) {
+            Some(entry) => group_name(by, &result.workspace, entry, key)?,
+            None => continue,
+        };
+        let group = groups.entry(name).or_default();
+        group.0 += 1;
+        group.1 += result.matches.len();
+    }
+
+    let groups = groups
+        .into_iter()
+        .map(|(name, (journals, matches))| Group {
+            name,
+            journals,
+            size: None,
+            matches: Some(matches),
+        })
+        .collect();
+    Ok(Output::Groups {
+        by: by.to_string(),
+        groups,
+    })
+}
+
+/// The group of a journal of `workspace` for `--group-by`: the workspace, the
+/// extension, or the month of the journal, dated as by `journal_date`.
+fn group_name(
+    by: &str,
+    workspace: &str,
+    entry: &FileEntry,
+    key: Option<&String>,
+) -> Result<String> {
+    match by {
+        "workspace" => Ok(workspace.to_string()),
+        "extension" => Ok(entry
+            .extension()
+            .map(|ext| ext.to_lowercase())
+            .unwrap_or_else(|| "(none)".to_string())),
+        _ => {
+            let fields = match Journal::open(entry, key.cloned())?.try_text()? {
+                Some(content) => frontmatter::parse(&content),
+                None => HashMap::new(),
+            };
+            Ok(journal_date(&fields, entry)?.format("%Y-%m").to_string())
+        }
+    }
+}
+
+/// The date of a journal: the `date` in its front matter, the date its
+/// name starts with, such as those created by `jn log`, or when it was modified.
+fn journal_date(fields: &HashMap<String, String>, entry: &FileEntry) -> Result<chrono::NaiveDate> {
+    if let Some(date) = named_date(fields, entry) {
+        return Ok(date);
+    }
+
+    let modified: chrono::DateTime<chrono::Local> = entry.modified()?.into();
+    Ok(modified.date_naive())
+}
+
+fn page(text: &str) -> Result<()> {
+    let pager = env::var("PAGER").unwrap_or_else(|_| "less -R".to_string());
+    let mut parts = pager.split_whitespace();
+    let program = match parts.next() {
+        Some(program) => program,
+        None => bail!("no pager configured"),
+    };
+
+    let mut child = process::Command::new(program)
+        .args(parts)
+        .stdin(process::Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager may be closed before all output is written.
+        let _ = writeln!(stdin, "{}", text);
+    }
+    child.wait()?;
+    Ok(())
+}
+
+/// The filename of the journal as on disk, relative to its workspace.
+/// It's random in workspaces with encrypted filenames, and the path
+/// from the workspace for journals in subdirectories.
+fn disk_filename(entry: &FileEntry) -> String {
+    let path = entry.path();
+    let name = entry.filename();
+    if name.contains('/') && path.ends_with(&name) {
+        return name;
+    }
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Key of a journal in the metadata store, i.e. {workspace}/{filename}
+/// with the filename as on disk.
+fn meta_key(entry: &FileEntry) -> String {
+    let filename = disk_filename(entry);
+    let depth = filename.split('/').count();
+    let workspace = entry
+        .path()
+        .ancestors()
+        .nth(depth)
+        .and_then(|p| p.file_name())
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    format!("{}/{}", workspace, filename)
+}
+
+/// Creates the directories of a journal in a subdirectory of its workspace.
+fn create_parent_dirs(filepath: &FileEntry) -> Result<()> {
+    if let Some(dir) = filepath.path().parent() {
+        fs::create_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+/// Removes the directories that a moved or removed journal at `filepath` was in,
+/// up to the workspace `dir`, that are now empty.
+fn remove_empty_dirs(filepath: &FileEntry, dir: &FileEntry) {
+    for parent in filepath.path().ancestors().skip(1) {
+        // Fails if the directory isn't empty
+        if parent == dir.path() || fs::remove_dir(parent).is_err() {
+            break;
         }
     }
 }
 
+/// A secret typed at the prompt, or read from stdin if it isn't a terminal.
+fn read_secret() -> Result<String> {
+    let secret = if stdin().is_terminal() {
+        inquire::Password::new("Secret:").prompt()?
+    } else {
+        let mut secret = String::new();
+        stdin().read_to_string(&mut secret)?;
+        secret.trim_end_matches(['\r', '\n']).to_string()
+    };
+    if secret.is_empty() {
+        bail!("no secret given");
+    }
+    Ok(secret)
+}
+
 fn get_key(from_args: Option<String>) -> Option<String> {
     if let Some(key) = from_args {
         return Some(key);
     }
 
-    match env::var("JOURNAL_KEY") {
-        Ok(key) => Some(key),
-        Err(_) => None,
+    env::var("JOURNAL_KEY").ok()
+}
+
+/// A command running `cmd` with the shell of the platform.
+fn shell_command(cmd: &str) -> process::Command {
+    let mut command = if cfg!(windows) {
+        let mut command = process::Command::new("cmd");
+        command.arg("/C");
+        command
+    } else {
+        let mut command = process::Command::new("sh");
+        command.arg("-c");
+        command
+    };
+    command.arg(cmd);
+    command
+}
+
+/// Shows `msg` as a desktop notification, if supported by the platform.
+fn send_notification(msg: &str) {
+    let mut cmd = if cfg!(target_os = "macos") {
+        let mut cmd = process::Command::new("osascript");
+        let script = format!("display notification {:?} with title \"journal\"", msg);
+        cmd.arg("-e").arg(script);
+        cmd
+    } else {
+        let mut cmd = process::Command::new("notify-send");
+        cmd.arg("journal").arg(msg);
+        cmd
+    };
+
+    // Notifications are best effort, the reminder has already been printed.
+    let _ = cmd.status();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{multiline_matches, Handler};
+    use crate::server::{Request, Response};
+    use crate::{cli::Cli, fs::FileEntry, state::State, types::Journal};
+    use anyhow::Result;
+    use clap::Parser;
+    use regex::RegexBuilder;
+    use std::path::PathBuf;
+
+    /// A root directory with a config file, removed when dropped.
+    struct Fixture {
+        dir: PathBuf,
+    }
+
+    impl Fixture {
+        fn new(name: &str, config: &str) -> Self {
+            let dir = PathBuf::from(format!("./temptestdir-{}", name));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            let config = format!("root = \"{}\"\n{}", dir.display(), config);
+            std::fs::write(dir.join("config.toml"), config).unwrap();
+            Self { dir }
+        }
+
+        fn handler(&self) -> Handler {
+            Handler::new(Some(&self.dir.join("config.toml"))).unwrap()
+        }
+
+        /// Writes the journal `name` in `workspace`, under `root` in the fixture.
+        fn journal(&self, root: &str, workspace: &str, name: &str, key: Option<&str>) {
+            let dir = self.dir.join(root).join("workspaces").join(workspace);
+            std::fs::create_dir_all(&dir).unwrap();
+            let key = key.map(|k| k.to_string());
+            Journal::save(&FileEntry::new(&dir.join(name)), key.as_ref(), b"# Notes").unwrap();
+        }
+
+        fn state(&self) -> State {
+            State::load(&FileEntry::new(&self.dir.join("state.json"))).unwrap()
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn run(handler: &Handler, args: &[&str]) -> Result<()> {
+        let args = std::iter::once("jn").chain(args.iter().copied());
+        handler.handle(Cli::parse_from(args))
+    }
+
+    /// Routes an API request, as `jn serve` does, with the key in `X-Journal-Key` if given.
+    fn request(
+        handler: &Handler,
+        method: &str,
+        path: &str,
+        key: Option<&str>,
+        body: &str,
+    ) -> Response {
+        handler.load_setup().unwrap();
+        let headers: Vec<(&str, &str)> = key.map(|k| ("x-journal-key", k)).into_iter().collect();
+        let req = Request::new(method, path, &headers, body.as_bytes());
+        match handler.route(&req) {
+            Ok(res) => res,
+            Err(err) => Response::error(400, &err.to_string()),
+        }
+    }
+
+    #[test]
+    fn listing_filtered_journals_by_index() {
+        let fx = Fixture::new("filtered-listing", "");
+        fx.journal(".", "default", "a.md", None);
+        fx.journal(".", "default", "b.md", Some("a long secret key"));
+        let handler = fx.handler();
+
+        run(&handler, &["list", "--encrypted-only"]).unwrap();
+
+        let state = fx.state();
+        assert_eq!(state.listed_journal("default", 1).unwrap(), "b.md");
+        assert!(state.listed_journal("default", 2).is_err());
+    }
+
+    #[test]
+    fn opening_by_index_in_other_root() {
+        let fx = Fixture::new(
+            "other-root-index",
+            "[roots.other]\npath = \"./temptestdir-other-root-index/other\"\n",
+        );
+        fx.journal("other", "notes", "a.md", None);
+        let handler = fx.handler();
+
+        run(&handler, &["list", "-w", "other:notes"]).unwrap();
+        run(&handler, &["print", "--index", "1", "-w", "other:notes"]).unwrap();
+
+        assert_eq!(fx.state().listed_journal("other:notes", 1).unwrap(), "a.md");
+    }
+
+    #[test]
+    fn creating_range_in_subdirectories() {
+        let fx = Fixture::new("range-subdirectories", "");
+        let handler = fx.handler();
+
+        let args = ["create", "--pattern", "daily/{date}.md"];
+        let dates = ["--from", "2024-06-05", "--to", "2024-06-06"];
+        run(&handler, &[&args[..], &dates[..]].concat()).unwrap();
+
+        let dir = fx.dir.join("workspaces").join("default").join("daily");
+        assert!(dir.join("2024-06-05.md").exists());
+        assert!(dir.join("2024-06-06.md").exists());
+    }
+
+    #[test]
+    fn creating_range_outside_workspace() {
+        let fx = Fixture::new("range-outside", "");
+        let handler = fx.handler();
+
+        let args = ["create", "--pattern", "../{date}.md"];
+        let dates = ["--from", "2024-06-05", "--to", "2024-06-05"];
+        let result = run(&handler, &[&args[..], &dates[..]].concat());
+
+        assert!(result.is_err());
+        assert!(!fx.dir.join("workspaces").join("2024-06-05.md").exists());
+    }
+
+    #[test]
+    fn getting_workspaces_and_journals() {
+        let fx = Fixture::new("api-get", "");
+        fx.journal(".", "default", "a.md", None);
+        let handler = fx.handler();
+
+        let res = request(&handler, "GET", "/workspaces", None, "");
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.body()[0]["name"], "default");
+        assert_eq!(res.body()[0]["journals"][0], "a.md");
+
+        let res = request(&handler, "GET", "/journals/default/a.md", None, "");
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.body()["content"], "# Notes");
+
+        let res = request(&handler, "GET", "/journals/default/b.md", None, "");
+        assert_eq!(res.status(), 404);
+        let res = request(&handler, "GET", "/journals/missing/a.md", None, "");
+        assert_eq!(res.status(), 404);
+    }
+
+    #[test]
+    fn routing_unknown_requests() {
+        let fx = Fixture::new("api-unknown", "");
+        let handler = fx.handler();
+
+        assert_eq!(request(&handler, "GET", "/unknown", None, "").status(), 404);
+        assert_eq!(
+            request(&handler, "DELETE", "/workspaces", None, "").status(),
+            405
+        );
+    }
+
+    #[test]
+    fn putting_journals() {
+        let fx = Fixture::new("api-put", "");
+        fx.journal(".", "default", "a.md", None);
+        let handler = fx.handler();
+        let body = r#"{"content": "Updated"}"#;
+
+        let res = request(&handler, "PUT", "/journals/default/a.md", None, body);
+        assert_eq!(res.status(), 200);
+        let res = request(&handler, "PUT", "/journals/default/b.md", None, body);
+        assert_eq!(res.status(), 201);
+
+        let dir = fx.dir.join("workspaces").join("default");
+        assert_eq!(
+            std::fs::read_to_string(dir.join("a.md")).unwrap(),
+            "Updated"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("b.md")).unwrap(),
+            "Updated"
+        );
+    }
+
+    #[test]
+    fn putting_journals_in_missing_workspace() {
+        let fx = Fixture::new("api-put-missing", "create-workspaces = \"never\"\n");
+        let handler = fx.handler();
+        let body = r#"{"content": "Notes"}"#;
+
+        let res = request(&handler, "PUT", "/journals/work/a.md", None, body);
+
+        assert_eq!(res.status(), 404);
+        assert!(!fx.dir.join("workspaces").join("work").exists());
+    }
+
+    #[test]
+    fn putting_encrypted_journals_with_wrong_key() {
+        let fx = Fixture::new("api-put-key", "");
+        fx.journal(".", "default", "a.md", Some("a long secret key"));
+        let handler = fx.handler();
+        let body = r#"{"content": "Replaced"}"#;
+
+        let res = request(
+            &handler,
+            "PUT",
+            "/journals/default/a.md",
+            Some("another long key"),
+            body,
+        );
+        assert_eq!(res.status(), 401);
+        let res = request(&handler, "PUT", "/journals/default/a.md", None, body);
+        assert_eq!(res.status(), 400);
+
+        let res = request(
+            &handler,
+            "GET",
+            "/journals/default/a.md",
+            Some("a long secret key"),
+            "",
+        );
+        assert_eq!(res.body()["content"], "# Notes");
+    }
+
+    #[test]
+    fn searching_journals() {
+        let fx = Fixture::new("api-search", "");
+        fx.journal(".", "default", "a.md", None);
+        fx.journal(".", "work", "b.md", None);
+        let handler = fx.handler();
+
+        let body = r#"{"pattern": "notes", "case_insensitive": true, "workspace": "work"}"#;
+        let res = request(&handler, "POST", "/search", None, body);
+
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.body().as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn saving_input_as_new_journal() {
+        let fx = Fixture::new("edit-stdin", "");
+        let handler = fx.handler();
+        handler.load_setup().unwrap();
+        let dir = FileEntry::new(&fx.dir.join("workspaces").join("default"));
+
+        // Fails if the editor is opened, as there's none to open
+        handler
+            .save_input(&dir, "notes.md", None, b"# From stdin")
+            .unwrap();
+
+        let saved = std::fs::read_to_string(dir.path().join("notes.md")).unwrap();
+        assert_eq!(saved, "# From stdin");
+    }
+
+    #[test]
+    fn saving_input_in_encrypted_workspace_without_key() {
+        let config = "[workspaces.secret]\nencrypt = true\n";
+        let fx = Fixture::new("edit-stdin-encrypted", config);
+        let handler = fx.handler();
+        handler.load_setup().unwrap();
+        let dir = FileEntry::new(&fx.dir.join("workspaces").join("secret"));
+
+        let result = handler.save_input(&dir, "notes.md", None, b"# From stdin");
+
+        assert!(result.is_err());
+        assert!(!dir.path().join("notes.md").exists());
+    }
+
+    #[test]
+    fn undoing_changes_in_other_root() {
+        let fx = Fixture::new(
+            "other-root-undo",
+            "[roots.other]\npath = \"./temptestdir-other-root-undo/other\"\n",
+        );
+        fx.journal("other", "notes", "a.md", None);
+        let handler = fx.handler();
+        let dir = fx.dir.join("other").join("workspaces").join("notes");
+
+        run(&handler, &["remove", "a.md", "-w", "other:notes"]).unwrap();
+        assert!(!dir.join("a.md").exists());
+        run(&handler, &["undo"]).unwrap();
+        assert!(dir.join("a.md").exists());
+
+        run(&handler, &["rename", "a.md", "b.md", "-w", "other:notes"]).unwrap();
+        run(&handler, &["undo"]).unwrap();
+        assert!(dir.join("a.md").exists());
+        assert!(!dir.join("b.md").exists());
+        assert!(!fx.dir.join("workspaces").join("notes").exists());
+    }
+
+    #[test]
+    fn creating_range_keeps_hidden_journals() {
+        let fx = Fixture::new("range-hidden", "");
+        fx.journal(".", "default", ".2024-06-05.md", None);
+        let handler = fx.handler();
+
+        let args = ["create", "--pattern", ".{date}.md"];
+        let dates = ["--from", "2024-06-05", "--to", "2024-06-06"];
+        run(&handler, &[&args[..], &dates[..]].concat()).unwrap();
+
+        let dir = fx.dir.join("workspaces").join("default");
+        let kept = std::fs::read_to_string(dir.join(".2024-06-05.md")).unwrap();
+        assert_eq!(kept, "# Notes");
+        assert!(dir.join(".2024-06-06.md").exists());
+    }
+
+    #[test]
+    fn splitting_keeps_ignored_journals() {
+        let fx = Fixture::new("split-ignored", "");
+        fx.journal(".", "default", "intro.md", None);
+        let dir = fx.dir.join("workspaces").join("default");
+        std::fs::write(dir.join(".journalignore"), "intro.md\n").unwrap();
+        std::fs::write(dir.join("notes.md"), "## Intro\nFirst\n## Outro\nLast\n").unwrap();
+        let handler = fx.handler();
+
+        run(&handler, &["split", "notes.md"]).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dir.join("intro.md")).unwrap(),
+            "# Notes"
+        );
+        assert!(dir.join("intro-2.md").exists());
+        assert!(dir.join("outro.md").exists());
+    }
+
+    #[test]
+    fn logging_in_encrypted_workspace_without_key() {
+        let config = "[workspaces.secret]\nencrypt = true\n";
+        let fx = Fixture::new("log-encrypted", config);
+        let handler = fx.handler();
+
+        let result = run(&handler, &["log", "Hello", "-w", "secret"]);
+
+        assert!(result.is_err());
+        let dir = fx.dir.join("workspaces").join("secret");
+        assert_eq!(std::fs::read_dir(dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn matching_across_lines() {
+        let re = RegexBuilder::new("wrapped.*sentence")
+            .dot_matches_new_line(true)
+            .build()
+            .unwrap();
+        let content = "# Notes\nthis is a wrapped\n  long sentence\nend";
+
+        let matches = multiline_matches(&re, content);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].line, 2);
+        assert_eq!(matches[0].text, "wrapped long sentence");
     }
 }
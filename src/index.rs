@@ -0,0 +1,103 @@
+use crate::crypto;
+use crate::fs::FileEntry;
+use crate::types::Journal;
+use anyhow::{bail, Result};
+use data_encoding::HEXLOWER;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Name of the encrypted index file in workspaces with encrypted filenames.
+pub const INDEX_FILENAME: &str = ".index";
+
+/// Maps logical journal names to the randomly named files
+/// they are stored as in a workspace with encrypted filenames.
+///
+/// The index itself is stored encrypted in the workspace directory,
+/// so the key is required to resolve any journal in the workspace.
+#[derive(Default, Deserialize, Serialize)]
+pub struct NameIndex {
+    names: BTreeMap<String, String>,
+}
+
+impl NameIndex {
+    /// Loads the index of the workspace `dir`.
+    /// Returns an empty index if the workspace has no index yet.
+    pub fn load(dir: &FileEntry, key: Option<&String>) -> Result<Self> {
+        let key = match key {
+            Some(key) => key,
+            None => bail!(
+                "key required for workspace with encrypted filenames: {}",
+                dir.filename()
+            ),
+        };
+
+        let path = dir.push(INDEX_FILENAME);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let journal = Journal::open(&path, Some(key.to_string()))?;
+        let bytes = journal.bytes()?;
+        let index: NameIndex = serde_json::from_slice(&bytes)?;
+        Ok(index)
+    }
+
+    pub fn save(&self, dir: &FileEntry, key: Option<&String>) -> Result<()> {
+        let key = match key {
+            Some(key) => key,
+            None => bail!("key required to save filename index"),
+        };
+
+        let bytes = serde_json::to_vec(self)?;
+        Journal::save(&dir.push(INDEX_FILENAME), Some(key), &bytes)
+    }
+
+    /// The journals of the workspace, displayed by their logical names.
+    pub fn entries(&self, dir: &FileEntry) -> Vec<FileEntry> {
+        self.names
+            .iter()
+            .map(|(name, physical)| dir.push(physical).with_alias(name))
+            .collect()
+    }
+
+    /// Adds `name` to the index under a new random filename.
+    pub fn insert(&mut self, dir: &FileEntry, name: &str) -> Result<FileEntry> {
+        if self.names.contains_key(name) {
+            bail!("journal {} already exists (hint: jn open --help)", name);
+        }
+
+        let physical = HEXLOWER.encode(&crypto::random_bytes(16)?);
+        let entry = dir.push(&physical).with_alias(name);
+        self.names.insert(name.to_string(), physical);
+        Ok(entry)
+    }
+
+    pub fn remove(&mut self, dir: &FileEntry, name: &str) -> Option<FileEntry> {
+        self.names
+            .remove(name)
+            .map(|physical| dir.push(&physical).with_alias(name))
+    }
+
+    /// Adds `name` back to the index as the existing file `physical`.
+    pub fn restore(&mut self, name: &str, physical: &str) -> Result<()> {
+        if self.names.contains_key(name) {
+            bail!("journal {} already exists", name);
+        }
+        self.names.insert(name.to_string(), physical.to_string());
+        Ok(())
+    }
+
+    pub fn rename(&mut self, old: &str, new: &str) -> Result<()> {
+        if self.names.contains_key(new) {
+            bail!("journal {} already exists", new);
+        }
+
+        match self.names.remove(old) {
+            Some(physical) => {
+                self.names.insert(new.to_string(), physical);
+                Ok(())
+            }
+            None => bail!("no journal named: {}", old),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use crate::crypto;
+use anyhow::{bail, Context, Result};
+use data_encoding::{BASE64, HEXLOWER};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Number of random bytes in generated keys, as many as the encryption key has.
+const KEY_BYTES: usize = 32;
+
+/// Service that generated keys are stored under in the OS keyring.
+pub const KEYRING_SERVICE: &str = "journal";
+
+/// Generates a random key, encoded as `base64` or `hex`.
+pub fn generate(format: &str) -> Result<String> {
+    let bytes = crypto::random_bytes(KEY_BYTES)?;
+    match format {
+        "base64" => Ok(BASE64.encode(&bytes)),
+        "hex" => Ok(HEXLOWER.encode(&bytes)),
+        _ => bail!("unknown key format: {}", format),
+    }
+}
+
+/// Writes `key` to a new file at `path`, only readable by the user.
+pub fn write_file(path: &Path, key: &str) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("failed to create key file {}", path.display()))?;
+    writeln!(file, "{}", key)?;
+    Ok(())
+}
+
+/// Stores `key` in the OS keyring by `service` and `account`, with the
+/// `security` command on macOS and `secret-tool` (libsecret) elsewhere.
+pub fn store_in_keyring(service: &str, account: &str, key: &str) -> Result<()> {
+    let status = if cfg!(target_os = "macos") {
+        // security only takes the password as an argument when not prompting
+        Command::new("security")
+            .args(["add-generic-password", "-U", "-s", service, "-a"])
+            .args([account, "-w", key])
+            .status()
+            .context("failed to run security")?
+    } else {
+        let label = format!("--label={} {}", service, account);
+        let mut child = Command::new("secret-tool")
+            .args(["store", &label, "service", service])
+            .args(["account", account])
+            .stdin(Stdio::piped())
+            .spawn()
+            .context("failed to run secret-tool (hint: is libsecret installed?)")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(key.as_bytes())?;
+        }
+        child.wait()?
+    };
+
+    if !status.success() {
+        bail!("failed to store key in the keyring: exited with {}", status);
+    }
+    Ok(())
+}
+
+/// The secret stored by `service` and `account` in the OS keyring.
+pub fn lookup_in_keyring(service: &str, account: &str) -> Result<String> {
+    let mut cmd = if cfg!(target_os = "macos") {
+        let mut cmd = Command::new("security");
+        cmd.args(["find-generic-password", "-s", service, "-a", account, "-w"]);
+        cmd
+    } else {
+        let mut cmd = Command::new("secret-tool");
+        cmd.args(["lookup", "service", service, "account", account]);
+        cmd
+    };
+
+    let output = cmd
+        .stderr(Stdio::inherit())
+        .output()
+        .context("failed to look up secret in the keyring")?;
+    if !output.status.success() {
+        bail!(
+            "no secret in the keyring for {}/{} (hint: jn config secret --keyring {}/{})",
+            service,
+            account,
+            service,
+            account
+        );
+    }
+    let secret = String::from_utf8(output.stdout)?;
+    Ok(secret.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// The command that prints the key stored by `account` in the OS keyring.
+pub fn keyring_lookup(account: &str) -> String {
+    if cfg!(target_os = "macos") {
+        format!(
+            "security find-generic-password -s {} -a {} -w",
+            KEYRING_SERVICE, account
+        )
+    } else {
+        format!(
+            "secret-tool lookup service {} account {}",
+            KEYRING_SERVICE, account
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::generate;
+
+    #[test]
+    fn generating_keys() {
+        let key = generate("base64").unwrap();
+        assert_eq!(key.len(), 44);
+        assert_ne!(key, generate("base64").unwrap());
+
+        let key = generate("hex").unwrap();
+        assert_eq!(key.len(), 64);
+        assert!(key.chars().all(|c| c.is_ascii_hexdigit()));
+
+        assert!(generate("words").is_err());
+    }
+}
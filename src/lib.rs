@@ -1,11 +1,34 @@
+mod bundle;
+mod cancel;
 pub mod cli;
 mod config;
+mod conflicts;
 mod crypto;
+mod dedupe;
+mod diff;
 mod export;
 mod format;
+mod frontmatter;
 mod fs;
 pub mod handler;
+mod index;
+mod keygen;
+mod links;
+mod meta;
+mod perf;
+mod remote;
+mod review;
+mod search_cache;
+mod secrets;
+mod server;
+mod setup;
+mod share;
+mod split;
+mod state;
+mod strength;
 mod template;
+mod todo;
+mod tui;
 mod types;
 mod util;
 mod validate;
@@ -0,0 +1,80 @@
+use std::path::Path;
+
+/// The name `new` keeps the extension of `old` unless it has one of its own,
+/// so that `jn rename notes.md ideas` renames it to `ideas.md`.
+pub fn keep_extension(old: &str, new: &str) -> String {
+    match (Path::new(old).extension(), Path::new(new).extension()) {
+        (Some(ext), None) => format!("{}.{}", new, ext.to_string_lossy()),
+        _ => new.to_string(),
+    }
+}
+
+/// Rewrites the `[[wiki-links]]` to the journal `old` in `content` to link
+/// to `new` instead. Links may leave out the extension and end with a heading
+/// (`[[notes#todo]]`) or a label (`[[notes|my notes]]`), which are kept.
+/// Returns `None` if nothing links to `old`.
+pub fn rewrite(content: &str, old: &str, new: &str) -> Option<String> {
+    let stem = |name: &str| {
+        Path::new(name)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| name.to_string())
+    };
+    let (old_stem, new_stem) = (stem(old), stem(new));
+
+    let mut rewritten = String::with_capacity(content.len());
+    let mut changed = false;
+    let mut rest = content;
+    while let Some(start) = rest.find("[[") {
+        let (before, link) = rest.split_at(start + 2);
+        rewritten.push_str(before);
+
+        let end = match link.find("]]") {
+            Some(end) => end,
+            None => {
+                rest = link;
+                break;
+            }
+        };
+        let inner = &link[..end];
+        let target_end = inner.find(['#', '|']).unwrap_or(inner.len());
+        let (target, suffix) = inner.split_at(target_end);
+
+        if target == old {
+            rewritten.push_str(new);
+            changed = true;
+        } else if target == old_stem {
+            rewritten.push_str(&new_stem);
+            changed = true;
+        } else {
+            rewritten.push_str(target);
+        }
+        rewritten.push_str(suffix);
+        rest = &link[end..];
+    }
+    rewritten.push_str(rest);
+
+    changed.then_some(rewritten)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{keep_extension, rewrite};
+
+    #[test]
+    fn keeping_extensions() {
+        assert_eq!(keep_extension("notes.md", "ideas"), "ideas.md");
+        assert_eq!(keep_extension("notes.md", "ideas.org"), "ideas.org");
+        assert_eq!(keep_extension("notes", "ideas"), "ideas");
+    }
+
+    #[test]
+    fn rewriting_links() {
+        let content = "See [[notes]], [[notes.md#todo]] and [[notes|my notes]].\nNot [[notes-2]] or [[other]] [[";
+        assert_eq!(
+            rewrite(content, "notes.md", "ideas.md").unwrap(),
+            "See [[ideas]], [[ideas.md#todo]] and [[ideas|my notes]].\nNot [[notes-2]] or [[other]] [["
+        );
+        assert_eq!(rewrite("[[other]]", "notes.md", "ideas.md"), None);
+    }
+}
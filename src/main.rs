@@ -1,27 +1,62 @@
 use anyhow::Result;
+use clap::error::ErrorKind;
 use clap::Parser;
 use crossterm::style::Stylize;
-use journal::cli::{Cli, Command};
-use journal::handler::Handler;
+use journal::cli::{Cli, Command, ConfigArgs, ConfigCommand};
+use journal::handler::{ExitStatus, Handler};
 use rustyline::error::ReadlineError;
 use rustyline::DefaultEditor;
+use std::path::PathBuf;
 
 fn main() -> Result<()> {
-    let cli = Cli::parse();
+    // The handler loads the configuration when it handles the command, or
+    // expands an alias, so --help, --version and usage errors don't need it.
+    let args: Vec<String> = std::env::args().collect();
+    let (handler, cli) = match Cli::try_parse_from(&args) {
+        Ok(cli) => (Handler::new(cli.config.as_deref())?, cli),
+        Err(err) if err.kind() == ErrorKind::InvalidSubcommand => {
+            let mut args = args;
+            let config = take_config_arg(&mut args);
+            let handler = Handler::new(config.as_deref())?;
+            let cli = Cli::parse_from(handler.expand_aliases(args));
+            (handler, cli)
+        }
+        Err(err) => err.exit(),
+    };
     if let Command::Repl = cli.command {
-        return repl();
+        return repl(handler);
     }
 
-    let handler = Handler::new()?;
+    // Search errors exit with 2, as in grep, since 1 means no matches
+    let search = matches!(cli.command, Command::Search(_));
     if let Err(err) = handler.handle(cli) {
+        if let Some(status) = err.downcast_ref::<ExitStatus>() {
+            std::process::exit(status.0);
+        }
         eprintln!("{}: {}", "error".red(), err);
+        if search {
+            std::process::exit(2);
+        }
     }
 
     Ok(())
 }
 
-fn repl() -> Result<()> {
-    let handler = Handler::new()?;
+/// Removes --config and its path from `args`, without parsing them, so that
+/// an alias is expanded even if --config comes before it.
+fn take_config_arg(args: &mut Vec<String>) -> Option<PathBuf> {
+    let i = args
+        .iter()
+        .position(|arg| arg == "--config" || arg.starts_with("--config="))?;
+    let arg = args.remove(i);
+    match arg.strip_prefix("--config=") {
+        Some(path) => Some(PathBuf::from(path)),
+        None if i < args.len() => Some(PathBuf::from(args.remove(i))),
+        None => None,
+    }
+}
+
+fn repl(mut handler: Handler) -> Result<()> {
     let mut rl = DefaultEditor::new()?;
 
     // #[cfg(feature = "with-file-history")]
@@ -31,19 +66,36 @@ fn repl() -> Result<()> {
 
     println!("{}", JOURNAL);
 
+    let mut config_modified = handler.config_modified();
     loop {
         let readline = rl.readline(">> ");
 
         match readline {
             Ok(line) => {
                 rl.add_history_entry(line.as_str())?;
-                let mut line: Vec<&str> = line.split_whitespace().collect();
-                line.insert(0, "jn");
 
-                match Cli::try_parse_from(&line) {
+                // Changes to the configuration apply from the next command
+                if handler.config_modified() != config_modified {
+                    config_modified = handler.config_modified();
+                    reload(&mut handler);
+                }
+                let mut line: Vec<String> =
+                    line.split_whitespace().map(|s| s.to_string()).collect();
+                line.insert(0, "jn".to_string());
+
+                match Cli::try_parse_from(handler.expand_aliases(line)) {
+                    Ok(Cli {
+                        command:
+                            Command::Config(ConfigArgs {
+                                command: ConfigCommand::Reload,
+                            }),
+                        ..
+                    }) => reload(&mut handler),
                     Ok(cli) => {
-                        if let Err(err) = handler.handle(cli) {
-                            eprintln!("{}: {}", "error".red(), err);
+                        match handler.handle(cli) {
+                            Err(err) if err.is::<ExitStatus>() => {}
+                            Err(err) => eprintln!("{}: {}", "error".red(), err),
+                            Ok(_) => {}
                         }
                         println!();
                     }
@@ -66,6 +118,17 @@ fn repl() -> Result<()> {
     Ok(())
 }
 
+/// Loads the configuration of `handler` again, keeping the previous one if it's invalid.
+fn reload(handler: &mut Handler) {
+    match handler.reload() {
+        Ok(reloaded) => {
+            *handler = reloaded;
+            println!("{}", "Reloaded the configuration".dark_grey());
+        }
+        Err(err) => eprintln!("{}: {}", "error".red(), err),
+    }
+}
+
 const JOURNAL: &str = r"
       _                              _ 
      | | ___  _   _ _ __ _ __   __ _| |
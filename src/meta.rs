@@ -0,0 +1,233 @@
+use crate::frontmatter;
+use crate::fs::{digest, FileEntry};
+use crate::types::Journal;
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::fs;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Metadata of journals stored in an SQLite database in the root directory,
+/// so that journals don't have to be read and parsed again until they change.
+/// Journals are keyed by their path in the workspaces directory,
+/// i.e. {workspace}/{filename}.
+///
+/// Changes are made in a transaction that is committed by `save`,
+/// and rolled back if the store is dropped without saving.
+pub struct MetaStore {
+    conn: Connection,
+}
+
+#[derive(Clone, Default)]
+pub struct JournalMeta {
+    /// Modification time of the file in milliseconds since the epoch.
+    pub modified: u64,
+    /// SHA-256 of the file, as stored on disk.
+    pub digest: String,
+    /// Words and tags are only stored for plaintext journals,
+    /// to not leak anything about the content of encrypted ones.
+    pub words: Option<usize>,
+    pub tags: Vec<String>,
+}
+
+impl MetaStore {
+    /// Opens the store at `path`, creating it if it doesn't exist.
+    pub fn open(path: &FileEntry) -> Result<Self> {
+        if let Some(dir) = path.path().parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let conn = Connection::open(path.path())?;
+        // Other commands may be updating the store at the same time.
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS journals (
+                key TEXT PRIMARY KEY,
+                modified INTEGER NOT NULL,
+                digest TEXT NOT NULL,
+                words INTEGER,
+                tags TEXT NOT NULL
+            );
+            BEGIN;",
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// Commits the changes made since the store was opened.
+    pub fn save(self) -> Result<()> {
+        self.conn.execute_batch("COMMIT")?;
+        Ok(())
+    }
+
+    /// The metadata of the journal at `entry`, recorded again
+    /// if it's missing or the journal has changed since.
+    pub fn get(&mut self, key: &str, entry: &FileEntry) -> Result<JournalMeta> {
+        let modified = modified_millis(entry)?;
+        match self.lookup(key)? {
+            Some(meta) if meta.modified == modified => Ok(meta),
+            _ => {
+                let meta = read_meta(entry)?;
+                self.insert(key, &meta)?;
+                Ok(meta)
+            }
+        }
+    }
+
+    /// Records the current metadata of the journal at `entry`.
+    pub fn record(&mut self, key: &str, entry: &FileEntry) -> Result<()> {
+        self.insert(key, &read_meta(entry)?)
+    }
+
+    fn insert(&self, key: &str, meta: &JournalMeta) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO journals (key, modified, digest, words, tags)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                key,
+                meta.modified as i64,
+                meta.digest,
+                meta.words.map(|w| w as i64),
+                serde_json::to_string(&meta.tags)?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn lookup(&self, key: &str) -> Result<Option<JournalMeta>> {
+        let row = self
+            .conn
+            .query_row(
+                "SELECT modified, digest, words, tags FROM journals WHERE key = ?1",
+                params![key],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Option<i64>>(2)?,
+                        row.get::<_, String>(3)?,
+                    ))
+                },
+            )
+            .optional()?;
+
+        let Some((modified, digest, words, tags)) = row else {
+            return Ok(None);
+        };
+        Ok(Some(JournalMeta {
+            modified: modified as u64,
+            digest,
+            words: words.map(|w| w as usize),
+            tags: serde_json::from_str(&tags)?,
+        }))
+    }
+
+    pub fn remove(&mut self, key: &str) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM journals WHERE key = ?1", params![key])?;
+        Ok(())
+    }
+
+    /// Removes all journals in `workspace`.
+    pub fn remove_workspace(&mut self, workspace: &str) -> Result<()> {
+        // Prefixes are compared with substr, as LIKE would take _ in names as a wildcard.
+        let prefix = format!("{}/", workspace);
+        self.conn.execute(
+            "DELETE FROM journals WHERE substr(key, 1, length(?1)) = ?1",
+            params![prefix],
+        )?;
+        Ok(())
+    }
+
+    pub fn rename(&mut self, old: &str, new: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE OR REPLACE journals SET key = ?2 WHERE key = ?1",
+            params![old, new],
+        )?;
+        Ok(())
+    }
+
+    /// Moves all journals in workspace `old` to `new`.
+    pub fn rename_workspace(&mut self, old: &str, new: &str) -> Result<()> {
+        let old = format!("{}/", old);
+        let new = format!("{}/", new);
+        self.conn.execute(
+            "UPDATE OR REPLACE journals SET key = ?2 || substr(key, length(?1) + 1)
+            WHERE substr(key, 1, length(?1)) = ?1",
+            params![old, new],
+        )?;
+        Ok(())
+    }
+}
+
+/// Reads the metadata of the journal at `entry`.
+fn read_meta(entry: &FileEntry) -> Result<JournalMeta> {
+    let bytes = entry.read_bytes()?;
+    let journal = Journal::open(entry, None)?;
+
+    let content = if journal.encrypted() {
+        None
+    } else {
+        journal.bytes().ok().and_then(|b| String::from_utf8(b).ok())
+    };
+
+    Ok(JournalMeta {
+        modified: modified_millis(entry)?,
+        digest: digest(&bytes)?,
+        words: content
+            .as_ref()
+            .map(|c| frontmatter::body(c).split_whitespace().count()),
+        tags: content
+            .map(|c| frontmatter::tags(&frontmatter::parse(&c)))
+            .unwrap_or_default(),
+    })
+}
+
+fn modified_millis(entry: &FileEntry) -> Result<u64> {
+    let since = entry.modified()?.duration_since(UNIX_EPOCH)?;
+    Ok(since.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MetaStore;
+    use crate::fs::FileEntry;
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn renaming_workspaces() {
+        let dir = PathBuf::from("./temptestdir-meta");
+        let _ = fs::remove_dir_all(&dir);
+        for name in ["work/a.md", "work/b.md", "work_out/c.md"] {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, "---\ntags: [x]\n---\nsome words").unwrap();
+        }
+
+        let db = FileEntry::new(&dir.join(".meta.db"));
+        let mut store = MetaStore::open(&db).unwrap();
+        for name in ["work/a.md", "work/b.md", "work_out/c.md"] {
+            store
+                .record(name, &FileEntry::new(&dir.join(name)))
+                .unwrap();
+        }
+        store.save().unwrap();
+
+        let mut store = MetaStore::open(&db).unwrap();
+        store.rename_workspace("work", "job").unwrap();
+        assert!(store.lookup("work/a.md").unwrap().is_none());
+        let meta = store.lookup("job/a.md").unwrap().unwrap();
+        assert_eq!(meta.words, Some(2));
+        assert_eq!(meta.tags, ["x"]);
+
+        store.remove_workspace("job").unwrap();
+        assert!(store.lookup("job/b.md").unwrap().is_none());
+        assert!(store.lookup("work_out/c.md").unwrap().is_some());
+        // Not saved, so the changes are rolled back.
+        drop(store);
+
+        let store = MetaStore::open(&db).unwrap();
+        assert!(store.lookup("work/a.md").unwrap().is_some());
+        drop(store);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
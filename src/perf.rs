@@ -0,0 +1,72 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Timings of the current command, if enabled by --profile-perf.
+static TIMINGS: Mutex<Option<Vec<Timing>>> = Mutex::new(None);
+
+/// The total time spent in a part of a command, such as decryption.
+pub struct Timing {
+    pub label: &'static str,
+    pub total: Duration,
+    pub count: usize,
+}
+
+/// Starts recording timings, discarding any earlier ones.
+pub fn start() {
+    *TIMINGS.lock().unwrap() = Some(Vec::new());
+}
+
+/// Stops recording, returning the timings in the order they were first recorded.
+pub fn finish() -> Vec<Timing> {
+    TIMINGS.lock().unwrap().take().unwrap_or_default()
+}
+
+/// Runs `f`, adding the time it took to `label` if recording.
+/// Parts may overlap, e.g. directory walks include decrypting
+/// the index of workspaces with encrypted filenames.
+pub fn time<T>(label: &'static str, f: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let value = f();
+    record(label, started.elapsed());
+    value
+}
+
+pub fn record(label: &'static str, elapsed: Duration) {
+    let mut timings = TIMINGS.lock().unwrap();
+    let timings = match timings.as_mut() {
+        Some(timings) => timings,
+        None => return,
+    };
+
+    match timings.iter_mut().find(|t| t.label == label) {
+        Some(timing) => {
+            timing.total += elapsed;
+            timing.count += 1;
+        }
+        None => timings.push(Timing {
+            label,
+            total: elapsed,
+            count: 1,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{finish, record, start, time};
+    use std::time::Duration;
+
+    #[test]
+    fn recording_timings() {
+        record("test", Duration::from_millis(5));
+        start();
+        record("test", Duration::from_millis(5));
+        assert_eq!(time("test", || 1), 1);
+        record("test", Duration::from_millis(10));
+
+        let timing = finish().into_iter().find(|t| t.label == "test").unwrap();
+        assert_eq!(timing.count, 3);
+        assert!(timing.total >= Duration::from_millis(15));
+        assert!(timing.total < Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,109 @@
+use crate::export::sftp::{quote, Target};
+use anyhow::{bail, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Exit status of the download script when the journal doesn't exist.
+const MISSING: i32 = 44;
+
+/// A workspace whose journals are stored on a server, reached with ssh.
+pub struct Remote {
+    target: Target,
+    /// Identity file given to ssh, else its default keys are used.
+    identity: Option<String>,
+}
+
+impl Remote {
+    /// Parses the server as `sftp://[user@]host[:port]/path`, like SFTP export targets.
+    pub fn new(url: &str, identity: Option<String>) -> Result<Self> {
+        Ok(Self {
+            target: Target::parse(url)?,
+            identity,
+        })
+    }
+
+    /// Where the journal `name` is on the server, e.g. `host:/srv/journals/notes.md`.
+    pub fn location(&self, name: &str) -> String {
+        format!("{}:{}", self.target.host, self.path(name))
+    }
+
+    /// Downloads the journal `name` as it's stored, i.e. still encrypted if it is.
+    /// Returns `None` if there's no such journal.
+    pub fn download(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        let path = quote(&self.path(name));
+        let script = format!(
+            "if [ -f {} ]; then cat {}; else exit {}; fi",
+            path, path, MISSING
+        );
+        let output = match self.ssh(&script).stderr(Stdio::inherit()).output() {
+            Ok(output) => output,
+            Err(err) => bail!("failed to run ssh: {}", err),
+        };
+
+        match output.status.code() {
+            Some(0) => Ok(Some(output.stdout)),
+            Some(MISSING) => Ok(None),
+            _ => bail!(
+                "failed to download {}: ssh exited with {}",
+                self.location(name),
+                output.status
+            ),
+        }
+    }
+
+    /// Uploads `content` as the journal `name`, replacing it if it exists.
+    pub fn upload(&self, name: &str, content: &[u8]) -> Result<()> {
+        let path = self.path(name);
+        let dir = match path.rsplit_once('/') {
+            Some(("", _)) => "/",
+            Some((dir, _)) => dir,
+            None => ".",
+        };
+        // Written next to the journal first, so it's never left half written
+        let temp = format!("{}.jn-upload", path);
+        let script = format!(
+            "mkdir -p {} && cat > {} && mv {} {}",
+            quote(dir),
+            quote(&temp),
+            quote(&temp),
+            quote(&path)
+        );
+
+        let mut child = match self.ssh(&script).stdin(Stdio::piped()).spawn() {
+            Ok(child) => child,
+            Err(err) => bail!("failed to run ssh: {}", err),
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(content)?;
+        }
+
+        let status = child.wait()?;
+        if !status.success() {
+            bail!(
+                "failed to upload {}: ssh exited with {}",
+                self.location(name),
+                status
+            );
+        }
+        Ok(())
+    }
+
+    fn path(&self, name: &str) -> String {
+        format!("{}/{}", self.target.path.trim_end_matches('/'), name)
+    }
+
+    /// Runs `script` on the server. Requires key based authentication,
+    /// since ssh may not prompt.
+    fn ssh(&self, script: &str) -> Command {
+        let mut cmd = Command::new("ssh");
+        cmd.args(["-o", "BatchMode=yes"]);
+        if let Some(port) = self.target.port {
+            cmd.args(["-p", &port.to_string()]);
+        }
+        if let Some(identity) = &self.identity {
+            cmd.arg("-i").arg(identity);
+        }
+        cmd.arg(self.target.destination()).arg(script);
+        cmd
+    }
+}
@@ -0,0 +1,104 @@
+use crate::todo;
+use chrono::NaiveDate;
+
+/// Template of generated reviews unless `review-template` is configured.
+pub const DEFAULT_TEMPLATE: &str =
+    "# Review {{FROM}} to {{TO}}\n\n{{ENTRIES}}\n\n## Reflections\n\n";
+
+/// A journal included in a review.
+pub struct Entry {
+    /// Workspace and name of the journal, e.g. `work/standup.md`.
+    pub title: String,
+    pub date: NaiveDate,
+    pub content: String,
+    pub org: bool,
+}
+
+/// Fills in `template` with the dates of the review and its entries, which
+/// are the whole journals or, if `summary` is set, only their headings and
+/// to-dos. The entries are given a heading each, in order.
+pub fn generate(
+    template: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+    entries: &[Entry],
+    summary: bool,
+) -> String {
+    let mut text = String::new();
+    for entry in entries {
+        let content = if summary {
+            outline(&entry.content, entry.org)
+        } else {
+            entry.content.trim().to_string()
+        };
+        if content.is_empty() {
+            continue;
+        }
+        text.push_str(&format!(
+            "## {} ({})\n\n{}\n\n",
+            entry.title, entry.date, content
+        ));
+    }
+
+    template
+        .replace("{{FROM}}", &from.to_string())
+        .replace("{{TO}}", &to.to_string())
+        // Last, so that placeholders in journals are kept as they are
+        .replace("{{ENTRIES}}", text.trim_end())
+}
+
+/// The headings and open to-dos of `content`, as a list.
+fn outline(content: &str, org: bool) -> String {
+    let todos = todo::extract(content, org);
+    let heading_marker = if org { '*' } else { '#' };
+
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(num, line)| {
+            if let Some(todo) = todos.iter().find(|todo| todo.line == num + 1) {
+                return Some(format!("- {}", todo.text));
+            }
+            let title = line.trim_start_matches(heading_marker);
+            if title.len() < line.len() && title.starts_with(' ') {
+                return Some(format!("- {}", title.trim()));
+            }
+            None
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate, Entry, DEFAULT_TEMPLATE};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn generating_reviews() {
+        let date = |d| NaiveDate::from_ymd_opt(2024, 6, d).unwrap();
+        let entries = [
+            Entry {
+                title: "work/a.md".to_string(),
+                date: date(3),
+                content: "# Standup\nnotes\n- [ ] Call {{TO}}\n".to_string(),
+                org: false,
+            },
+            Entry {
+                title: "work/empty.md".to_string(),
+                date: date(4),
+                content: "no headings\n".to_string(),
+                org: false,
+            },
+        ];
+
+        let review = generate(DEFAULT_TEMPLATE, date(1), date(7), &entries, false);
+        assert!(review.starts_with("# Review 2024-06-01 to 2024-06-07\n\n## work/a.md (2024-06-03)\n\n# Standup\nnotes\n- [ ] Call {{TO}}\n\n## work/empty.md"));
+
+        let review = generate("{{ENTRIES}}", date(1), date(7), &entries, true);
+        assert_eq!(
+            review,
+            "## work/a.md (2024-06-03)\n\n- Standup\n- Call {{TO}}"
+        );
+    }
+}
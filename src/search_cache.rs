@@ -0,0 +1,94 @@
+use crate::fs::FileEntry;
+use crate::types::Journal;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The decrypted content of encrypted journals, kept between searches
+/// in a file encrypted with the same key, so that plaintext is never
+/// stored on disk. Content is used only while the digest of the
+/// encrypted journal is the one it was cached with.
+#[derive(Default, Deserialize, Serialize)]
+pub struct SearchCache {
+    /// Keyed by {workspace}/{filename}, with the filename as on disk.
+    journals: HashMap<String, CachedJournal>,
+    #[serde(skip)]
+    changed: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+struct CachedJournal {
+    /// SHA-256 of the encrypted file.
+    digest: String,
+    content: String,
+}
+
+impl SearchCache {
+    /// Loads the cache at `path`. It's empty if there's no cache yet, or if
+    /// it was written with another key, in which case it's replaced on save.
+    pub fn load(path: &FileEntry, key: &str) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let journal = Journal::open(path, Some(key.to_string()))?;
+        let cache = match journal.try_bytes()? {
+            Some(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
+            None => Self::default(),
+        };
+        Ok(cache)
+    }
+
+    /// Saves the cache encrypted with `key`, if it changed.
+    pub fn save(&self, path: &FileEntry, key: &str) -> Result<()> {
+        if !self.changed {
+            return Ok(());
+        }
+
+        let bytes = serde_json::to_vec(self)?;
+        Journal::save(path, Some(&key.to_string()), &bytes)
+    }
+
+    pub fn get(&self, name: &str, digest: &str) -> Option<&str> {
+        self.journals
+            .get(name)
+            .filter(|journal| journal.digest == digest)
+            .map(|journal| journal.content.as_str())
+    }
+
+    pub fn insert(&mut self, name: &str, digest: &str, content: &str) {
+        let journal = CachedJournal {
+            digest: digest.to_string(),
+            content: content.to_string(),
+        };
+        self.journals.insert(name.to_string(), journal);
+        self.changed = true;
+    }
+
+    /// Forgets the journals for which `keep` returns false, e.g. removed journals.
+    pub fn retain(&mut self, keep: impl Fn(&str) -> bool) {
+        let count = self.journals.len();
+        self.journals.retain(|name, _| keep(name));
+        self.changed = self.changed || self.journals.len() < count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SearchCache;
+
+    #[test]
+    fn caching_content() {
+        let mut cache = SearchCache::default();
+        cache.insert("work/a", "1", "content");
+        cache.insert("work/b", "2", "other");
+
+        assert_eq!(cache.get("work/a", "1"), Some("content"));
+        assert_eq!(cache.get("work/a", "changed"), None);
+        assert_eq!(cache.get("work/c", "1"), None);
+
+        cache.retain(|name| name != "work/b");
+        assert_eq!(cache.get("work/b", "2"), None);
+        assert!(cache.changed);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::crypto;
+use crate::keygen;
+use anyhow::{bail, Result};
+use data_encoding::BASE64;
+use ring::aead::NONCE_LEN;
+
+/// Prefix of config values stored in the OS keyring, as `keyring:{service}/{account}`.
+pub const KEYRING_PREFIX: &str = "keyring:";
+
+/// Prefix of config values encrypted with the journal key, as written by `jn config secret`.
+pub const ENCRYPTED_PREFIX: &str = "encrypted:";
+
+/// Length of the authentication tag of AES-256-GCM.
+const TAG_LEN: usize = 16;
+
+/// The value of a credential in the config, which is looked up in the
+/// keyring if it's `keyring:{service}/{account}`, or decrypted with `key`
+/// if it's `encrypted:{base64}`. Other values are used as they are.
+pub fn resolve(value: &str, key: Option<&str>) -> Result<String> {
+    if let Some(reference) = value.strip_prefix(KEYRING_PREFIX) {
+        let (service, account) = keyring_reference(reference)?;
+        return keygen::lookup_in_keyring(service, account);
+    }
+
+    let encoded = match value.strip_prefix(ENCRYPTED_PREFIX) {
+        Some(encoded) => encoded,
+        None => return Ok(value.to_string()),
+    };
+    let key = match key {
+        Some(key) => key,
+        None => bail!("key required to decrypt a secret in the config (hint: set JOURNAL_KEY)"),
+    };
+    let bytes = match BASE64.decode(encoded.trim().as_bytes()) {
+        Ok(bytes) if bytes.len() >= NONCE_LEN + TAG_LEN => bytes,
+        _ => bail!("invalid encrypted secret in the config"),
+    };
+
+    let (nonce, rest) = bytes.split_at(NONCE_LEN);
+    let (tag, ciphertext) = rest.split_at(TAG_LEN);
+    let plaintext = match crypto::decrypt(key, nonce, tag, ciphertext.to_vec()) {
+        Ok(plaintext) => plaintext,
+        Err(err) => bail!("failed to decrypt a secret in the config: {}", err),
+    };
+    Ok(String::from_utf8(plaintext)?)
+}
+
+/// Encrypts `secret` with `key` as a config value, `encrypted:{base64}`
+/// of the nonce, tag and ciphertext.
+pub fn encrypt(secret: &str, key: &str) -> Result<String> {
+    let encrypted = crypto::encrypt(secret.as_bytes(), key)?;
+    let mut bytes = encrypted.nonce;
+    bytes.extend(encrypted.tag);
+    bytes.extend(encrypted.ciphertext);
+    Ok(format!("{}{}", ENCRYPTED_PREFIX, BASE64.encode(&bytes)))
+}
+
+/// Splits `{service}/{account}`, e.g. `journal/webdav`.
+pub fn keyring_reference(reference: &str) -> Result<(&str, &str)> {
+    match reference.split_once('/') {
+        Some((service, account)) if !service.is_empty() && !account.is_empty() => {
+            Ok((service, account))
+        }
+        _ => bail!(
+            "invalid keyring reference: {} (hint: use service/account, e.g. journal/webdav)",
+            reference
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encrypt, keyring_reference, resolve};
+
+    #[test]
+    fn resolving_secrets() {
+        let key = "testing-secrets";
+        let encrypted = encrypt("sv=2021&sig=abc", key).unwrap();
+        assert!(encrypted.starts_with("encrypted:"));
+        assert_eq!(resolve(&encrypted, Some(key)).unwrap(), "sv=2021&sig=abc");
+        assert!(resolve(&encrypted, Some("wrong")).is_err());
+        assert!(resolve(&encrypted, None).is_err());
+        assert!(resolve("encrypted:nonsense", Some(key)).is_err());
+
+        assert_eq!(resolve("plain", None).unwrap(), "plain");
+        assert_eq!(
+            keyring_reference("journal/webdav").unwrap(),
+            ("journal", "webdav")
+        );
+        assert!(keyring_reference("webdav").is_err());
+    }
+}
@@ -0,0 +1,234 @@
+use anyhow::{bail, Result};
+use ring::constant_time::verify_slices_are_equal;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+/// Requests with larger bodies are rejected.
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// Clients that stall reading or writing for longer are disconnected,
+/// since requests are served one at a time.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    #[cfg(test)]
+    pub fn new(method: &str, path: &str, headers: &[(&str, &str)], body: &[u8]) -> Self {
+        Self {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: body.to_vec(),
+        }
+    }
+
+    /// Returns the value of the header `name`, which must be lowercase.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(|v| v.as_str())
+    }
+
+    /// The percent-decoded segments of the path.
+    pub fn segments(&self) -> Vec<String> {
+        self.path
+            .split('?')
+            .next()
+            .unwrap_or_default()
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(percent_decode)
+            .collect()
+    }
+
+    fn read(stream: &mut TcpStream) -> Result<Self> {
+        let mut reader = BufReader::new(stream);
+
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let mut parts = line.split_whitespace();
+        let (method, path) = match (parts.next(), parts.next()) {
+            (Some(method), Some(path)) => (method.to_string(), path.to_string()),
+            _ => bail!("malformed request line"),
+        };
+
+        let mut headers = HashMap::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = line.split_once(':') {
+                headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+            }
+        }
+
+        let length: usize = match headers.get("content-length") {
+            Some(len) => len.parse()?,
+            None => 0,
+        };
+        if length > MAX_BODY_SIZE {
+            bail!("request body too large");
+        }
+
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+
+        Ok(Self {
+            method,
+            path,
+            headers,
+            body,
+        })
+    }
+}
+
+pub struct Response {
+    status: u16,
+    body: Vec<u8>,
+}
+
+impl Response {
+    pub fn json<T: Serialize>(status: u16, value: &T) -> Self {
+        match serde_json::to_vec(value) {
+            Ok(body) => Self { status, body },
+            Err(err) => Self::error(500, &err.to_string()),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    #[cfg(test)]
+    pub fn body(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+
+    pub fn error(status: u16, message: &str) -> Self {
+        let body = serde_json::json!({ "error": message });
+        Self {
+            status,
+            body: body.to_string().into_bytes(),
+        }
+    }
+
+    fn write(&self, stream: &mut TcpStream) -> Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            201 => "Created",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            _ => "Internal Server Error",
+        };
+
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            reason,
+            self.body.len()
+        )?;
+        stream.write_all(&self.body)?;
+        stream.flush()?;
+        Ok(())
+    }
+}
+
+/// Serves requests on `addr` one at a time, passing the authorized
+/// ones to `route`. Clients authorize with `Authorization: Bearer <token>`.
+pub fn serve<F>(addr: &str, token: &str, route: F) -> Result<()>
+where
+    F: Fn(&Request) -> Response,
+{
+    let listener = TcpListener::bind(addr)?;
+
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+        let timeouts = stream
+            .set_read_timeout(Some(TIMEOUT))
+            .and_then(|_| stream.set_write_timeout(Some(TIMEOUT)));
+        if timeouts.is_err() {
+            continue;
+        }
+
+        let response = match Request::read(&mut stream) {
+            Ok(req) if authorized(&req, token) => route(&req),
+            Ok(_) => Response::error(401, "missing or invalid token"),
+            Err(err) => Response::error(400, &err.to_string()),
+        };
+
+        // The client may already have hung up.
+        let _ = response.write(&mut stream);
+    }
+
+    Ok(())
+}
+
+fn authorized(req: &Request, token: &str) -> bool {
+    match req.header("authorization") {
+        Some(value) => match value.strip_prefix("Bearer ") {
+            Some(given) => verify_slices_are_equal(given.as_bytes(), token.as_bytes()).is_ok(),
+            None => false,
+        },
+        None => false,
+    }
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                decoded.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::percent_decode;
+
+    #[test]
+    fn decoding_path_segments() {
+        assert_eq!(percent_decode("notes.md"), "notes.md");
+        assert_eq!(percent_decode("my%20notes.md"), "my notes.md");
+        assert_eq!(percent_decode("b%C3%BCro"), "büro");
+        assert_eq!(percent_decode("100%"), "100%");
+    }
+}
@@ -0,0 +1,201 @@
+use crate::config::Config;
+use crate::fs::{read_file, FileEntry};
+use anyhow::{bail, Result};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// Name of the configuration in a setup archive.
+const CONFIG_NAME: &str = "config.toml";
+
+/// Directory of the files the configuration refers to in a setup archive,
+/// such as the `prompts-file`.
+const FILES_DIR: &str = "files/";
+
+/// Keys of the credentials in the configuration, left out unless asked for.
+const SECRET_KEYS: [&str; 2] = ["api-token", "sas-token"];
+
+/// Packs the configuration at `config_path`, which includes the templates,
+/// aliases and appearance, into a zip archive at `filepath` along with the
+/// `prompts-file`. Credentials are left out unless `include_secrets` is set.
+/// Returns the packed files and the number of credentials left out.
+pub fn export(
+    config_path: &FileEntry,
+    filepath: &FileEntry,
+    include_secrets: bool,
+) -> Result<(Vec<String>, usize)> {
+    if !config_path.exists() {
+        bail!("no config file at {}", config_path.path().display());
+    }
+
+    let (content, left_out) = match read_file(config_path.path())? {
+        content if include_secrets => (content, 0),
+        content => strip_secrets(&content),
+    };
+    let config: Config = toml::from_str(&content)?;
+    let azure_token = config
+        .export
+        .as_ref()
+        .and_then(|export| export.azure.as_ref())
+        .and_then(|azure| azure.sas_token.as_ref());
+    if !include_secrets && (config.api_token.is_some() || azure_token.is_some()) {
+        bail!("failed to leave out the secrets of the config (hint: put them on lines of their own, or use --include-secrets)");
+    }
+
+    let file = fs::File::create(filepath.path())?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    zip.start_file(CONFIG_NAME, options)?;
+    zip.write_all(content.as_bytes())?;
+    let mut packed = vec![CONFIG_NAME.to_string()];
+
+    if let Some(path) = &config.prompts_file {
+        let name = match Path::new(path).file_name() {
+            Some(name) => format!("{}{}", FILES_DIR, name.to_string_lossy()),
+            None => bail!("invalid prompts-file: {}", path),
+        };
+        zip.start_file(&name, options)?;
+        zip.write_all(&fs::read(path)?)?;
+        packed.push(name);
+    }
+
+    zip.finish()?;
+    Ok((packed, left_out))
+}
+
+/// Unpacks a setup archive made by `export`, writing the configuration to
+/// `config_path` and the files it refers to next to it, with `prompts-file`
+/// set to where it was written. An existing configuration is only replaced
+/// if `force` is set. Returns the paths of the written files.
+pub fn import(filepath: &FileEntry, config_path: &FileEntry, force: bool) -> Result<Vec<String>> {
+    if !filepath.exists() {
+        bail!("no setup archive at {}", filepath.path().display());
+    }
+    if config_path.exists() && !force {
+        bail!(
+            "config already exists at {} (hint: use --force to replace it)",
+            config_path.path().display()
+        );
+    }
+
+    let mut archive = zip::ZipArchive::new(fs::File::open(filepath.path())?)?;
+    let mut content = String::new();
+    match archive.by_name(CONFIG_NAME) {
+        Ok(mut file) => file.read_to_string(&mut content)?,
+        Err(_) => bail!("not a setup archive, there's no {} in it", CONFIG_NAME),
+    };
+    let config: Config = toml::from_str(&content)?;
+    let prompts_name = config
+        .prompts_file
+        .as_deref()
+        .and_then(|path| Path::new(path).file_name())
+        .map(|name| name.to_string_lossy().to_string());
+
+    let dir = match config_path.path().parent() {
+        Some(dir) => dir.to_path_buf(),
+        None => bail!("invalid config path: {}", config_path.path().display()),
+    };
+    fs::create_dir_all(&dir)?;
+
+    let mut written = Vec::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let name = match file.name().strip_prefix(FILES_DIR) {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        // Only written next to the configuration
+        if name.is_empty() || name.contains(['/', '\\']) || name == ".." {
+            bail!("setup archive contains an invalid path: {}", file.name());
+        }
+
+        let target = dir.join(&name);
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        fs::write(&target, bytes)?;
+        if prompts_name.as_deref() == Some(name.as_str()) {
+            content = set_value(&content, "prompts-file", &target.display().to_string());
+        }
+        written.push(target.display().to_string());
+    }
+
+    fs::write(config_path.path(), content)?;
+    written.insert(0, config_path.path().display().to_string());
+    Ok(written)
+}
+
+/// The configuration without the lines setting credentials, and how many there were.
+fn strip_secrets(content: &str) -> (String, usize) {
+    let mut left_out = 0;
+    let lines: Vec<&str> = content
+        .lines()
+        .filter(|line| match line_key(line) {
+            Some(key) if SECRET_KEYS.contains(&key) => {
+                left_out += 1;
+                false
+            }
+            _ => true,
+        })
+        .collect();
+    (lines.join("\n") + "\n", left_out)
+}
+
+/// The configuration with the top-level `key` set to the string `value`,
+/// keeping the rest of it as it was, comments included.
+fn set_value(content: &str, key: &str, value: &str) -> String {
+    let line = format!("{} = {}", key, toml::Value::String(value.to_string()));
+    let mut lines: Vec<String> = Vec::new();
+    let mut replaced = false;
+    for existing in content.lines() {
+        // Only keys before the first table are top-level
+        let top_level = !lines.iter().any(|l| l.trim_start().starts_with('['));
+        if top_level && !replaced && line_key(existing) == Some(key) {
+            lines.push(line.clone());
+            replaced = true;
+        } else {
+            lines.push(existing.to_string());
+        }
+    }
+    if !replaced {
+        lines.insert(0, line);
+    }
+    lines.join("\n") + "\n"
+}
+
+/// The key that `line` sets, e.g. `sas-token` for `sas-token = "…"`.
+fn line_key(line: &str) -> Option<&str> {
+    let (key, _) = line.split_once('=')?;
+    let key = key.trim().trim_matches('"');
+    Some(key).filter(|key| !key.is_empty() && !key.starts_with('#'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{set_value, strip_secrets};
+
+    #[test]
+    fn leaving_out_secrets() {
+        let config = "fuzzy-match = true\n\n[export.azure]\naccount = \"me\"\n  sas-token = \"sv=2021\"\n# sas-token = \"old\"\n";
+        let (stripped, left_out) = strip_secrets(config);
+        assert_eq!(left_out, 1);
+        assert_eq!(
+            stripped,
+            "fuzzy-match = true\n\n[export.azure]\naccount = \"me\"\n# sas-token = \"old\"\n"
+        );
+    }
+
+    #[test]
+    fn setting_values() {
+        let config = "prompts-file = \"/old/prompts.txt\" # prompts\n[remind]\nprompts-file = 1\n";
+        assert_eq!(
+            set_value(config, "prompts-file", "/new/prompts.txt"),
+            "prompts-file = \"/new/prompts.txt\"\n[remind]\nprompts-file = 1\n"
+        );
+        assert_eq!(
+            set_value("[remind]\n", "prompts-file", "/new/p.txt"),
+            "prompts-file = \"/new/p.txt\"\n[remind]\n"
+        );
+    }
+}
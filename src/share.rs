@@ -0,0 +1,108 @@
+use crate::crypto;
+use crate::fs::FileEntry;
+use crate::types::Journal;
+use anyhow::{bail, Context, Result};
+use data_encoding::BASE32_NOPAD;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Extension of the files written by `jn share`.
+pub const EXTENSION: &str = "jnshare";
+
+/// What a shared journal is encrypted to.
+pub enum Secret<'a> {
+    /// A passphrase, encrypting the journal as `jn` does.
+    Passphrase(&'a str),
+    /// The public key of an age recipient, using the age command.
+    Recipient(&'a str),
+}
+
+/// A random passphrase of 24 characters, in groups of four
+/// to be easier to read out, e.g. `ABCD-EFGH-...`.
+pub fn passphrase() -> Result<String> {
+    let encoded = BASE32_NOPAD.encode(&crypto::random_bytes(15)?);
+    let groups: Vec<String> = encoded
+        .as_bytes()
+        .chunks(4)
+        .map(|chunk| String::from_utf8_lossy(chunk).to_string())
+        .collect();
+    Ok(groups.join("-"))
+}
+
+/// Writes `content` encrypted to `secret` to `filepath`.
+pub fn create(filepath: &FileEntry, content: &[u8], secret: &Secret) -> Result<()> {
+    match secret {
+        Secret::Passphrase(passphrase) => {
+            Journal::save(filepath, Some(&passphrase.to_string()), content)
+        }
+        Secret::Recipient(recipient) => {
+            let mut child = Command::new("age")
+                .args(["--encrypt", "--armor", "--recipient", recipient, "--output"])
+                .arg(filepath.path())
+                .stdin(Stdio::piped())
+                .spawn()
+                .context("failed to run age (hint: is it installed?)")?;
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(content)?;
+            }
+
+            let status = child.wait()?;
+            if !status.success() {
+                bail!("failed to encrypt with age: exited with {}", status);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Whether the shared journal at `filepath` was encrypted with age.
+pub fn is_age(filepath: &FileEntry) -> Result<bool> {
+    let prefix = filepath.read_prefix(64)?;
+    Ok(prefix.starts_with(b"-----BEGIN AGE ENCRYPTED FILE-----")
+        || prefix.starts_with(b"age-encryption.org/"))
+}
+
+/// Decrypts the shared journal at `filepath` with a passphrase,
+/// or with the age identity file `identity` if it was encrypted with age.
+pub fn open(
+    filepath: &FileEntry,
+    passphrase: Option<&str>,
+    identity: Option<&str>,
+) -> Result<Vec<u8>> {
+    if !is_age(filepath)? {
+        let journal = Journal::open(filepath, passphrase.map(str::to_string))?;
+        journal.require_encrypted()?;
+        return journal.bytes();
+    }
+
+    let identity = match identity {
+        Some(identity) => identity,
+        None => bail!("shared journal is encrypted with age (hint: use --identity)"),
+    };
+    let output = Command::new("age")
+        .args(["--decrypt", "--identity", identity])
+        .arg(filepath.path())
+        .stderr(Stdio::inherit())
+        .output()
+        .context("failed to run age (hint: is it installed?)")?;
+    if !output.status.success() {
+        bail!("failed to decrypt with age: exited with {}", output.status);
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::passphrase;
+
+    #[test]
+    fn generating_passphrases() {
+        let first = passphrase().unwrap();
+        assert_eq!(first.len(), 29);
+        assert!(first.split('-').all(|group| group.len() == 4
+            && group
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())));
+        assert_ne!(first, passphrase().unwrap());
+    }
+}
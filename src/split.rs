@@ -0,0 +1,78 @@
+/// A part of a journal starting at a heading.
+pub struct Section {
+    pub title: String,
+    pub content: String,
+}
+
+/// Splits markdown `content` at the headings of `level`, e.g. `## ` for 2.
+/// A section lasts until the next heading of the same or a higher level,
+/// so deeper headings stay in their section. Returns the content before
+/// the first heading along with the sections. Headings in code blocks
+/// aren't split at.
+pub fn sections(content: &str, level: usize) -> (String, Vec<Section>) {
+    let mut preamble = String::new();
+    let mut sections: Vec<Section> = Vec::new();
+    let mut in_code = false;
+    let mut current: Option<Section> = None;
+
+    for line in content.split_inclusive('\n') {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+
+        match heading(line).filter(|_| !in_code) {
+            Some((l, title)) if l == level => {
+                sections.extend(current.take());
+                current = Some(Section {
+                    title: title.to_string(),
+                    content: String::new(),
+                });
+            }
+            Some((l, _)) if l < level => {
+                // A higher level heading ends the section, and
+                // is kept with the content before the sections.
+                sections.extend(current.take());
+            }
+            _ => {}
+        }
+
+        match current.as_mut() {
+            Some(section) => section.content.push_str(line),
+            None => preamble.push_str(line),
+        }
+    }
+    sections.extend(current);
+
+    (preamble, sections)
+}
+
+/// The level and title of a markdown heading, e.g. `(2, "Title")` for `## Title`.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let rest = &line[level..];
+    if level == 0 || level > 6 || !(rest.starts_with(' ') || rest.trim().is_empty()) {
+        return None;
+    }
+    Some((level, rest.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sections;
+
+    #[test]
+    fn splitting_at_headings() {
+        let content = "# Notes\nintro\n## First\na\n### Sub\nb\n## Second\n```\n## not a heading\n```\n# Other\nc\n## Third\nd";
+        let (preamble, sections) = sections(content, 2);
+
+        let titles: Vec<&str> = sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, ["First", "Second", "Third"]);
+        assert_eq!(sections[0].content, "## First\na\n### Sub\nb\n");
+        assert_eq!(
+            sections[1].content,
+            "## Second\n```\n## not a heading\n```\n"
+        );
+        assert_eq!(sections[2].content, "## Third\nd");
+        assert_eq!(preamble, "# Notes\nintro\n# Other\nc\n");
+    }
+}
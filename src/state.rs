@@ -0,0 +1,131 @@
+use crate::fs::{read_file, FileEntry};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+
+/// State kept between invocations, stored as JSON in the root directory.
+#[derive(Default, Deserialize, Serialize)]
+pub struct State {
+    /// Time of the last successful export, in RFC 3339.
+    #[serde(rename = "last-export")]
+    last_export: Option<String>,
+    /// The journals of each workspace in the order they were last listed.
+    #[serde(rename = "last-listing", default)]
+    last_listing: HashMap<String, Vec<String>>,
+    /// When journals were last opened, in RFC 3339,
+    /// keyed by {workspace}/{filename} with the filename as on disk.
+    #[serde(rename = "last-opened", default)]
+    last_opened: HashMap<String, String>,
+    /// The last removal, rename or overwrite, to be reverted by `jn undo`.
+    #[serde(rename = "last-change")]
+    last_change: Option<Change>,
+}
+
+/// A destructive operation that can be undone.
+/// Removed and overwritten files are kept as copies in a backup directory.
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Change {
+    /// A journal named `name`, stored as `filename`, was removed.
+    /// The names differ in workspaces with encrypted filenames.
+    Removed {
+        workspace: String,
+        name: String,
+        filename: String,
+        backup: String,
+    },
+    /// A workspace was removed, and moved to the backup directory.
+    RemovedWorkspace { workspace: String, backup: String },
+    /// A journal was renamed, or a workspace if `workspace` is `None`.
+    Renamed {
+        workspace: Option<String>,
+        from: String,
+        to: String,
+    },
+    /// Journals, as workspace and filename, were overwritten by a replacement.
+    Overwritten {
+        journals: Vec<(String, String)>,
+        backup: String,
+    },
+}
+
+impl State {
+    pub fn load(path: &FileEntry) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = read_file(path.path())?;
+        let state: State = serde_json::from_str(&content)?;
+        Ok(state)
+    }
+
+    pub fn save(&self, path: &FileEntry) -> Result<()> {
+        if let Some(dir) = path.path().parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path.path(), content)?;
+        Ok(())
+    }
+
+    pub fn last_export(&self) -> Option<DateTime<Local>> {
+        self.last_export
+            .as_ref()
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|t| t.with_timezone(&Local))
+    }
+
+    /// Returns the name of the journal at `index`, starting at 1,
+    /// in the last listing of `workspace`.
+    pub fn listed_journal(&self, workspace: &str, index: usize) -> Result<&str> {
+        let names = match self.last_listing.get(workspace) {
+            Some(names) => names,
+            None => bail!("workspace {} hasn't been listed (hint: jn list)", workspace),
+        };
+
+        match index.checked_sub(1).and_then(|i| names.get(i)) {
+            Some(name) => Ok(name.as_str()),
+            None => bail!("no journal at index {} in the last listing", index),
+        }
+    }
+
+    pub fn set_listing(&mut self, workspace: &str, names: Vec<String>) {
+        self.last_listing.insert(workspace.to_string(), names);
+    }
+
+    pub fn set_last_export(&mut self, time: DateTime<Local>) {
+        self.last_export = Some(time.to_rfc3339());
+    }
+
+    pub fn last_opened(&self, journal: &str) -> Option<DateTime<Local>> {
+        self.last_opened
+            .get(journal)
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|t| t.with_timezone(&Local))
+    }
+
+    pub fn set_opened(&mut self, journal: &str, time: DateTime<Local>) {
+        self.last_opened
+            .insert(journal.to_string(), time.to_rfc3339());
+    }
+
+    /// Moves the last opened time of a renamed journal,
+    /// or forgets it if `new` is `None`.
+    pub fn move_opened(&mut self, old: &str, new: Option<&str>) {
+        if let (Some(time), Some(new)) = (self.last_opened.remove(old), new) {
+            self.last_opened.insert(new.to_string(), time);
+        }
+    }
+
+    pub fn set_last_change(&mut self, change: Change) {
+        self.last_change = Some(change);
+    }
+
+    pub fn take_last_change(&mut self) -> Option<Change> {
+        self.last_change.take()
+    }
+}
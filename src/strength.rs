@@ -0,0 +1,98 @@
+/// How hard a key is to guess, estimated by `estimate`.
+#[derive(Debug, PartialEq, PartialOrd)]
+pub enum Strength {
+    VeryWeak,
+    Weak,
+    Fair,
+    Strong,
+}
+
+/// Passwords and words that are among the first to be guessed.
+const COMMON: &[&str] = &[
+    "password", "passwort", "123456", "qwerty", "azerty", "qwertz", "letmein", "welcome",
+    "iloveyou", "admin", "monkey", "dragon", "sunshine", "princess", "football", "baseball",
+    "master", "shadow", "secret", "abc123", "trustno1", "hello", "login", "starwars", "whatever",
+    "freedom", "changeme", "default", "journal", "diary",
+];
+
+/// Estimates the strength of `key` like zxcvbn does, though much simpler:
+/// common passwords and repeated or sequential characters, like `aaa` and
+/// `123`, are counted as easy to guess.
+pub fn estimate(key: &str) -> Strength {
+    match entropy(key) {
+        bits if bits < 30.0 => Strength::VeryWeak,
+        bits if bits < 50.0 => Strength::Weak,
+        bits if bits < 70.0 => Strength::Fair,
+        _ => Strength::Strong,
+    }
+}
+
+/// Estimated bits of entropy of `key`, i.e. log2 of the guesses needed.
+fn entropy(key: &str) -> f64 {
+    let chars: Vec<char> = key.chars().map(|c| c.to_ascii_lowercase()).collect();
+    let bits_per_char = (charset_size(key) as f64).log2();
+
+    // Common passwords are guessed as a whole, from a short list
+    let mut bits = 0.0;
+    let mut guessed = vec![false; chars.len()];
+    for word in COMMON {
+        let word: Vec<char> = word.chars().collect();
+        for start in 0..chars.len().saturating_sub(word.len() - 1) {
+            if chars[start..start + word.len()] == word[..] {
+                guessed[start..start + word.len()].fill(true);
+                bits += 10.0;
+            }
+        }
+    }
+
+    for (i, c) in chars.iter().enumerate() {
+        if guessed[i] {
+            continue;
+        }
+        let predictable = i > 0 && {
+            let (prev, c) = (chars[i - 1] as i64, *c as i64);
+            (prev - c).abs() <= 1
+        };
+        bits += if predictable { 1.0 } else { bits_per_char };
+    }
+    bits
+}
+
+/// Number of characters in the classes used by `key`.
+fn charset_size(key: &str) -> usize {
+    let mut size = 0;
+    if key.chars().any(|c| c.is_ascii_lowercase()) {
+        size += 26;
+    }
+    if key.chars().any(|c| c.is_ascii_uppercase()) {
+        size += 26;
+    }
+    if key.chars().any(|c| c.is_ascii_digit()) {
+        size += 10;
+    }
+    if key.chars().any(|c| c.is_ascii_punctuation() || c == ' ') {
+        size += 33;
+    }
+    if !key.is_ascii() {
+        size += 100;
+    }
+    size.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{estimate, Strength};
+
+    #[test]
+    fn estimating_strength() {
+        assert_eq!(estimate("password123"), Strength::VeryWeak);
+        assert_eq!(estimate("aaaaaaaaaaaa"), Strength::VeryWeak);
+        assert_eq!(estimate("abcdefgh"), Strength::VeryWeak);
+        assert_eq!(estimate("Journal2024!"), Strength::Weak);
+        assert_eq!(estimate("correct-horse-battery-staple"), Strength::Strong);
+        assert_eq!(
+            estimate("TO8W6jU38f0JMQ6KU30XRlR9ue4MHd85R1e/1g2E0tQ="),
+            Strength::Strong
+        );
+    }
+}
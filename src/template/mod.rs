@@ -1,12 +1,53 @@
-use crate::util;
+use chrono::{DateTime, Datelike, FixedOffset, SecondsFormat};
+use std::path::Path;
 
-pub fn create(template: Option<&String>) -> String {
+/// Template used for org files unless one is configured.
+const ORG_TEMPLATE: &str = "#+TITLE: {{TITLE}}\n#+DATE: {{DATE}}\n\n";
+
+/// The built-in template for files with `extension`, if any.
+pub fn default_template(extension: &str) -> Option<&'static str> {
+    match extension {
+        "org" => Some(ORG_TEMPLATE),
+        _ => None,
+    }
+}
+
+/// Creates the content of the journal `filename` from `template`,
+/// with the date placeholders and prompt of the day set by `now`.
+pub fn create(
+    template: Option<&str>,
+    filename: &str,
+    prompts: &[String],
+    now: DateTime<FixedOffset>,
+) -> String {
     let template = match template {
         None => return String::new(),
         Some(tmp) => tmp,
     };
 
-    let items = vec![("{{DATE}}", util::get_date())];
+    let date = now.date_naive();
+    let path = Path::new(filename);
+    let formatted = match path.extension().and_then(|ext| ext.to_str()) {
+        // Org timestamps, e.g. [2024-06-05 Wed]
+        Some("org") => date.format("[%Y-%m-%d %a]").to_string(),
+        _ => date.format("%Y-%m-%d").to_string(),
+    };
+    let title = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let items = vec![
+        ("{{DATE}}", formatted),
+        // ISO 8601 with the offset, e.g. 2024-06-05T09:30:00+02:00
+        (
+            "{{DATETIME}}",
+            now.to_rfc3339_opts(SecondsFormat::Secs, true),
+        ),
+        ("{{TIME}}", now.format("%H:%M").to_string()),
+        ("{{TITLE}}", title),
+        ("{{PROMPT_OF_DAY}}", prompt_of_day(prompts, date)),
+    ];
 
     let mut text = template.to_string();
     for (placeholder, s) in items {
@@ -15,3 +56,54 @@ pub fn create(template: Option<&String>) -> String {
 
     text
 }
+
+/// Creates an entry to append to the journal `filename`, with `content` in place
+/// of `{{CONTENT}}` in `template`, or on its own line if there's no template.
+/// The other placeholders are the same as for `create`, but aren't replaced
+/// in `content`.
+pub fn entry(
+    template: Option<&str>,
+    filename: &str,
+    prompts: &[String],
+    now: DateTime<FixedOffset>,
+    content: &str,
+) -> String {
+    let content = content.trim_end_matches('\n');
+    match template {
+        Some(_) => create(template, filename, prompts, now).replace("{{CONTENT}}", content),
+        None => format!("{}\n", content),
+    }
+}
+
+/// Picks one of the prompts by the date,
+/// so that it changes every day and is the same during a day.
+fn prompt_of_day(prompts: &[String], date: chrono::NaiveDate) -> String {
+    if prompts.is_empty() {
+        return String::new();
+    }
+
+    let day = date.num_days_from_ce() as usize;
+    prompts[day % prompts.len()].clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::entry;
+    use chrono::DateTime;
+
+    #[test]
+    fn creating_entries() {
+        let now = DateTime::parse_from_rfc3339("2024-06-05T09:30:00+02:00").unwrap();
+        assert_eq!(
+            entry(
+                Some("\n## {{TIME}}\n{{CONTENT}}\n"),
+                "a.md",
+                &[],
+                now,
+                "Hi {{DATE}}\n"
+            ),
+            "\n## 09:30\nHi {{DATE}}\n"
+        );
+        assert_eq!(entry(None, "a.md", &[], now, "Hi"), "Hi\n");
+    }
+}
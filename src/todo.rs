@@ -0,0 +1,80 @@
+use crate::types::LineMatch;
+
+/// Org keywords of headings that are still to be done.
+const ORG_OPEN_KEYWORDS: [&str; 3] = ["TODO", "NEXT", "WAITING"];
+
+/// Extracts the open to-dos in `content`. In org files these are headings
+/// with an open keyword, e.g. `** TODO Call mom`, and otherwise unchecked
+/// Markdown tasks, e.g. `- [ ] Call mom`, or lines with `TODO:`.
+pub fn extract(content: &str, org: bool) -> Vec<LineMatch> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(num, line)| {
+            let todo = if org {
+                org_todo(line)
+            } else {
+                markdown_todo(line)
+            };
+            todo.map(|text| LineMatch {
+                line: num + 1,
+                text,
+            })
+        })
+        .collect()
+}
+
+fn org_todo(line: &str) -> Option<String> {
+    let rest = line.trim_start_matches('*');
+    // Headings start with stars followed by a space
+    if rest.len() == line.len() || !rest.starts_with(' ') {
+        return None;
+    }
+
+    let rest = rest.trim_start();
+    let (keyword, title) = rest.split_once(' ').unwrap_or((rest, ""));
+    if ORG_OPEN_KEYWORDS.contains(&keyword) {
+        Some(format!("{} {}", keyword, title.trim()))
+    } else {
+        None
+    }
+}
+
+fn markdown_todo(line: &str) -> Option<String> {
+    let trimmed = line.trim_start();
+    for prefix in ["- [ ]", "* [ ]", "+ [ ]"] {
+        if let Some(task) = trimmed.strip_prefix(prefix) {
+            return Some(task.trim().to_string());
+        }
+    }
+
+    if line.contains("TODO:") {
+        return Some(line.trim().to_string());
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::extract;
+
+    #[test]
+    fn org_todos() {
+        let content = "#+TITLE: Notes\n* TODO Call mom\n** DONE Buy milk\n*** NEXT  Write report\n*TODO not a heading\n- [ ] not org";
+        let todos = extract(content, true);
+        let todos: Vec<(usize, &str)> = todos.iter().map(|t| (t.line, t.text.as_str())).collect();
+        assert_eq!(todos, [(2, "TODO Call mom"), (4, "NEXT Write report")]);
+    }
+
+    #[test]
+    fn markdown_todos() {
+        let content = "# Notes\n- [ ] Call mom\n- [x] Buy milk\n  * [ ] Nested\nTODO: fix the sink\n* TODO org style";
+        let todos = extract(content, false);
+        let todos: Vec<(usize, &str)> = todos.iter().map(|t| (t.line, t.text.as_str())).collect();
+        assert_eq!(
+            todos,
+            [(2, "Call mom"), (4, "Nested"), (5, "TODO: fix the sink")]
+        );
+    }
+}
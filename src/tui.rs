@@ -0,0 +1,557 @@
+use crate::cli::{Cli, Command, CreateArgs, RemoveArgs};
+use crate::fs::FileEntry;
+use crate::handler::Handler;
+use crate::types::Journal;
+use anyhow::Result;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Print, Stylize};
+use crossterm::terminal::{
+    self, disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
+    LeaveAlternateScreen,
+};
+use crossterm::{execute, queue};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use regex::RegexBuilder;
+use std::io::{stdout, Stdout, Write};
+use std::time::{Duration, SystemTime};
+
+const SIDEBAR_WIDTH: u16 = 20;
+const LIST_WIDTH: u16 = 30;
+
+/// How often the configuration file is checked for changes while idle.
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+const HELP: &str =
+    "enter: open  n: new  d: remove  /: filter  s: search  r: reload  tab: switch  q: quit";
+
+#[derive(PartialEq)]
+enum Focus {
+    Workspaces,
+    Journals,
+}
+
+/// What keys typed by the user are currently used for.
+enum Mode {
+    Normal,
+    Filter,
+    Create(String),
+    Search(String),
+    ConfirmRemove,
+}
+
+/// Restores the terminal when dropped, also on errors.
+struct Screen;
+
+impl Screen {
+    fn enter() -> Result<Self> {
+        Self::activate()?;
+        Ok(Self)
+    }
+
+    fn activate() -> Result<()> {
+        enable_raw_mode()?;
+        execute!(stdout(), EnterAlternateScreen, Hide)?;
+        Ok(())
+    }
+
+    fn leave(&self) -> Result<()> {
+        execute!(stdout(), Show, LeaveAlternateScreen)?;
+        disable_raw_mode()?;
+        Ok(())
+    }
+
+    /// Gives the terminal back while running `f`, e.g. for an editor.
+    fn suspend<T>(&self, f: impl FnOnce() -> T) -> Result<T> {
+        self.leave()?;
+        let res = f();
+        Self::activate()?;
+        Ok(res)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = self.leave();
+    }
+}
+
+pub struct Tui<'a> {
+    handler: &'a Handler,
+    /// The handler with the configuration reloaded, once it has changed.
+    reloaded: Option<Handler>,
+    config_modified: Option<SystemTime>,
+    key: Option<String>,
+    workspaces: Vec<(String, Vec<FileEntry>)>,
+    focus: Focus,
+    mode: Mode,
+    workspace: usize,
+    journal: usize,
+    filter: String,
+    /// Journals matching the last search, with the matching line numbers.
+    search: Option<Vec<(String, Vec<usize>)>>,
+    status: String,
+    matcher: SkimMatcherV2,
+}
+
+impl<'a> Tui<'a> {
+    pub fn new(handler: &'a Handler, key: Option<String>) -> Self {
+        Self {
+            handler,
+            reloaded: None,
+            config_modified: handler.config_modified(),
+            key,
+            workspaces: vec![],
+            focus: Focus::Journals,
+            mode: Mode::Normal,
+            workspace: 0,
+            journal: 0,
+            filter: String::new(),
+            search: None,
+            status: String::new(),
+            matcher: SkimMatcherV2::default(),
+        }
+    }
+
+    fn handler(&self) -> &Handler {
+        self.reloaded.as_ref().unwrap_or(self.handler)
+    }
+
+    /// Loads the configuration again if the file has changed, so that edits to
+    /// templates and appearance apply without restarting. Returns if it changed.
+    fn reload_config(&mut self) -> bool {
+        let modified = self.handler().config_modified();
+        if modified == self.config_modified {
+            return false;
+        }
+        self.config_modified = modified;
+
+        match self.handler().reload() {
+            Ok(handler) => {
+                self.reloaded = Some(handler);
+                self.status = "Reloaded the configuration".to_string();
+            }
+            // The previous configuration is kept until it's fixed
+            Err(err) => self.status = format!("error: {}", err),
+        }
+        true
+    }
+
+    pub fn run(mut self) -> Result<()> {
+        self.reload()?;
+
+        let screen = Screen::enter()?;
+        let mut out = stdout();
+
+        loop {
+            self.draw(&mut out)?;
+
+            let event = loop {
+                if event::poll(CONFIG_POLL_INTERVAL)? {
+                    break event::read()?;
+                }
+                if self.reload_config() {
+                    self.draw(&mut out)?;
+                }
+            };
+            let key = match event {
+                Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                _ => continue,
+            };
+
+            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                break;
+            }
+
+            let mode = std::mem::replace(&mut self.mode, Mode::Normal);
+            self.mode = match mode {
+                Mode::Normal => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => break,
+                    KeyCode::Enter | KeyCode::Char('o') => {
+                        let res = screen.suspend(|| self.open_selected())?;
+                        self.report(res);
+                        Mode::Normal
+                    }
+                    _ => self.on_normal_key(key),
+                },
+                Mode::Filter => self.on_filter_key(key),
+                Mode::Create(name) => match key.code {
+                    KeyCode::Enter => {
+                        let res = screen.suspend(|| self.create(&name))?;
+                        self.report(res);
+                        Mode::Normal
+                    }
+                    code => edit_input(name, code, Mode::Create),
+                },
+                Mode::Search(pattern) => match key.code {
+                    KeyCode::Enter => {
+                        let res = self.search(&pattern);
+                        self.report(res);
+                        Mode::Normal
+                    }
+                    code => edit_input(pattern, code, Mode::Search),
+                },
+                Mode::ConfirmRemove => {
+                    if key.code == KeyCode::Char('y') {
+                        let res = self.remove_selected();
+                        self.report(res);
+                    } else {
+                        self.status = "Cancelled".to_string();
+                    }
+                    Mode::Normal
+                }
+            };
+        }
+
+        Ok(())
+    }
+
+    fn on_normal_key(&mut self, key: KeyEvent) -> Mode {
+        match key.code {
+            KeyCode::Tab | KeyCode::BackTab | KeyCode::Left | KeyCode::Right => {
+                self.focus = match self.focus {
+                    Focus::Workspaces => Focus::Journals,
+                    Focus::Journals => Focus::Workspaces,
+                };
+            }
+            KeyCode::Char('h') => self.focus = Focus::Workspaces,
+            KeyCode::Char('l') => self.focus = Focus::Journals,
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Char('/') => {
+                self.focus = Focus::Journals;
+                return Mode::Filter;
+            }
+            KeyCode::Char('s') => return Mode::Search(String::new()),
+            KeyCode::Char('n') => return Mode::Create(String::new()),
+            KeyCode::Char('d') if self.selected_journal().is_some() => {
+                self.status = "Remove journal? (y/n)".to_string();
+                return Mode::ConfirmRemove;
+            }
+            KeyCode::Char('r') => {
+                let res = self.reload();
+                self.report(res);
+            }
+            _ => {}
+        }
+
+        Mode::Normal
+    }
+
+    fn on_filter_key(&mut self, key: KeyEvent) -> Mode {
+        match key.code {
+            KeyCode::Enter => return Mode::Normal,
+            KeyCode::Esc => self.filter.clear(),
+            KeyCode::Backspace => {
+                self.filter.pop();
+            }
+            KeyCode::Char(ch) => self.filter.push(ch),
+            _ => {}
+        }
+
+        self.journal = 0;
+        match key.code {
+            KeyCode::Esc => Mode::Normal,
+            _ => Mode::Filter,
+        }
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let (selected, len) = match self.focus {
+            Focus::Workspaces => (&mut self.workspace, self.workspaces.len()),
+            Focus::Journals => {
+                let len = self.visible_journals().len();
+                (&mut self.journal, len)
+            }
+        };
+
+        if len == 0 {
+            return;
+        }
+
+        *selected = (*selected as isize + delta).rem_euclid(len as isize) as usize;
+
+        if self.focus == Focus::Workspaces {
+            self.journal = 0;
+            self.search = None;
+        }
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        let mut workspaces: Vec<(String, Vec<FileEntry>)> = self
+            .handler
+            .list_workspaces_files(self.key.as_ref())?
+            .into_values()
+            .map(|ws| {
+                let mut files = ws.files;
+                files.sort_by_key(|f| f.filename());
+                (ws.name, files)
+            })
+            .collect();
+        workspaces.sort_by(|a, b| a.0.cmp(&b.0));
+
+        self.workspaces = workspaces;
+        self.workspace = self.workspace.min(self.workspaces.len().saturating_sub(1));
+        self.journal = 0;
+        Ok(())
+    }
+
+    fn current_workspace(&self) -> Option<&(String, Vec<FileEntry>)> {
+        self.workspaces.get(self.workspace)
+    }
+
+    /// The journals of the current workspace, narrowed down
+    /// by the fuzzy filter and search, best match first.
+    fn visible_journals(&self) -> Vec<&FileEntry> {
+        let files = match self.current_workspace() {
+            Some((_, files)) => files,
+            None => return vec![],
+        };
+
+        let mut scored: Vec<(i64, &FileEntry)> = files
+            .iter()
+            .filter(|f| match &self.search {
+                Some(results) => results.iter().any(|(name, _)| *name == f.filename()),
+                None => true,
+            })
+            .filter_map(|f| {
+                if self.filter.is_empty() {
+                    return Some((0, f));
+                }
+                self.matcher
+                    .fuzzy_match(&f.filename(), &self.filter)
+                    .map(|score| (score, f))
+            })
+            .collect();
+
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored.into_iter().map(|(_, f)| f).collect()
+    }
+
+    fn selected_journal(&self) -> Option<FileEntry> {
+        self.visible_journals()
+            .get(self.journal)
+            .map(|f| (*f).clone())
+    }
+
+    fn open_selected(&mut self) -> Result<()> {
+        if let Some(entry) = self.selected_journal() {
+            if !Journal::open(&entry, self.key.clone())?.edit()? {
+                self.status = "No changes".to_string();
+            }
+        }
+        Ok(())
+    }
+
+    fn create(&mut self, name: &str) -> Result<()> {
+        if name.is_empty() {
+            return Ok(());
+        }
+
+        let workspace = self.current_workspace().map(|(ws, _)| ws.clone());
+        self.handler().handle(Cli {
+            command: Command::Create(CreateArgs {
+                name: Some(name.to_string()),
+                pattern: None,
+                from: None,
+                to: None,
+                workspace,
+                key: self.key.clone(),
+            }),
+            // The status line shows what was done
+            quiet: true,
+            hidden: false,
+            profile_perf: false,
+            allow_weak_key: false,
+            config: None,
+        })?;
+
+        self.reload()?;
+        self.status = format!("Created {}", name);
+        Ok(())
+    }
+
+    fn remove_selected(&mut self) -> Result<()> {
+        let entry = match self.selected_journal() {
+            Some(entry) => entry,
+            None => return Ok(()),
+        };
+
+        let workspace = self.current_workspace().map(|(ws, _)| ws.clone());
+        self.handler().handle(Cli {
+            command: Command::Remove(RemoveArgs {
+                name: entry.filename(),
+                workspace,
+                remove_workspace: false,
+                dry_run: false,
+                key: self.key.clone(),
+            }),
+            // The status line shows what was done
+            quiet: true,
+            hidden: false,
+            profile_perf: false,
+            allow_weak_key: false,
+            config: None,
+        })?;
+
+        self.reload()?;
+        self.status = format!("Removed {}", entry.filename());
+        Ok(())
+    }
+
+    fn search(&mut self, pattern: &str) -> Result<()> {
+        if pattern.is_empty() {
+            self.search = None;
+            return Ok(());
+        }
+
+        let re = RegexBuilder::new(pattern).case_insensitive(true).build()?;
+        let mut workspaces = self.handler().list_workspaces_files(self.key.as_ref())?;
+        if let Some((ws, _)) = self.current_workspace() {
+            workspaces.retain(|name, _| name == ws);
+        }
+
+        let (results, _) =
+            self.handler()
+                .search_journals(&re, workspaces, self.key.clone(), false)?;
+        self.status = format!("{} journals matching {}", results.len(), pattern);
+        self.search = Some(
+            results
+                .into_iter()
+                .map(|r| (r.journal, r.matches.iter().map(|m| m.line).collect()))
+                .collect(),
+        );
+        self.journal = 0;
+        Ok(())
+    }
+
+    fn report(&mut self, res: Result<()>) {
+        if let Err(err) = res {
+            self.status = format!("error: {}", err);
+        }
+    }
+
+    fn draw(&self, out: &mut Stdout) -> Result<()> {
+        let (width, height) = terminal::size()?;
+        let body = height.saturating_sub(2);
+        queue!(out, Clear(ClearType::All))?;
+
+        // Workspace sidebar
+        for (i, (name, _)) in self.workspaces.iter().take(body as usize).enumerate() {
+            let text = fit(name, SIDEBAR_WIDTH - 1);
+            let text = if i == self.workspace {
+                if self.focus == Focus::Workspaces {
+                    text.reverse().to_string()
+                } else {
+                    text.bold().to_string()
+                }
+            } else {
+                text
+            };
+            queue!(out, MoveTo(0, i as u16), Print(text))?;
+        }
+
+        // Journal list
+        let journals = self.visible_journals();
+        for (i, entry) in journals.iter().take(body as usize).enumerate() {
+            let text = fit(&entry.filename(), LIST_WIDTH - 1);
+            let text = if i == self.journal && self.focus == Focus::Journals {
+                text.reverse().to_string()
+            } else {
+                text
+            };
+            queue!(out, MoveTo(SIDEBAR_WIDTH, i as u16), Print(text))?;
+        }
+
+        // Preview
+        let preview_x = SIDEBAR_WIDTH + LIST_WIDTH;
+        let preview_width = width.saturating_sub(preview_x);
+        if let Some(entry) = journals.get(self.journal) {
+            let lines = self.preview(entry, body as usize, preview_width);
+            for (i, line) in lines.into_iter().enumerate() {
+                queue!(out, MoveTo(preview_x, i as u16), Print(line))?;
+            }
+        }
+
+        // Status and prompt lines
+        let prompt = match &self.mode {
+            Mode::Normal | Mode::ConfirmRemove => self.status.clone(),
+            Mode::Filter => format!("/{}", self.filter),
+            Mode::Create(name) => format!("New journal: {}", name),
+            Mode::Search(pattern) => format!("Search: {}", pattern),
+        };
+        queue!(
+            out,
+            MoveTo(0, height.saturating_sub(2)),
+            Print(fit(&prompt, width)),
+            MoveTo(0, height.saturating_sub(1)),
+            Print(fit(HELP, width).dark_grey())
+        )?;
+
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Renders the first lines of the journal, with basic markdown styling.
+    fn preview(&self, entry: &FileEntry, height: usize, width: u16) -> Vec<String> {
+        let bytes = match Journal::open(entry, self.key.clone()).and_then(|j| j.bytes()) {
+            Ok(bytes) => bytes,
+            Err(_) => return vec!["(encrypted)".dark_grey().to_string()],
+        };
+
+        let matching: Vec<usize> = match &self.search {
+            Some(results) => results
+                .iter()
+                .find(|(name, _)| *name == entry.filename())
+                .map(|(_, lines)| lines.clone())
+                .unwrap_or_default(),
+            None => vec![],
+        };
+
+        let content = String::from_utf8_lossy(&bytes);
+        let mut in_code = false;
+
+        content
+            .lines()
+            .enumerate()
+            .take(height)
+            .map(|(num, line)| {
+                let text = fit(line, width);
+                if line.trim_start().starts_with("```") {
+                    in_code = !in_code;
+                    return text.dark_grey().to_string();
+                }
+
+                if matching.contains(&(num + 1)) {
+                    text.black().on_yellow().to_string()
+                } else if in_code {
+                    text.dark_grey().to_string()
+                } else if line.starts_with('#') {
+                    text.bold().magenta().to_string()
+                } else if line.starts_with("- ") || line.starts_with("* ") {
+                    text.cyan().to_string()
+                } else {
+                    text
+                }
+            })
+            .collect()
+    }
+}
+
+fn edit_input(mut input: String, code: KeyCode, mode: fn(String) -> Mode) -> Mode {
+    match code {
+        KeyCode::Esc => return Mode::Normal,
+        KeyCode::Backspace => {
+            input.pop();
+        }
+        KeyCode::Char(ch) => input.push(ch),
+        _ => {}
+    }
+    mode(input)
+}
+
+/// Truncates `s` to at most `width` characters.
+fn fit(s: &str, width: u16) -> String {
+    s.chars().take(width as usize).collect()
+}
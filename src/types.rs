@@ -1,9 +1,11 @@
-use crate::crypto;
-use crate::fs::{Editor, FileEntry};
-use anyhow::{bail, Context, Result};
+use crate::crypto::{self, DecryptError};
+use crate::fs::{read_file, Editor, FileEntry};
+use crate::perf;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs::OpenOptions;
-use std::io::Write;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 
 /// A map of workspace names to workspaces.
 /// A workspace consists of a list of files in
@@ -23,14 +25,236 @@ impl Workspace {
     }
 }
 
+/// Name of the file with workspace metadata in the workspace directory.
+pub const WORKSPACE_META_FILENAME: &str = ".workspace.toml";
+
+/// Metadata of a workspace, stored in the workspace directory.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct WorkspaceMeta {
+    pub description: Option<String>,
+    /// Date the workspace was created.
+    pub created: Option<String>,
+    /// Shown before the workspace name, e.g. an emoji.
+    pub icon: Option<String>,
+    /// Color of the workspace name, e.g. "blue".
+    pub color: Option<String>,
+}
+
+impl WorkspaceMeta {
+    /// Loads the metadata of the workspace `dir`, empty if it has none.
+    pub fn load(dir: &FileEntry) -> Result<Self> {
+        let path = dir.push(WORKSPACE_META_FILENAME);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = read_file(path.path())?;
+        let meta: WorkspaceMeta = toml::from_str(&content)?;
+        Ok(meta)
+    }
+
+    pub fn save(&self, dir: &FileEntry) -> Result<()> {
+        let content = toml::to_string(self)?;
+        std::fs::write(dir.push(WORKSPACE_META_FILENAME).path(), content)?;
+        Ok(())
+    }
+}
+
+/// A journal as shown when listing journals.
+pub struct ListedJournal {
+    pub entry: FileEntry,
+    pub encrypted: bool,
+}
+
+/// Metadata of a journal, as a row when listing in CSV or TSV.
+pub struct JournalRow {
+    pub workspace: String,
+    pub name: String,
+    /// Size of the file in bytes.
+    pub size: u64,
+    /// When the journal was modified, in RFC 3339.
+    pub modified: String,
+    pub encrypted: bool,
+    /// Number of words, if the journal could be read.
+    pub words: Option<usize>,
+    pub tags: Vec<String>,
+}
+
+/// The lines matching a search in a journal.
+#[derive(Serialize)]
+pub struct SearchResult {
+    pub workspace: String,
+    pub journal: String,
+    pub matches: Vec<LineMatch>,
+}
+
+/// What was searched, and the journals skipped, by a search.
+#[derive(Default)]
+pub struct SearchStats {
+    pub searched: usize,
+    pub binary: Vec<String>,
+    pub oversized: Vec<String>,
+    pub undecryptable: Vec<String>,
+}
+
+/// A journal that hasn't been opened in a while.
+pub struct ReviewJournal {
+    pub workspace: String,
+    pub name: String,
+    /// Days since the journal was last opened, or modified if never opened.
+    pub days: i64,
+    pub opened: bool,
+}
+
+/// The average mood of a week or month.
+pub struct MoodPeriod {
+    /// The week, e.g. 2024-W23, or month, e.g. 2024-06.
+    pub period: String,
+    pub average: f64,
+    pub entries: usize,
+}
+
+/// A journal from the same day in an earlier year or month, for `jn onthisday`.
+pub struct PastJournal {
+    pub date: chrono::NaiveDate,
+    pub workspace: String,
+    pub name: String,
+    /// None if it couldn't be decrypted, or is sensitive and wasn't revealed.
+    pub content: Option<String>,
+    /// Whether it has a tag in `sensitive-tags`.
+    pub sensitive: bool,
+}
+
+/// The number of journals and words in them, in total and of those written today.
+pub struct WordStats {
+    pub journals: usize,
+    pub words: usize,
+    pub today_journals: usize,
+    pub today_words: usize,
+    /// Words to write every day, if configured.
+    pub goal: Option<usize>,
+}
+
+/// The lines, words and characters of a journal, counted like `wc`.
+pub struct WordCount {
+    pub name: String,
+    pub lines: usize,
+    pub words: usize,
+    pub chars: usize,
+}
+
+impl WordCount {
+    pub fn of(name: &str, content: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            lines: content.lines().count(),
+            words: content.split_whitespace().count(),
+            chars: content.chars().count(),
+        }
+    }
+}
+
+/// Journals counted together by `--group-by`, e.g. those of a month.
+pub struct Group {
+    /// The workspace, extension or month, e.g. 2024-06.
+    pub name: String,
+    pub journals: usize,
+    /// Total size in bytes of the journals, when listing.
+    pub size: Option<u64>,
+    /// Number of matches in the journals, when searching.
+    pub matches: Option<usize>,
+}
+
+/// How many journals of a workspace are encrypted, and which keys decrypt them.
+pub struct WorkspaceStatus {
+    pub name: String,
+    /// Encrypted and plaintext journals, or `None` if the filenames
+    /// are encrypted and none of the keys opens the index.
+    pub counts: Option<(usize, usize)>,
+    /// Encrypted journals decrypted by each key, in the order they were given.
+    pub keys: Vec<usize>,
+    /// Encrypted journals none of the keys decrypt.
+    pub locked: usize,
+    /// Plaintext journals in a workspace configured to be encrypted.
+    pub plaintext_flagged: Vec<String>,
+}
+
+/// A conflicting copy of a journal, made by a sync tool.
+pub struct Conflict {
+    /// Workspace and name of the copy, e.g. `work/notes (conflicted copy).md`.
+    pub copy: String,
+    /// Workspace and name of the journal it's a copy of.
+    pub original: String,
+    /// Lines inserted and deleted in the copy, unless it couldn't be decrypted.
+    pub changes: Option<(usize, usize)>,
+}
+
+/// A change that a command with `--dry-run` would make, as {workspace}/{journal}.
+pub enum PlannedChange {
+    /// A journal, or a workspace and its files, of `bytes` in size.
+    Remove {
+        path: String,
+        files: usize,
+        bytes: u64,
+    },
+    Rename {
+        from: String,
+        to: String,
+    },
+    /// A journal written with `bytes`, replacing the `replaced` bytes if it exists.
+    Write {
+        path: String,
+        bytes: u64,
+        replaced: Option<u64>,
+    },
+    /// An existing journal that would be left as it is.
+    Skip {
+        path: String,
+    },
+}
+
+/// The lines changed by a replacement in a journal.
+pub struct Replacement {
+    pub workspace: String,
+    pub journal: String,
+    pub changes: Vec<LineChange>,
+}
+
+pub struct LineChange {
+    /// Line number, starting at 1.
+    pub line: usize,
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Serialize)]
+pub struct LineMatch {
+    /// Line number, starting at 1.
+    pub line: usize,
+    pub text: String,
+}
+
+/// The largest possible header: the flag, the three lengths, and a nonce,
+/// tag and key check of at most 255 bytes each.
+const MAX_HEADER_SIZE: usize = 4 + 3 * u8::MAX as usize;
+
+/// First byte of journals encrypted before headers had a key check.
+const HEADER_V1: u8 = 0x01;
+/// First byte of journals encrypted with a key check in the header.
+const HEADER_V2: u8 = 0x02;
+
 /// A journal file has a header if it was encrypted, meaning it has to
 /// be decoded.
-/// If the journal was encoded, the first byte is set to 0x01 (00000001).
+/// If the journal was encoded, the first byte is set to its version,
+/// 0x02 (00000010), or 0x01 (00000001) for older journals.
 /// It is then followed by two bytes:
 ///   - nonce length in bytes
 ///   - tag length in bytes
-/// Then those two bytes are immediately followed by
-/// the nonce and tag, respectively.
+///
+/// Version 2 has a third byte with the length of the key check.
+///
+/// Then those bytes are immediately followed by
+/// the nonce, tag and key check, respectively.
 ///
 /// Then the actual content starts.
 /// If no encryption is set, the content starts immediately.
@@ -42,6 +266,9 @@ struct Header {
     /// Authentication tag used when encrypting/decrypting.
     /// Empty if not encrypted.
     tag: Vec<u8>,
+    /// Tells if a key is the right one without decrypting, see `crypto::key_check`.
+    /// Empty if not encrypted or encrypted with version 1.
+    check: Vec<u8>,
 }
 
 impl Header {
@@ -49,15 +276,17 @@ impl Header {
         Self {
             nonce: vec![],
             tag: vec![],
+            check: vec![],
             size: 0,
         }
     }
 
-    fn new_encrypted(nonce: Vec<u8>, tag: Vec<u8>) -> Self {
+    fn new_encrypted(nonce: Vec<u8>, tag: Vec<u8>, check: Vec<u8>) -> Self {
         Self {
-            size: 1 + nonce.len() + tag.len(),
+            size: 4 + nonce.len() + tag.len() + check.len(),
             nonce,
             tag,
+            check,
         }
     }
 
@@ -67,54 +296,61 @@ impl Header {
         }
 
         let mut buf = Vec::with_capacity(self.size);
-        buf.push(0x01);
+        buf.push(HEADER_V2);
 
         buf.push(self.nonce.len() as u8);
         buf.push(self.tag.len() as u8);
+        buf.push(self.check.len() as u8);
         buf.extend_from_slice(&self.nonce);
         buf.extend_from_slice(&self.tag);
+        buf.extend_from_slice(&self.check);
 
         writer.write_all(&buf)?;
 
         Ok(())
     }
 
-    fn decode(value: &[u8]) -> Result<Self> {
-        let flag = match value.first() {
+    /// Reads the header from the start of `reader`, consuming only the header bytes.
+    /// A plaintext journal leaves the reader untouched.
+    fn decode<R: BufRead>(reader: &mut R) -> Result<Self> {
+        let flag = match reader.fill_buf()?.first() {
             Some(b) => *b,
             None => return Ok(Header::empty()),
         };
 
-        if flag != 0x01 {
+        if flag != HEADER_V1 && flag != HEADER_V2 {
             return Ok(Header::empty());
         }
 
         // File was encrypted.
-        let mut size = 1;
-        let mut nonce: Vec<u8> = vec![];
-        let mut tag: Vec<u8> = vec![];
-
-        // The next byte is the size of the nonce in bytes.
-        let nonce_size = value
-            .get(1)
-            .context("failed to decode header: missing nonce size")?;
-        let nonce_size = *nonce_size as usize;
-
-        // The next byte is the size of the tag in bytes.
-        let tag_size = value
-            .get(2)
-            .context("failed to decode header: missing tag size")?;
-        let tag_size = *tag_size as usize;
-
-        size += 2;
-
-        nonce.extend_from_slice(&value[size..(size + nonce_size)]);
-        size += nonce_size;
-
-        tag.extend_from_slice(&value[size..(size + tag_size)]);
-        size += tag_size;
-
-        Ok(Self { size, nonce, tag })
+        // The flag is followed by the size of the nonce and the tag in bytes,
+        // and of the key check in version 2.
+        let mut sizes = vec![0u8; if flag == HEADER_V2 { 4 } else { 3 }];
+        reader
+            .read_exact(&mut sizes)
+            .context("missing nonce or tag size")?;
+        let nonce_size = sizes[1] as usize;
+        let tag_size = sizes[2] as usize;
+        let check_size = sizes.get(3).copied().unwrap_or_default() as usize;
+
+        let mut nonce = vec![0; nonce_size];
+        reader.read_exact(&mut nonce).context("truncated nonce")?;
+
+        let mut tag = vec![0; tag_size];
+        reader.read_exact(&mut tag).context("truncated tag")?;
+
+        let mut check = vec![0; check_size];
+        reader
+            .read_exact(&mut check)
+            .context("truncated key check")?;
+
+        let size = sizes.len() + nonce_size + tag_size + check_size;
+        Ok(Self {
+            size,
+            nonce,
+            tag,
+            check,
+        })
     }
 }
 
@@ -122,102 +358,373 @@ pub struct Journal {
     filepath: FileEntry,
     key: Option<String>,
     header: Header,
-    contents: Vec<u8>,
 }
 
 impl Journal {
-    pub fn create(filepath: &FileEntry, key: Option<String>, content: &[u8]) -> Result<()> {
+    /// Creates the journal with `content` edited by the user.
+    /// Returns false if the journal wasn't created since the content was
+    /// empty and `discard_empty` is set.
+    pub fn create(
+        filepath: &FileEntry,
+        key: Option<String>,
+        content: &[u8],
+        discard_empty: bool,
+    ) -> Result<bool> {
         let editor = Editor::new();
 
         let filename = filepath.filename();
         let content = editor.edit_temp(&filename, content)?;
 
-        let mut file = OpenOptions::new()
+        if discard_empty && content.iter().all(|b| b.is_ascii_whitespace()) {
+            return Ok(false);
+        }
+
+        Self::save_new(filepath, key.as_ref(), &content)?;
+        Ok(true)
+    }
+
+    /// Writes `content` to the new journal at `filepath`, failing if it already exists.
+    pub fn save_new(filepath: &FileEntry, key: Option<&String>, content: &[u8]) -> Result<()> {
+        let file = OpenOptions::new()
             .write(true)
             .create_new(true)
             .open(filepath.as_ref())?;
+        Self::write(file, key, content)
+    }
 
-        Self::write(&mut file, key.as_ref(), &content)?;
+    /// Writes `content` to `filepath`, replacing any previous content.
+    pub fn save(filepath: &FileEntry, key: Option<&String>, content: &[u8]) -> Result<()> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(filepath.as_ref())?;
+        Self::write(file, key, content)
+    }
 
-        Ok(())
+    /// Checks if the journal is encrypted by reading only the header flag.
+    pub fn is_encrypted(file_entry: &FileEntry) -> Result<bool> {
+        let flag = file_entry.read_prefix(1)?;
+        Ok(matches!(flag.first(), Some(&HEADER_V1 | &HEADER_V2)))
     }
 
+    /// Opens the journal, reading only the header.
+    /// The content is read on demand by `bytes` and `write_to`.
     pub fn open(file_entry: &FileEntry, key: Option<String>) -> Result<Self> {
-        let contents = file_entry.read_bytes()?;
-        let header = Header::decode(contents.as_ref())?;
+        let prefix = file_entry.read_prefix(MAX_HEADER_SIZE)?;
+        let header = Header::decode(&mut prefix.as_slice()).map_err(|err| {
+            let err = DecryptError::CorruptedHeader(err.to_string());
+            anyhow!("failed to decrypt {}: {}", file_entry.filename(), err)
+        })?;
         Ok(Self {
             filepath: file_entry.clone(),
             key,
             header,
-            contents,
         })
     }
 
-    fn encrypted(&self) -> bool {
+    pub fn encrypted(&self) -> bool {
         self.header.size > 0
     }
 
+    /// The key the journal was opened with.
+    pub fn key(&self) -> Option<&String> {
+        self.key.as_ref()
+    }
+
+    /// Returns the plaintext content of the journal.
     pub fn bytes(&self) -> Result<Vec<u8>> {
+        if self.encrypted() && self.key_matches()? == Some(false) {
+            return Err(self.decrypt_error(DecryptError::WrongKey));
+        }
+
+        let data = self.read_content()?;
         if self.encrypted() {
-            self.decrypt()
+            self.decrypt(data)
         } else {
-            let data = &self.contents[self.header.size..];
-            let mut bs = Vec::with_capacity(data.len());
-            bs.extend_from_slice(data);
-            Ok(bs)
+            Ok(data)
         }
     }
 
-    pub fn edit(&self) -> Result<()> {
-        let editor = Editor::new();
-        let content = self.bytes()?;
+    /// Whether the key is the one the journal was encrypted with, checked
+    /// against the header without decrypting the content. `None` if that
+    /// can't be known without decrypting, i.e. for journals encrypted before
+    /// headers had a key check. Fails without a key.
+    pub fn key_matches(&self) -> Result<Option<bool>> {
+        let key = self.require_key()?;
+        if self.header.check.is_empty() {
+            return Ok(None);
+        }
+        let check = crypto::key_check(key, &self.header.nonce)?;
+        Ok(Some(check == self.header.check))
+    }
+
+    /// Returns the plaintext content like `bytes`, but `None` if the journal
+    /// is encrypted and no key, or the wrong key, was given. Errors are then
+    /// only from reading the file.
+    pub fn try_bytes(&self) -> Result<Option<Vec<u8>>> {
+        if !self.encrypted() {
+            return Ok(Some(self.read_content()?));
+        }
+        // Checked before reading, since the content is not needed then
+        if self.key.is_none() || self.key_matches().ok() == Some(Some(false)) {
+            return Ok(None);
+        }
+        Ok(self.decrypt(self.read_content()?).ok())
+    }
+
+    /// Returns the plaintext content of the journal as text, replacing invalid
+    /// UTF-8, which is only copied if there is any.
+    pub fn text(&self) -> Result<String> {
+        Ok(into_text(self.bytes()?))
+    }
+
+    /// Returns the content as text like `text`, if it could be read as by `try_bytes`.
+    pub fn try_text(&self) -> Result<Option<String>> {
+        Ok(self.try_bytes()?.map(into_text))
+    }
+
+    pub fn has_key(&self) -> bool {
+        self.key.is_some()
+    }
+
+    /// Writes the plaintext content of the journal to `writer`.
+    /// Plaintext journals are streamed without being buffered in memory.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        if self.encrypted() {
+            let bytes = self.bytes()?;
+            writer.write_all(&bytes)?;
+        } else {
+            let mut reader = self.content_reader()?;
+            io::copy(&mut reader, writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Edits the journal in the editor.
+    /// Returns false if the content was left unchanged, in which case
+    /// the file isn't rewritten.
+    pub fn edit(&self) -> Result<bool> {
+        self.edit_with(Editor::new())
+    }
+
+    /// Like `edit`, but with the given editor, e.g. opened at a line.
+    pub fn edit_with(&self, editor: Editor) -> Result<bool> {
+        let original = self.bytes()?;
+        // Used to detect if the file is changed by someone else while editing.
+        let before = self.filepath.digest()?;
 
         let filename = self.filepath.filename();
-        let content = editor.edit_temp(&filename, &content)?;
+        let content = editor.edit_temp(&filename, &original)?;
+
+        if content == original {
+            return Ok(false);
+        }
+
+        let after = self.filepath.digest()?;
+        if before != after {
+            let conflict = self.save_conflict(&content)?;
+            bail!(
+                "journal was modified while editing, changes saved to {}",
+                conflict.path().display()
+            );
+        }
 
-        let mut file = OpenOptions::new()
+        let file = OpenOptions::new()
             .write(true)
             .truncate(true)
             .open(self.filepath.as_ref())?;
-        Self::write(&mut file, self.key.as_ref(), &content)?;
+        Self::write(file, self.key.as_ref(), &content)?;
 
-        Ok(())
+        Ok(true)
     }
 
-    fn write<W: Write>(writer: &mut W, key: Option<&String>, content: &[u8]) -> Result<()> {
+    /// Offers to save `content` next to the journal, since the journal
+    /// was modified by someone else and must not be overwritten.
+    fn save_conflict(&self, content: &[u8]) -> Result<FileEntry> {
+        // Keep the extension so the editor recognizes the file type.
+        let timestamp = chrono::Local::now().format("%Y%m%d%H%M%S");
+        let path = self.filepath.path();
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let filename = match path.extension() {
+            Some(ext) => format!("{}.conflict-{}.{}", stem, timestamp, ext.to_string_lossy()),
+            None => format!("{}.conflict-{}", stem, timestamp),
+        };
+        let conflict = FileEntry::new(&path.with_file_name(filename));
+
+        let msg = format!(
+            "{} was modified while editing. Save your changes to {}?",
+            self.filepath, conflict
+        );
+        // The changes are kept if the user can't be asked, e.g. without a TTY.
+        if let Ok(false) = inquire::Confirm::new(&msg).with_default(true).prompt() {
+            bail!("journal was modified while editing, changes discarded");
+        }
+
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(conflict.as_ref())?;
+        Self::write(file, self.key.as_ref(), content)?;
+
+        Ok(conflict)
+    }
+
+    fn write(file: File, key: Option<&String>, content: &[u8]) -> Result<()> {
+        // The header is written in parts, so these are buffered
+        let mut writer = BufWriter::new(file);
         if let Some(key) = &key {
             // When writing the file it may not be encrypted before,
             // so the header must be updated accordingly.
             let res = crypto::encrypt(content, key)?;
-            let header = Header::new_encrypted(res.nonce, res.tag);
-            header.encode(writer)?;
+            let check = crypto::key_check(key, &res.nonce)?;
+            let header = Header::new_encrypted(res.nonce, res.tag, check);
+            header.encode(&mut writer)?;
 
             writer.write_all(&res.ciphertext)?;
         } else {
             writer.write_all(content)?;
         }
 
+        // Flushed explicitly, since errors are ignored when dropped
+        writer.flush()?;
         Ok(())
     }
 
-    fn decrypt(&self) -> Result<Vec<u8>> {
+    /// Opens the file positioned at the start of the content, i.e. after the header.
+    fn content_reader(&self) -> Result<BufReader<File>> {
+        let mut file = self.filepath.open()?;
+        file.seek(SeekFrom::Start(self.header.size as u64))?;
+        Ok(BufReader::new(file))
+    }
+
+    /// Reads the content into a buffer allocated for its size, with room
+    /// for the tag that's appended to it when decrypting.
+    fn read_content(&self) -> Result<Vec<u8>> {
+        perf::time("read", || {
+            let mut file = self.filepath.open()?;
+            let len = file.metadata()?.len() as usize;
+            file.seek(SeekFrom::Start(self.header.size as u64))?;
+
+            let capacity = len.saturating_sub(self.header.size) + self.header.tag.len();
+            let mut data = Vec::with_capacity(capacity);
+            file.read_to_end(&mut data)?;
+            Ok(data)
+        })
+    }
+
+    fn decrypt(&self, data: Vec<u8>) -> Result<Vec<u8>> {
         let key = self.require_key()?;
 
-        let data = &self.contents[self.header.size..];
         let plaintext = crypto::decrypt(
             key,
             self.header.nonce.as_ref(),
             self.header.tag.as_ref(),
             data,
-        )?;
+        );
+
+        match plaintext {
+            Ok(plaintext) => Ok(plaintext),
+            Err(err) => match err.downcast::<DecryptError>() {
+                // The key check tells a wrong key from corrupted content
+                Ok(err) => Err(self.decrypt_error(match self.key_matches()? {
+                    Some(true) => DecryptError::Corrupted,
+                    Some(false) => DecryptError::WrongKey,
+                    None => err,
+                })),
+                Err(err) => Err(err),
+            },
+        }
+    }
 
-        Ok(plaintext)
+    /// Fails unless the journal is encrypted, for files that must be, e.g. bundles.
+    pub fn require_encrypted(&self) -> Result<()> {
+        if !self.encrypted() {
+            return Err(self.decrypt_error(DecryptError::NotEncrypted));
+        }
+        Ok(())
+    }
+
+    fn decrypt_error(&self, err: DecryptError) -> anyhow::Error {
+        anyhow!("failed to decrypt {}: {}", self.filepath.filename(), err)
     }
 
     fn require_key(&self) -> Result<&str> {
         match &self.key {
             Some(key) => Ok(key.as_str()),
-            None => bail!("key required for encrypted file"),
+            None => Err(self.decrypt_error(DecryptError::MissingKey)),
         }
     }
 }
+
+/// Converts `bytes` to a string without copying them, unless they
+/// must be because invalid UTF-8 is replaced.
+fn into_text(bytes: Vec<u8>) -> String {
+    match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(err) => String::from_utf8_lossy(err.as_bytes()).into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_roundtrip() -> Result<()> {
+        let header = Header::new_encrypted(vec![1; 12], vec![2; 16], vec![3; 8]);
+        let mut buf = Vec::new();
+        header.encode(&mut buf)?;
+        buf.extend_from_slice(b"ciphertext");
+
+        let mut reader = buf.as_slice();
+        let decoded = Header::decode(&mut reader)?;
+        assert_eq!(decoded.size, header.size);
+        assert_eq!(decoded.nonce, header.nonce);
+        assert_eq!(decoded.tag, header.tag);
+        assert_eq!(decoded.check, header.check);
+        assert_eq!(reader, b"ciphertext");
+
+        Ok(())
+    }
+
+    #[test]
+    fn header_decode_v1() -> Result<()> {
+        let mut buf = vec![HEADER_V1, 2, 1, 7, 7, 9];
+        buf.extend_from_slice(b"ciphertext");
+
+        let mut reader = buf.as_slice();
+        let header = Header::decode(&mut reader)?;
+        assert_eq!(header.size, 6);
+        assert_eq!(header.nonce, [7, 7]);
+        assert_eq!(header.tag, [9]);
+        assert!(header.check.is_empty());
+        assert_eq!(reader, b"ciphertext");
+
+        Ok(())
+    }
+
+    #[test]
+    fn header_decode_plaintext() -> Result<()> {
+        let mut reader: &[u8] = b"# Journal";
+        let header = Header::decode(&mut reader)?;
+        assert_eq!(header.size, 0);
+        assert_eq!(reader, b"# Journal");
+
+        Ok(())
+    }
+
+    #[test]
+    fn counting_words() {
+        let count = WordCount::of("notes.md", "# Notes\n\nfirst draft, åäö\n");
+        assert_eq!((count.lines, count.words, count.chars), (3, 5, 26));
+        assert_eq!(WordCount::of("empty.md", "").lines, 0);
+    }
+
+    #[test]
+    fn header_decode_truncated() {
+        let mut reader: &[u8] = &[0x01, 12, 16, 1, 2];
+        assert!(Header::decode(&mut reader).is_err());
+    }
+}
@@ -1,3 +1,247 @@
-pub fn get_date() -> String {
-    chrono::Local::now().format("%Y-%m-%d").to_string()
+use anyhow::{bail, Result};
+use chrono::{DateTime, FixedOffset, Local, Months, NaiveDate, TimeZone, Utc};
+
+/// The time zone of dates in filenames and templates.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Zone {
+    /// The time zone of the system.
+    #[default]
+    Local,
+    /// A fixed offset from UTC, which is UTC itself for 0.
+    Offset(FixedOffset),
+}
+
+impl Zone {
+    /// Parses `local`, `utc` or an offset from UTC such as `+02:00` or `-0530`.
+    pub fn parse(s: &str) -> Result<Self> {
+        let s = s.trim();
+        match s.to_lowercase().as_str() {
+            "local" => return Ok(Zone::Local),
+            "utc" | "z" => return Ok(Zone::Offset(FixedOffset::east_opt(0).unwrap())),
+            _ => {}
+        }
+
+        let (sign, digits) = match s.chars().next() {
+            Some('+') => (1, &s[1..]),
+            Some('-') => (-1, &s[1..]),
+            _ => bail!(
+                "invalid time zone, expected local, utc or an offset such as +02:00: {}",
+                s
+            ),
+        };
+        let digits = digits.replace(':', "");
+        let (hours, minutes) = match (digits.len(), digits.parse::<i32>()) {
+            (2, Ok(n)) => (n, 0),
+            (4, Ok(n)) => (n / 100, n % 100),
+            _ => bail!("invalid time zone offset: {}", s),
+        };
+        match FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)) {
+            Some(offset) if minutes < 60 => Ok(Zone::Offset(offset)),
+            _ => bail!("invalid time zone offset: {}", s),
+        }
+    }
+
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        match self {
+            Zone::Local => Local::now().fixed_offset(),
+            Zone::Offset(offset) => Utc::now().with_timezone(offset),
+        }
+    }
+
+    /// The start of `date` in the time zone.
+    pub fn midnight(&self, date: NaiveDate) -> DateTime<FixedOffset> {
+        let naive = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+        let local = match self {
+            Zone::Local => Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|d| d.fixed_offset()),
+            Zone::Offset(offset) => offset.from_local_datetime(&naive).earliest(),
+        };
+        local.unwrap_or_else(|| naive.and_utc().fixed_offset())
+    }
+}
+
+/// Today's date in `zone`, as used in filenames, e.g. 2024-06-05.
+pub fn get_date(zone: Zone) -> String {
+    zone.now().format("%Y-%m-%d").to_string()
+}
+
+/// The point in time given by `s`: the start of a date in `zone`, e.g. 2024-06-01,
+/// or a duration before `now` in hours, days, weeks, months or years, e.g. 30d or 1mo.
+pub fn parse_since(
+    s: &str,
+    zone: Zone,
+    now: DateTime<FixedOffset>,
+) -> Result<DateTime<FixedOffset>> {
+    let s = s.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(zone.midnight(date));
+    }
+
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (count, unit) = s.split_at(split);
+    let count: u32 = match count.parse() {
+        Ok(count) => count,
+        Err(_) => bail!(
+            "expected a date such as 2024-06-01 or a duration such as 30d: {}",
+            s
+        ),
+    };
+    let since = match unit {
+        "h" => now.checked_sub_signed(chrono::Duration::hours(count.into())),
+        "d" => now.checked_sub_signed(chrono::Duration::days(count.into())),
+        "w" => now.checked_sub_signed(chrono::Duration::weeks(count.into())),
+        "mo" => now.checked_sub_months(Months::new(count)),
+        "y" => now.checked_sub_months(Months::new(count.saturating_mul(12))),
+        _ => bail!("unknown unit of duration, expected h, d, w, mo or y: {}", s),
+    };
+    match since {
+        Some(since) => Ok(since),
+        None => bail!("duration is too long: {}", s),
+    }
+}
+
+/// Splits `s` into arguments by whitespace, except within quotes.
+pub fn split_args(s: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quote: Option<char> = None;
+
+    for ch in s.chars() {
+        match (quote, ch) {
+            (Some(q), ch) if ch == q => quote = None,
+            (Some(_), ch) => current.get_or_insert_with(String::new).push(ch),
+            (None, '"' | '\'') => {
+                quote = Some(ch);
+                current.get_or_insert_with(String::new);
+            }
+            (None, ch) if ch.is_whitespace() => args.extend(current.take()),
+            (None, ch) => current.get_or_insert_with(String::new).push(ch),
+        }
+    }
+
+    args.extend(current);
+    args
+}
+
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+/// Matches `name` against a glob `pattern`, where `*` matches any
+/// number of characters and `?` matches exactly one.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    // Position of the last star and the name position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                // Let the star match one more character
+                Some((star, at)) => {
+                    p = star + 1;
+                    n = at + 1;
+                    backtrack = Some((star, at + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Lowercases `s` and joins its words with dashes, for use in filenames,
+/// e.g. `Meeting notes: Q3` becomes `meeting-notes-q3`.
+pub fn slugify(s: &str) -> String {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<String>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{glob_match, parse_since, slugify, split_args, Zone};
+
+    #[test]
+    fn splitting_args() {
+        assert_eq!(
+            split_args("create a.md -w work"),
+            ["create", "a.md", "-w", "work"]
+        );
+        assert_eq!(
+            split_args("  search  'to do' -F "),
+            ["search", "to do", "-F"]
+        );
+        assert_eq!(split_args(r#"search "it's" """#), ["search", "it's", ""]);
+        assert!(split_args("   ").is_empty());
+    }
+
+    #[test]
+    fn matching_globs() {
+        assert!(glob_match("work", "work"));
+        assert!(glob_match("work*", "work"));
+        assert!(glob_match("work*", "work-2024"));
+        assert!(glob_match("client-*", "client-acme"));
+        assert!(glob_match("*-notes", "team-notes"));
+        assert!(glob_match("q?-2024", "q3-2024"));
+        assert!(glob_match("*a*b*", "xxaxxbxx"));
+
+        assert!(!glob_match("work", "work-2024"));
+        assert!(!glob_match("client-*", "clients"));
+        assert!(!glob_match("q?-2024", "q10-2024"));
+        assert!(!glob_match("*a*b", "xxaxxbxx"));
+    }
+
+    #[test]
+    fn slugs() {
+        assert_eq!(slugify("Meeting notes: Q3"), "meeting-notes-q3");
+        assert_eq!(slugify("  Åka skidor!  "), "åka-skidor");
+        assert_eq!(slugify("--"), "");
+    }
+
+    #[test]
+    fn parsing_zones() {
+        let offset = |secs| Zone::Offset(chrono::FixedOffset::east_opt(secs).unwrap());
+        assert_eq!(Zone::parse("local").unwrap(), Zone::Local);
+        assert_eq!(Zone::parse("UTC").unwrap(), offset(0));
+        assert_eq!(Zone::parse("+02:00").unwrap(), offset(7200));
+        assert_eq!(Zone::parse("-0530").unwrap(), offset(-19800));
+        assert_eq!(Zone::parse("+09").unwrap(), offset(32400));
+
+        assert!(Zone::parse("Europe/Stockholm").is_err());
+        assert!(Zone::parse("+02:75").is_err());
+        assert!(Zone::parse("+25:00").is_err());
+    }
+
+    #[test]
+    fn parsing_since() {
+        let zone = Zone::parse("+02:00").unwrap();
+        let now = chrono::DateTime::parse_from_rfc3339("2024-06-05T12:00:00+02:00").unwrap();
+        let since = |s| parse_since(s, zone, now).unwrap().to_rfc3339();
+        assert_eq!(since("2024-06-01"), "2024-06-01T00:00:00+02:00");
+        assert_eq!(since("12h"), "2024-06-05T00:00:00+02:00");
+        assert_eq!(since("2w"), "2024-05-22T12:00:00+02:00");
+        assert_eq!(since("1mo"), "2024-05-05T12:00:00+02:00");
+        assert_eq!(since("1y"), "2023-06-05T12:00:00+02:00");
+
+        assert!(parse_since("30", zone, now).is_err());
+        assert!(parse_since("3 days", zone, now).is_err());
+        assert!(parse_since("yesterday", zone, now).is_err());
+    }
 }
@@ -1,50 +1,195 @@
+use crate::{export::sftp, frontmatter, util};
 use anyhow::{bail, Result};
 
-const WORKSPACE_CHARS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ-_";
+/// Characters allowed in workspace names besides letters and digits.
+const WORKSPACE_CHARS: &str = "-_";
 
 pub fn valid_workspace_name(s: &str) -> Result<String> {
     let s = s.trim();
-    if s.len() < 2 {
+    // Workspaces of other roots are named {root}:{workspace}
+    if let Some((root, workspace)) = s.split_once(':') {
+        if workspace.contains(':') {
+            bail!("invalid workspace name: {}", s);
+        }
+        valid_workspace_name(root)?;
+        return Ok(format!("{}:{}", root, valid_workspace_name(workspace)?));
+    }
+
+    let len = s.chars().count();
+    if len < 2 {
         bail!("too short workspace name: {}", s);
     }
 
-    if s.len() > 25 {
+    if len > 25 {
         bail!("too long workspace name");
     }
 
     let mut invalid = s
         .chars()
-        .filter(|ch| !WORKSPACE_CHARS.contains(*ch))
+        .filter(|ch| !ch.is_alphanumeric() && !WORKSPACE_CHARS.contains(*ch))
         .collect::<Vec<char>>();
     invalid.dedup();
 
     if invalid.is_empty() {
         Ok(s.to_string())
     } else {
-        let s: String = invalid.iter().collect();
+        let s: String = invalid
+            .iter()
+            .map(|ch| ch.escape_default().to_string())
+            .collect();
         bail!("contains invalid characters: {}", s)
     }
 }
 
+/// Validates the name of a journal, which may be a path to a journal in
+/// a subdirectory of the workspace, e.g. `projects/alpha/notes.md`.
+pub fn valid_journal_path(s: &str) -> Result<String> {
+    let invalid = s.contains('\\')
+        || s.split('/')
+            .any(|part| part.is_empty() || part == "." || part == "..");
+    if invalid {
+        bail!("invalid journal name: {}", s);
+    }
+    Ok(s.to_string())
+}
+
+/// Validates a workspace name, or a glob pattern matching workspace names.
+pub fn valid_workspace_pattern(s: &str) -> Result<String> {
+    let s = s.trim();
+    if !util::is_glob(s) {
+        return valid_workspace_name(s);
+    }
+    if let Some((root, pattern)) = s.split_once(':') {
+        if pattern.contains(':') {
+            bail!("invalid workspace pattern: {}", s);
+        }
+        valid_workspace_name(root)?;
+        return Ok(format!("{}:{}", root, valid_workspace_pattern(pattern)?));
+    }
+
+    let invalid = s
+        .chars()
+        .any(|ch| !ch.is_alphanumeric() && !WORKSPACE_CHARS.contains(ch) && !"*?".contains(ch));
+    if invalid {
+        bail!("contains invalid characters: {}", s);
+    }
+
+    Ok(s.to_string())
+}
+
+/// Validates an export target: zip, pdf, azure or an sftp:// URL.
+pub fn valid_export_target(s: &str) -> Result<String> {
+    let s = s.trim();
+    match s {
+        "zip" | "pdf" | "azure" | "plaintext-mirror" => Ok(s.to_string()),
+        _ if s.starts_with("sftp://") => {
+            sftp::Target::parse(s)?;
+            Ok(s.to_string())
+        }
+        _ => bail!("expected zip, pdf, azure, plaintext-mirror or sftp://[user@]host[:port]/path"),
+    }
+}
+
+/// Validates a point in time given as a date or a duration ago, e.g. 2024-06-01 or 30d.
+pub fn valid_since(s: &str) -> Result<String> {
+    util::parse_since(s, util::Zone::Local, chrono::Local::now().fixed_offset())?;
+    Ok(s.trim().to_string())
+}
+
+pub fn valid_mood(s: &str) -> Result<String> {
+    match frontmatter::mood_score(s) {
+        Some(_) => Ok(s.trim().to_string()),
+        None => bail!("mood must be 1 to 5 or one of: 😢 🙁 😐 🙂 😄"),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::valid_workspace_name;
+    use super::{valid_journal_path, valid_workspace_name, valid_workspace_pattern};
 
     #[test]
     fn valid_workspace_names() {
-        let names = ["abc", "Abettername", "work", "work_space", "work-space"];
+        let names = [
+            "abc",
+            "Abettername",
+            "work",
+            "work_space",
+            "work-space",
+            "2024",
+            "q3-2024",
+            "arbeit-büro",
+            "日記",
+            "work:notes",
+        ];
         for name in names {
             let res = valid_workspace_name(name);
-            assert!(res.is_ok());
+            assert!(res.is_ok(), "{}", name);
         }
     }
 
+    #[test]
+    fn workspaces_of_roots() {
+        assert_eq!(valid_workspace_name("work:notes").unwrap(), "work:notes");
+        assert!(valid_workspace_name("work:").is_err());
+        assert!(valid_workspace_name("a:b:c").is_err());
+        assert_eq!(
+            valid_workspace_pattern("work:client-*").unwrap(),
+            "work:client-*"
+        );
+        assert!(valid_workspace_pattern("work:a:*").is_err());
+    }
+
     #[test]
     fn invalid_workspace_names() {
-        let names = ["", " ", "a", ".", "!#1238"];
+        let names = [
+            "",
+            " ",
+            "a",
+            ".",
+            "!#1238",
+            "..",
+            "work/space",
+            "work\\space",
+            "tab\tbed",
+            "new\nline",
+            "null\0",
+        ];
         for name in names {
             let res = valid_workspace_name(name);
-            assert!(res.is_err());
+            assert!(res.is_err(), "{:?}", name);
         }
     }
+
+    #[test]
+    fn journal_paths() {
+        assert!(valid_journal_path("notes.md").is_ok());
+        assert!(valid_journal_path("projects/alpha/notes.md").is_ok());
+        for path in [
+            "/notes.md",
+            "a//b.md",
+            "../notes.md",
+            "a/./b.md",
+            "a\\b.md",
+            "dir/",
+        ] {
+            assert!(valid_journal_path(path).is_err(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn workspace_patterns() {
+        assert!(valid_workspace_pattern("work").is_ok());
+        assert!(valid_workspace_pattern("client-*").is_ok());
+        assert!(valid_workspace_pattern("*").is_ok());
+        assert!(valid_workspace_pattern("q?-2024").is_ok());
+        assert!(valid_workspace_pattern("a").is_err());
+        assert!(valid_workspace_pattern("../*").is_err());
+    }
+
+    #[test]
+    fn workspace_name_length_counts_characters() {
+        assert!(valid_workspace_name("ää").is_ok());
+        assert!(valid_workspace_name(&"ü".repeat(25)).is_ok());
+        assert!(valid_workspace_name(&"ü".repeat(26)).is_err());
+    }
 }